@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Event, StdError, StdResult};
+
+pub const ESCROW_CREATED: &str = "escrow_created";
+pub const ESCROW_WITHDRAWN: &str = "escrow_withdrawn";
+pub const ESCROW_CANCELLED: &str = "escrow_cancelled";
+pub const FUNDS_RESCUED: &str = "funds_rescued";
+pub const ORDER_FILLED: &str = "order_filled";
+pub const SECRET_REVEALED: &str = "secret_revealed";
+
+/// wasmd prefixes custom contract event types with `wasm-`
+const WASM_EVENT_PREFIX: &str = "wasm-";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscrowEventKind {
+    Created,
+    Withdrawn,
+    Cancelled,
+    FundsRescued,
+    OrderFilled,
+    SecretRevealed,
+}
+
+impl EscrowEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscrowEventKind::Created => ESCROW_CREATED,
+            EscrowEventKind::Withdrawn => ESCROW_WITHDRAWN,
+            EscrowEventKind::Cancelled => ESCROW_CANCELLED,
+            EscrowEventKind::FundsRescued => FUNDS_RESCUED,
+            EscrowEventKind::OrderFilled => ORDER_FILLED,
+            EscrowEventKind::SecretRevealed => SECRET_REVEALED,
+        }
+    }
+
+    pub fn from_event_type(ty: &str) -> Option<Self> {
+        match ty.strip_prefix(WASM_EVENT_PREFIX).unwrap_or(ty) {
+            ESCROW_CREATED => Some(EscrowEventKind::Created),
+            ESCROW_WITHDRAWN => Some(EscrowEventKind::Withdrawn),
+            ESCROW_CANCELLED => Some(EscrowEventKind::Cancelled),
+            FUNDS_RESCUED => Some(EscrowEventKind::FundsRescued),
+            ORDER_FILLED => Some(EscrowEventKind::OrderFilled),
+            SECRET_REVEALED => Some(EscrowEventKind::SecretRevealed),
+            _ => None,
+        }
+    }
+}
+
+/// Lifecycle event of a swap. Every contract builds and parses it through this
+/// one type, so relayers can follow a swap across both legs without diffing balances.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowEvent {
+    pub kind: EscrowEventKind,
+    /// hex encoded
+    pub order_hash: String,
+    /// hex encoded
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub amount: Vec<Coin>,
+    /// timelock stage the escrow was in when the event happened
+    pub stage: String,
+    /// the revealed secret, only set on `escrow_withdrawn` and `secret_revealed`
+    pub secret: Option<String>,
+}
+
+impl From<EscrowEvent> for Event {
+    fn from(e: EscrowEvent) -> Event {
+        let amount = e
+            .amount
+            .iter()
+            .map(Coin::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let event = Event::new(e.kind.as_str())
+            .add_attribute("order_hash", e.order_hash)
+            .add_attribute("hashlock", e.hashlock)
+            .add_attribute("maker", e.maker)
+            .add_attribute("taker", e.taker)
+            .add_attribute("amount", amount)
+            .add_attribute("stage", e.stage);
+
+        match e.secret {
+            Some(secret) => event.add_attribute("secret", secret),
+            None => event,
+        }
+    }
+}
+
+impl TryFrom<&Event> for EscrowEvent {
+    type Error = StdError;
+
+    fn try_from(event: &Event) -> StdResult<Self> {
+        let kind = EscrowEventKind::from_event_type(&event.ty).ok_or_else(|| {
+            StdError::generic_err(format!("Not an escrow event: {}", event.ty))
+        })?;
+
+        let attr = |key: &str| -> Option<String> {
+            event
+                .attributes
+                .iter()
+                .find(|a| a.key == key)
+                .map(|a| a.value.clone())
+        };
+        let required = |key: &str| -> StdResult<String> {
+            attr(key).ok_or_else(|| {
+                StdError::generic_err(format!("Missing attribute `{key}` on {}", event.ty))
+            })
+        };
+
+        let amount = required("amount")?;
+        let amount = amount
+            .split(',')
+            .filter(|coin| !coin.is_empty())
+            .map(|coin| Coin::from_str(coin).map_err(|e| StdError::generic_err(e.to_string())))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(EscrowEvent {
+            kind,
+            order_hash: required("order_hash")?,
+            hashlock: required("hashlock")?,
+            maker: Addr::unchecked(required("maker")?),
+            taker: Addr::unchecked(required("taker")?),
+            amount,
+            stage: required("stage")?,
+            secret: attr("secret"),
+        })
+    }
+}
+
+/// Pick all escrow lifecycle events out of a transaction's events
+pub fn parse_escrow_events(events: &[Event]) -> StdResult<Vec<EscrowEvent>> {
+    events
+        .iter()
+        .filter(|e| EscrowEventKind::from_event_type(&e.ty).is_some())
+        .map(EscrowEvent::try_from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_round_trip() {
+        let event = EscrowEvent {
+            kind: EscrowEventKind::Withdrawn,
+            order_hash: "aa".to_string(),
+            hashlock: "bb".to_string(),
+            maker: Addr::unchecked("maker"),
+            taker: Addr::unchecked("taker"),
+            amount: vec![Coin::new(1000u32, "stake"), Coin::new(5u32, "uatom")],
+            stage: "private_withdrawal".to_string(),
+            secret: Some("secret".to_string()),
+        };
+
+        let mut emitted: Event = event.clone().into();
+        // as seen by a relayer reading tx results
+        emitted.ty = format!("wasm-{}", emitted.ty);
+        let other = Event::new("transfer").add_attribute("amount", "1stake");
+
+        let parsed = parse_escrow_events(&[other, emitted]).unwrap();
+        assert_eq!(parsed, vec![event]);
+    }
+}
@@ -0,0 +1,130 @@
+use cosmwasm_std::{Coin, Coins, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FundsError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Invalid token basket: {reason}")]
+    InvalidBasket { reason: String },
+
+    #[error("Denom {denom} is not part of the escrow's tokens")]
+    ExtraDenom { denom: String },
+
+    #[error("Insufficient {denom}: required {required}, received {received}")]
+    InsufficientFunds {
+        denom: String,
+        required: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Received {amount}{denom} more than required")]
+    ExcessFunds { denom: String, amount: Uint128 },
+}
+
+/// A basket holds at least one coin, with distinct denoms and non-zero amounts
+pub fn validate_basket(tokens: &[Coin]) -> Result<(), FundsError> {
+    let invalid = |reason: String| FundsError::InvalidBasket { reason };
+
+    if tokens.is_empty() {
+        return Err(invalid("no tokens".to_string()));
+    }
+    if let Some(coin) = tokens.iter().find(|coin| coin.amount.is_zero()) {
+        return Err(invalid(format!("zero amount of {}", coin.denom)));
+    }
+    Coins::try_from(tokens.to_vec()).map_err(|e| invalid(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Checks the sent funds against the escrow's basket and returns whatever was
+/// sent on top of it. Surplus is an error unless `refund_surplus` is set, in
+/// which case the caller sends it back.
+pub fn check_funds(
+    funds: &[Coin],
+    tokens: &[Coin],
+    refund_surplus: bool,
+) -> Result<Vec<Coin>, FundsError> {
+    let mut sent =
+        Coins::try_from(funds.to_vec()).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    for token in tokens {
+        let received = sent.amount_of(&token.denom);
+        if received < token.amount {
+            return Err(FundsError::InsufficientFunds {
+                denom: token.denom.clone(),
+                required: token.amount,
+                received,
+            });
+        }
+        sent.sub(token.clone())?;
+    }
+
+    let surplus = sent.into_vec();
+    if refund_surplus {
+        return Ok(surplus);
+    }
+    if let Some(extra) = surplus
+        .iter()
+        .find(|coin| !tokens.iter().any(|token| token.denom == coin.denom))
+    {
+        return Err(FundsError::ExtraDenom {
+            denom: extra.denom.clone(),
+        });
+    }
+    if let Some(excess) = surplus.first() {
+        return Err(FundsError::ExcessFunds {
+            denom: excess.denom.clone(),
+            amount: excess.amount,
+        });
+    }
+
+    Ok(surplus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn funds_must_match_the_basket() {
+        let tokens = [coin(100, "uatom"), coin(50, "uusdc")];
+        assert_eq!(check_funds(&tokens, &tokens, false), Ok(vec![]));
+
+        let short = [coin(100, "uatom"), coin(49, "uusdc")];
+        assert!(matches!(
+            check_funds(&short, &tokens, false),
+            Err(FundsError::InsufficientFunds { denom, .. }) if denom == "uusdc"
+        ));
+
+        let extra = [coin(100, "uatom"), coin(50, "uusdc"), coin(1, "stake")];
+        assert!(matches!(
+            check_funds(&extra, &tokens, false),
+            Err(FundsError::ExtraDenom { denom }) if denom == "stake"
+        ));
+        assert_eq!(check_funds(&extra, &tokens, true), Ok(vec![coin(1, "stake")]));
+
+        let excess = [coin(101, "uatom"), coin(50, "uusdc")];
+        assert!(matches!(
+            check_funds(&excess, &tokens, false),
+            Err(FundsError::ExcessFunds { denom, .. }) if denom == "uatom"
+        ));
+    }
+
+    #[test]
+    fn baskets_need_distinct_non_zero_coins() {
+        assert_eq!(validate_basket(&[coin(1, "uatom")]), Ok(()));
+        for basket in [
+            vec![],
+            vec![coin(0, "uatom")],
+            vec![coin(1, "uatom"), coin(2, "uatom")],
+        ] {
+            assert!(matches!(
+                validate_basket(&basket),
+                Err(FundsError::InvalidBasket { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+/// Hash function the hashlock was computed with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// EVM legs, as used by 1inch
+    #[default]
+    Keccak256,
+    /// Bitcoin and IBC style HTLCs
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Keccak256 => Keccak256::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SecretError {
+    #[error("Invalid Secret")]
+    InvalidSecret,
+}
+
+pub fn only_valid_secret(
+    secret: &[u8],
+    hashlock: &[u8],
+    algorithm: HashAlgorithm,
+) -> Result<(), SecretError> {
+    if algorithm.digest(secret) != hashlock {
+        return Err(SecretError::InvalidSecret);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_checked_with_the_hashlock_algorithm() {
+        let secret = b"secret";
+        let keccak = Keccak256::digest(secret).to_vec();
+        let sha = Sha256::digest(secret).to_vec();
+
+        assert_eq!(only_valid_secret(secret, &keccak, HashAlgorithm::Keccak256), Ok(()));
+        assert_eq!(only_valid_secret(secret, &sha, HashAlgorithm::Sha256), Ok(()));
+        assert_eq!(
+            only_valid_secret(secret, &keccak, HashAlgorithm::Sha256),
+            Err(SecretError::InvalidSecret)
+        );
+        assert_eq!(
+            only_valid_secret(b"other", &keccak, HashAlgorithm::Keccak256),
+            Err(SecretError::InvalidSecret)
+        );
+    }
+}
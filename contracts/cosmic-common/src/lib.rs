@@ -0,0 +1,6 @@
+pub mod events;
+pub mod funds;
+pub mod hashlock;
+pub mod ownership;
+pub mod stages;
+pub mod timelocks;
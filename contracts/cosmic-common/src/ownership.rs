@@ -0,0 +1,159 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, BlockInfo, StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+use thiserror::Error;
+
+#[cw_serde]
+pub struct Ownership {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    /// unix timestamp (in sec) after which the pending transfer can no longer be accepted
+    pub pending_expiry: Option<u64>,
+}
+
+pub const OWNERSHIP: Item<Ownership> = Item::new("ownership");
+
+#[cw_serde]
+pub enum OwnershipAction {
+    /// Propose a new owner, who has to accept before `expiry` (unix timestamp in sec)
+    TransferOwnership {
+        new_owner: String,
+        expiry: Option<u64>,
+    },
+    AcceptOwnership,
+    RenounceOwnership,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Caller is not the contract's current owner")]
+    NotOwner,
+
+    #[error("Contract ownership has been renounced")]
+    NoOwner,
+
+    #[error("Caller is not the contract's pending owner")]
+    NotPendingOwner,
+
+    #[error("There is no pending ownership transfer")]
+    TransferNotFound,
+
+    #[error("Pending ownership transfer has expired")]
+    TransferExpired,
+
+    #[error("Ownership transfer expiry is already in the past")]
+    InvalidExpiration,
+}
+
+/// Set the initial owner, defaulting to the instantiator when none is given.
+pub fn initialize_owner(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    owner: Option<&str>,
+    sender: &Addr,
+) -> StdResult<Ownership> {
+    let owner = match owner {
+        Some(owner) => api.addr_validate(owner)?,
+        None => sender.clone(),
+    };
+    let ownership = Ownership {
+        owner: Some(owner),
+        pending_owner: None,
+        pending_expiry: None,
+    };
+    OWNERSHIP.save(storage, &ownership)?;
+    Ok(ownership)
+}
+
+pub fn get_ownership(storage: &dyn Storage) -> StdResult<Ownership> {
+    OWNERSHIP.load(storage)
+}
+
+pub fn assert_owner(storage: &dyn Storage, sender: &Addr) -> Result<(), OwnershipError> {
+    let ownership = OWNERSHIP.load(storage)?;
+    match ownership.owner {
+        Some(owner) if owner == *sender => Ok(()),
+        Some(_) => Err(OwnershipError::NotOwner),
+        None => Err(OwnershipError::NoOwner),
+    }
+}
+
+/// Two-step ownership transfer: the owner proposes, the new owner accepts.
+pub fn update_ownership(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    sender: &Addr,
+    action: OwnershipAction,
+) -> Result<Ownership, OwnershipError> {
+    let ownership = match action {
+        OwnershipAction::TransferOwnership { new_owner, expiry } => {
+            transfer_ownership(storage, api, block, sender, &new_owner, expiry)?
+        }
+        OwnershipAction::AcceptOwnership => accept_ownership(storage, block, sender)?,
+        OwnershipAction::RenounceOwnership => renounce_ownership(storage, sender)?,
+    };
+    OWNERSHIP.save(storage, &ownership)?;
+    Ok(ownership)
+}
+
+fn transfer_ownership(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    sender: &Addr,
+    new_owner: &str,
+    expiry: Option<u64>,
+) -> Result<Ownership, OwnershipError> {
+    assert_owner(storage, sender)?;
+
+    if let Some(expiry) = expiry {
+        if expiry <= block.time.seconds() {
+            return Err(OwnershipError::InvalidExpiration);
+        }
+    }
+
+    let mut ownership = OWNERSHIP.load(storage)?;
+    ownership.pending_owner = Some(api.addr_validate(new_owner)?);
+    ownership.pending_expiry = expiry;
+    Ok(ownership)
+}
+
+fn accept_ownership(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+) -> Result<Ownership, OwnershipError> {
+    let ownership = OWNERSHIP.load(storage)?;
+
+    match ownership.pending_owner {
+        Some(pending_owner) if pending_owner == *sender => {}
+        Some(_) => return Err(OwnershipError::NotPendingOwner),
+        None => return Err(OwnershipError::TransferNotFound),
+    }
+
+    if let Some(expiry) = ownership.pending_expiry {
+        if expiry <= block.time.seconds() {
+            return Err(OwnershipError::TransferExpired);
+        }
+    }
+
+    Ok(Ownership {
+        owner: Some(sender.clone()),
+        pending_owner: None,
+        pending_expiry: None,
+    })
+}
+
+fn renounce_ownership(storage: &dyn Storage, sender: &Addr) -> Result<Ownership, OwnershipError> {
+    assert_owner(storage, sender)?;
+
+    Ok(Ownership {
+        owner: None,
+        pending_owner: None,
+        pending_expiry: None,
+    })
+}
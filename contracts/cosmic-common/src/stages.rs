@@ -0,0 +1,272 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+use thiserror::Error;
+
+use crate::timelocks::{Side, Stage, Timelocks};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StageError {
+    #[error("Escrow has not been funded")]
+    NotFunded,
+
+    #[error("Escrow is already {status}")]
+    AlreadySettled { status: String },
+
+    #[error("{action} is not supported by {side} escrows")]
+    NotSupported { action: String, side: String },
+
+    #[error("Only Taker can call")]
+    OnlyTaker,
+
+    #[error("Rescue delay has not passed")]
+    RescueTimeLimit,
+
+    #[error("{action} is not allowed in the {stage} stage, it needs a withdrawal stage")]
+    NotInWithdrawalStage { action: String, stage: String },
+
+    #[error("{action} is not allowed in the {stage} stage, it needs a cancellation stage")]
+    NotInCancellationStage { action: String, stage: String },
+}
+
+/// Execute messages gated by the timelocks or the rescue delay, `WithdrawTo`
+/// counts as `Withdraw`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Withdraw,
+    PublicWithdraw,
+    Cancel,
+    PublicCancel,
+    RescueFunds,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [
+        Action::Withdraw,
+        Action::PublicWithdraw,
+        Action::Cancel,
+        Action::PublicCancel,
+        Action::RescueFunds,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Withdraw => "withdraw",
+            Action::PublicWithdraw => "public_withdraw",
+            Action::Cancel => "cancel",
+            Action::PublicCancel => "public_cancel",
+            Action::RescueFunds => "rescue_funds",
+        }
+    }
+
+    pub fn taker_only(&self) -> bool {
+        matches!(self, Action::Withdraw | Action::Cancel | Action::RescueFunds)
+    }
+
+    /// Stages the action is open in on `side`, none if the side doesn't
+    /// support it. Rescue only depends on the rescue delay.
+    pub fn stages(&self, side: Side) -> &'static [Stage] {
+        match (side, self) {
+            (_, Action::Withdraw) => &[Stage::PrivateWithdrawal, Stage::PublicWithdrawal],
+            (_, Action::PublicWithdraw) => &[Stage::PublicWithdrawal],
+            (Side::Src, Action::Cancel) => &[Stage::PrivateCancellation, Stage::PublicCancellation],
+            (Side::Src, Action::PublicCancel) => &[Stage::PublicCancellation],
+            (Side::Src, Action::RescueFunds) => &[],
+            (Side::Dst, Action::Cancel) => &[Stage::Cancellation],
+            (Side::Dst, Action::PublicCancel) => &[],
+            (Side::Dst, Action::RescueFunds) => &[
+                Stage::FinalityLock,
+                Stage::PrivateWithdrawal,
+                Stage::PublicWithdrawal,
+                Stage::Cancellation,
+            ],
+        }
+    }
+
+    /// Actions escrows on `side` support, in `ALL` order
+    pub fn supported(side: Side) -> impl Iterator<Item = Action> {
+        Action::ALL
+            .into_iter()
+            .filter(move |action| !action.stages(side).is_empty())
+    }
+
+    pub fn is_withdrawal(&self) -> bool {
+        matches!(self, Action::Withdraw | Action::PublicWithdraw)
+    }
+}
+
+/// Lifecycle of an escrow
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowStatus {
+    /// waiting for the maker's tokens, source escrows only
+    Unfunded,
+    Active,
+    Withdrawn,
+    Cancelled,
+    /// destination escrows only
+    Rescued,
+}
+
+impl EscrowStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscrowStatus::Unfunded => "unfunded",
+            EscrowStatus::Active => "active",
+            EscrowStatus::Withdrawn => "withdrawn",
+            EscrowStatus::Cancelled => "cancelled",
+            EscrowStatus::Rescued => "rescued",
+        }
+    }
+
+    /// Whether the escrow paid out, nothing runs on it anymore
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self,
+            EscrowStatus::Withdrawn | EscrowStatus::Cancelled | EscrowStatus::Rescued
+        )
+    }
+}
+
+/// What the stage rules look at, wherever the escrow keeps it
+pub struct EscrowTerms<'a> {
+    pub side: Side,
+    pub status: EscrowStatus,
+    pub taker: &'a Addr,
+    /// factory forwarding the taker's batched withdrawals and cancellations,
+    /// destination escrows only
+    pub factory: Option<&'a Addr>,
+    pub timelocks: &'a Timelocks,
+    pub deployed_at: u64,
+    pub rescue_delay: u64,
+}
+
+/// Decides whether `caller` may run `action` at `now`, returning the current
+/// stage. Nothing runs before the escrow is funded or once it paid out.
+pub fn authorize(
+    terms: &EscrowTerms,
+    caller: &Addr,
+    action: Action,
+    now: u64,
+) -> Result<Stage, StageError> {
+    if terms.status.is_settled() {
+        return Err(StageError::AlreadySettled {
+            status: terms.status.as_str().to_string(),
+        });
+    }
+    if terms.status == EscrowStatus::Unfunded {
+        return Err(StageError::NotFunded);
+    }
+    let stages = action.stages(terms.side);
+    if stages.is_empty() {
+        return Err(StageError::NotSupported {
+            action: action.as_str().to_string(),
+            side: terms.side.as_str().to_string(),
+        });
+    }
+    // the payout goes to the same recipient whether the factory batches it or not
+    let factory_batch = terms.side == Side::Dst
+        && matches!(action, Action::Withdraw | Action::Cancel)
+        && terms.factory == Some(caller);
+    if action.taker_only() && caller != terms.taker && !factory_batch {
+        return Err(StageError::OnlyTaker);
+    }
+    if action == Action::RescueFunds && now < terms.deployed_at + terms.rescue_delay {
+        return Err(StageError::RescueTimeLimit);
+    }
+
+    let stage = terms.timelocks.stage(terms.side, terms.deployed_at, now);
+    if !stages.contains(&stage) {
+        let withdrawal = action.is_withdrawal();
+        let (action, stage) = (action.as_str().to_string(), stage.as_str().to_string());
+        return Err(if withdrawal {
+            StageError::NotInWithdrawalStage { action, stage }
+        } else {
+            StageError::NotInCancellationStage { action, stage }
+        });
+    }
+
+    Ok(stage)
+}
+
+/// Actions `caller` could run at `now`, in `Action::ALL` order
+pub fn allowed_actions(terms: &EscrowTerms, caller: &Addr, now: u64) -> Vec<Action> {
+    Action::ALL
+        .into_iter()
+        .filter(|action| authorize(terms, caller, *action, now).is_ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+
+    const DEPLOYED_AT: u64 = 1_000;
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn timelocks() -> Timelocks {
+        Timelocks {
+            dest_withdrawal: 10,
+            dest_public_withdrawal: 100,
+            dest_cancellation: 200,
+            src_withdrawal: 10,
+            src_public_withdrawal: 100,
+            src_cancellation: 300,
+            src_public_cancellation: 400,
+        }
+    }
+
+    #[test]
+    fn status_gates_every_action() {
+        let (taker, timelocks) = (addr("taker"), timelocks());
+        let terms = |side, status| EscrowTerms {
+            side,
+            status,
+            taker: &taker,
+            factory: None,
+            timelocks: &timelocks,
+            deployed_at: DEPLOYED_AT,
+            rescue_delay: 0,
+        };
+        let now = DEPLOYED_AT + 5_000;
+
+        let unfunded = terms(Side::Src, EscrowStatus::Unfunded);
+        assert_eq!(
+            authorize(&unfunded, &taker, Action::Cancel, now),
+            Err(StageError::NotFunded)
+        );
+        for status in [EscrowStatus::Withdrawn, EscrowStatus::Cancelled, EscrowStatus::Rescued] {
+            for side in [Side::Src, Side::Dst] {
+                assert_eq!(allowed_actions(&terms(side, status), &taker, now), vec![]);
+            }
+        }
+
+        assert_eq!(
+            allowed_actions(&terms(Side::Src, EscrowStatus::Active), &taker, now),
+            vec![Action::Cancel, Action::PublicCancel]
+        );
+        assert_eq!(
+            allowed_actions(&terms(Side::Dst, EscrowStatus::Active), &taker, now),
+            vec![Action::Cancel, Action::RescueFunds]
+        );
+    }
+
+    #[test]
+    fn sides_support_their_escrow_actions() {
+        let supported = |side| Action::supported(side).collect::<Vec<_>>();
+        assert_eq!(
+            supported(Side::Src),
+            vec![Action::Withdraw, Action::PublicWithdraw, Action::Cancel, Action::PublicCancel]
+        );
+        assert_eq!(
+            supported(Side::Dst),
+            vec![Action::Withdraw, Action::PublicWithdraw, Action::Cancel, Action::RescueFunds]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Which leg of the swap an escrow holds, it decides the timelocks and payouts
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    /// maker's tokens, withdrawn to the taker like `escrow_src`
+    Src,
+    /// taker's tokens, withdrawn to the maker like `escrow_dst`
+    Dst,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Src => "src",
+            Side::Dst => "dst",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Timelocks {
+    pub dest_withdrawal: u64,
+    pub dest_public_withdrawal: u64,
+    pub dest_cancellation: u64,
+    pub src_cancellation: u64,
+    pub src_withdrawal: u64,
+    pub src_public_withdrawal: u64,
+    pub src_public_cancellation: u64,
+}
+
+/// Timelock stage of an escrow, offsets are counted from `deployed_at`.
+/// Source escrows split cancellation into a private and a public stage,
+/// destination escrows only have `Cancellation`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    FinalityLock,
+    PrivateWithdrawal,
+    PublicWithdrawal,
+    Cancellation,
+    PrivateCancellation,
+    PublicCancellation,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::FinalityLock => "finality_lock",
+            Stage::PrivateWithdrawal => "private_withdrawal",
+            Stage::PublicWithdrawal => "public_withdrawal",
+            Stage::Cancellation => "cancellation",
+            Stage::PrivateCancellation => "private_cancellation",
+            Stage::PublicCancellation => "public_cancellation",
+        }
+    }
+}
+
+impl Timelocks {
+    pub fn stage(&self, side: Side, deployed_at: u64, now: u64) -> Stage {
+        let elapsed = now.saturating_sub(deployed_at);
+        match side {
+            Side::Src if elapsed < self.src_withdrawal => Stage::FinalityLock,
+            Side::Src if elapsed < self.src_public_withdrawal => Stage::PrivateWithdrawal,
+            Side::Src if elapsed < self.src_cancellation => Stage::PublicWithdrawal,
+            Side::Src if elapsed < self.src_public_cancellation => Stage::PrivateCancellation,
+            Side::Src => Stage::PublicCancellation,
+            Side::Dst if elapsed < self.dest_withdrawal => Stage::FinalityLock,
+            Side::Dst if elapsed < self.dest_public_withdrawal => Stage::PrivateWithdrawal,
+            Side::Dst if elapsed < self.dest_cancellation => Stage::PublicWithdrawal,
+            Side::Dst => Stage::Cancellation,
+        }
+    }
+
+    /// Offsets from `deployed_at` that `stage` starts and ends at, the last
+    /// stage never ends
+    pub fn stage_bounds(&self, side: Side, stage: Stage) -> (u64, Option<u64>) {
+        match (side, stage) {
+            (Side::Src, Stage::FinalityLock) => (0, Some(self.src_withdrawal)),
+            (Side::Src, Stage::PrivateWithdrawal) => {
+                (self.src_withdrawal, Some(self.src_public_withdrawal))
+            }
+            (Side::Src, Stage::PublicWithdrawal) => {
+                (self.src_public_withdrawal, Some(self.src_cancellation))
+            }
+            (Side::Src, Stage::PrivateCancellation | Stage::Cancellation) => {
+                (self.src_cancellation, Some(self.src_public_cancellation))
+            }
+            (Side::Src, Stage::PublicCancellation) => (self.src_public_cancellation, None),
+            (Side::Dst, Stage::FinalityLock) => (0, Some(self.dest_withdrawal)),
+            (Side::Dst, Stage::PrivateWithdrawal) => {
+                (self.dest_withdrawal, Some(self.dest_public_withdrawal))
+            }
+            (Side::Dst, Stage::PublicWithdrawal) => {
+                (self.dest_public_withdrawal, Some(self.dest_cancellation))
+            }
+            (Side::Dst, _) => (self.dest_cancellation, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timelocks() -> Timelocks {
+        Timelocks {
+            dest_withdrawal: 10,
+            dest_public_withdrawal: 100,
+            dest_cancellation: 200,
+            src_withdrawal: 10,
+            src_public_withdrawal: 100,
+            src_cancellation: 300,
+            src_public_cancellation: 400,
+        }
+    }
+
+    #[test]
+    fn every_stage_starts_where_its_bounds_say() {
+        let timelocks = timelocks();
+        let deployed_at = 1_000;
+        for side in [Side::Src, Side::Dst] {
+            let mut offset = 0;
+            loop {
+                let stage = timelocks.stage(side, deployed_at, deployed_at + offset);
+                let (start, end) = timelocks.stage_bounds(side, stage);
+                assert_eq!(start, offset, "{} {}", side.as_str(), stage.as_str());
+                match end {
+                    Some(end) => {
+                        assert_eq!(timelocks.stage(side, deployed_at, deployed_at + end - 1), stage);
+                        offset = end;
+                    }
+                    None => break,
+                }
+            }
+        }
+        assert_eq!(timelocks.stage(Side::Src, 1_000, 1_400), Stage::PublicCancellation);
+        assert_eq!(timelocks.stage(Side::Dst, 1_000, 1_400), Stage::Cancellation);
+    }
+}
@@ -1,32 +1,65 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, ReplyOn, SubMsg, WasmMsg,
-    Binary, Deps, DepsMut, Env, Event, MessageInfo, Reply, Response, StdResult,
+use cosmwasm_std::{from_json, to_json_binary, Addr, BankMsg, Empty, ReplyOn, SubMsg, SubMsgResponse, WasmMsg,
+    Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult,
 };
+use cosmic_common::events::{EscrowEvent, EscrowEventKind};
+use cw_storage_plus::Bound;
+use cw2::set_contract_version;
 use cw_utils::parse_instantiate_response_data;
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{EscrowInstantiateMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, COMPLETED_ORDERS, STATE};
+use crate::helpers::check_funds;
+use crate::migrations::{ensure_from_older_version, index_escrows, migrate_completed_orders};
+use crate::msg::{
+    ConfigResponse, EscrowInstantiateMsg, ExecuteMsg, InstantiateMsg, IsHashlockUsedResponse,
+    MigrateMsg, QueryMsg, RevealedSecretResponse, RevealedSecretsResponse, SudoMsg,
+};
+use cosmic_common::ownership::{assert_owner, get_ownership, initialize_owner};
+use crate::state::{
+    OrderRecord, State, ESCROW_ORDERS, EXPIRIES, ORDERS, OWNERSHIP, PAUSED, REVEALED_SECRETS,
+    STATE, USED_HASHLOCKS,
+};
+
+/// `stage` attribute of the factory's `escrow_created`, the destination
+/// timelocks start with the deployment
+pub const ESCROW_CREATED_STAGE: &str = "finality_lock";
 
 pub const ESCROW_DEPLOY_REPLY: u64 = 1;
-/*
+/// Reply to each escrow call of a `BatchWithdraw` or `BatchCancel`
+pub const BATCH_ITEM_REPLY: u64 = 2;
+
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:escrow-factory";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-*/
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Expired escrows cancelled per sudo call, keeps the call's gas bounded
+const DEFAULT_SWEEP_LIMIT: u32 = 10;
+const MAX_SWEEP_LIMIT: u32 = 50;
+/// Seconds before a failed sweep cancellation is retried, it goes behind the
+/// escrows expiring meanwhile so it can't hold them up
+const SWEEP_RETRY_DELAY: u64 = 3600;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let state = State {
         escrow_code_id: msg.escrow_code_id,
+        migratable_escrows: false,
+        escrow_vault: None,
     };
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     STATE.save(deps.storage, &state)?;
+    PAUSED.save(deps.storage, &false)?;
+    initialize_owner(deps.storage, deps.api, msg.owner.as_deref(), &info.sender)?;
 
     Ok(Response::new())
 }
@@ -40,40 +73,65 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::DeployEscrow(msg) => execute::deploy_dest_escrow(deps, env, info, msg),
+        ExecuteMsg::UpdateOwnership(action) => execute::update_ownership(deps, env, info, action),
+        ExecuteMsg::UpdateConfig {
+            escrow_code_id,
+            migratable_escrows,
+        } => execute::update_config(deps, info, escrow_code_id, migratable_escrows),
+        ExecuteMsg::SetEscrowVault { vault } => execute::set_escrow_vault(deps, info, vault),
+        ExecuteMsg::Pause {} => execute::set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => execute::set_paused(deps, info, false),
+        ExecuteMsg::MigrateEscrow {
+            escrow,
+            code_id,
+            msg,
+        } => execute::migrate_escrow(deps, info, escrow, code_id, msg),
+        ExecuteMsg::RecordSecret { order_hash, secret } => {
+            execute::record_secret(deps, info, order_hash, secret)
+        }
+        ExecuteMsg::BatchWithdraw { escrows } => execute::batch_withdraw(deps, info, escrows),
+        ExecuteMsg::BatchCancel { escrows } => execute::batch_cancel(deps, info, escrows),
     }
 }
 
 pub mod execute {
     use super::*;
+    use crate::msg::{
+        EscrowExecuteMsg, EscrowQueryMsg, EscrowStatusResponse, EscrowWithdrawMsg, OwnershipAction,
+        VaultCreateMsg, VaultExecuteMsg, VaultQueryMsg, VaultSide,
+    };
 
     pub fn deploy_dest_escrow(
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info: MessageInfo,
         msg: EscrowInstantiateMsg,
     ) -> Result<Response, ContractError> {
+        if PAUSED.load(deps.storage)? {
+            return Err(ContractError::Paused);
+        }
+
         //check if order already proccessed
         let state = STATE.load(deps.storage)?;
-        match  COMPLETED_ORDERS.may_load(deps.storage, msg.order_hash.clone()).unwrap() {
-            Some(val) => {
-                if val == true {
-                    return Err(ContractError::OrderAlreadyProcessed);
-                }
-            }
-            None => {}
+        if ORDERS.has(deps.storage, msg.order_hash.clone()) {
+            return Err(ContractError::OrderAlreadyProcessed);
         }
 
-        //check if send funds match the order details
-        let mut ok = false;
-        for asset in info.funds {
-            if asset.denom == msg.token.denom && asset.amount == msg.token.amount {
-                ok = true;
-            }
-        }
-        if !ok {
-            return Err(ContractError::UnmatchedDenomOrAmount);
+        // hex hashlocks are compared case-insensitively, like the escrows decode them
+        let hashlock = msg.hashlock.to_ascii_lowercase();
+        if USED_HASHLOCKS.has(deps.storage, hashlock.clone()) {
+            return Err(ContractError::HashlockAlreadyUsed {
+                hashlock: msg.hashlock,
+            });
         }
 
+        //check if send funds match the order details
+        let surplus = check_funds(&info.funds, &msg.tokens, msg.refund_surplus)?;
+        USED_HASHLOCKS.save(deps.storage, hashlock, &Empty {})?;
+
+        // swept once the destination cancellation stage opens, in both modes
+        let cancel_at = env.block.time.seconds() + msg.timelocks.dest_cancellation;
+
         //deploy the contract with funds
         let escrow_init_playload_msg = EscrowInstantiateMsg {
             hashlock: msg.hashlock,
@@ -82,57 +140,1662 @@ pub mod execute {
             order_hash: msg.order_hash.clone(),
             rescue_delay: msg.rescue_delay,
             timelocks: msg.timelocks,
-            token: msg.token,
+            tokens: msg.tokens,
+            hash_algorithm: msg.hash_algorithm,
+            factory: Some(env.contract.address.clone()),
+            // the factory forwards exactly `tokens` and refunds the rest itself
+            refund_surplus: false,
+            receiver: msg.receiver,
         };
 
-        let instantiate_child_msg = WasmMsg::Instantiate {
-            admin: None,
-            code_id: state.escrow_code_id,
-            msg: to_json_binary(&escrow_init_playload_msg)?,
-            funds: vec![escrow_init_playload_msg.token],
-            label: format!(
-                "Escrow Contract for {}",
-                escrow_init_playload_msg.order_hash.as_str()
-            ),
+        let mut order = OrderRecord {
+            maker: Some(msg.maker),
+            taker: Some(escrow_init_playload_msg.taker.clone()),
+            created_at: Some(env.block.time.seconds()),
+            escrow: None,
         };
-        let submsg = SubMsg {
-            payload: Binary::new(vec![]),
-            msg: instantiate_child_msg.into(),
-            gas_limit: None,
-            id: ESCROW_DEPLOY_REPLY, // assign an ID to catch the reply
-            reply_on: ReplyOn::Always,
+
+        // the vault holds the escrow as soon as `Create` succeeds, there is no address to wait for
+        let mut res = match state.escrow_vault {
+            Some(vault) => {
+                order.escrow = Some(vault.clone());
+                let event = escrow_created_event(&escrow_init_playload_msg, &vault);
+                let create_msg = WasmMsg::Execute {
+                    contract_addr: vault.to_string(),
+                    funds: escrow_init_playload_msg.tokens.clone(),
+                    msg: to_json_binary(&vault_create_msg(escrow_init_playload_msg))?,
+                };
+                Response::new().add_message(create_msg).add_event(event)
+            }
+            None => {
+                let instantiate_child_msg = WasmMsg::Instantiate {
+                    admin: state
+                        .migratable_escrows
+                        .then(|| env.contract.address.to_string()),
+                    code_id: state.escrow_code_id,
+                    msg: to_json_binary(&escrow_init_playload_msg)?,
+                    funds: escrow_init_playload_msg.tokens.clone(),
+                    label: format!(
+                        "Escrow Contract for {}",
+                        escrow_init_playload_msg.order_hash.as_str()
+                    ),
+                };
+                // the reply reports the deployment once the escrow's address is known
+                let submsg = SubMsg {
+                    payload: to_json_binary(&escrow_init_playload_msg)?,
+                    msg: instantiate_child_msg.into(),
+                    gas_limit: None,
+                    id: ESCROW_DEPLOY_REPLY, // assign an ID to catch the reply
+                    reply_on: ReplyOn::Always,
+                };
+                Response::new().add_submessage(submsg)
+            }
         };
-        let event = Event::new("escrow_contract").add_attribute("order_hash", escrow_init_playload_msg.order_hash);
+        EXPIRIES.save(deps.storage, (cancel_at, msg.order_hash.clone()), &Empty {})?;
+        ORDERS.save(deps.storage, msg.order_hash, &order)?;
+
+        if !surplus.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: info.sender.into(),
+                amount: surplus,
+            });
+        }
+        Ok(res)
+    }
+
+    /// The vault holds the escrow under the same order hash and rules as a spawned
+    /// `escrow_dst`, and reports the revealed secret back to the factory
+    fn vault_create_msg(escrow: EscrowInstantiateMsg) -> VaultExecuteMsg {
+        VaultExecuteMsg::Create(VaultCreateMsg {
+            side: VaultSide::Dst,
+            rescue_delay: escrow.rescue_delay,
+            order_hash: escrow.order_hash,
+            hashlock: escrow.hashlock,
+            maker: escrow.maker,
+            taker: escrow.taker,
+            tokens: escrow.tokens,
+            timelocks: escrow.timelocks,
+            hash_algorithm: escrow.hash_algorithm,
+            factory: escrow.factory,
+            receiver: escrow.receiver,
+        })
+    }
+
+    pub fn update_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: OwnershipAction,
+    ) -> Result<Response, ContractError> {
+        let ownership = cosmic_common::ownership::update_ownership(
+            deps.storage,
+            deps.api,
+            &env.block,
+            &info.sender,
+            action,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_ownership")
+            .add_attribute("owner", display_addr(ownership.owner))
+            .add_attribute("pending_owner", display_addr(ownership.pending_owner)))
+    }
 
-        COMPLETED_ORDERS.save(deps.storage, msg.order_hash.clone(), &true)?;
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrow_code_id: Option<u64>,
+        migratable_escrows: Option<bool>,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
 
-        Ok(Response::new().add_submessage(submsg).add_event(event))
+        let mut state = STATE.load(deps.storage)?;
+        if let Some(escrow_code_id) = escrow_code_id {
+            state.escrow_code_id = escrow_code_id;
+        }
+        if let Some(migratable_escrows) = migratable_escrows {
+            state.migratable_escrows = migratable_escrows;
+        }
+        STATE.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("escrow_code_id", state.escrow_code_id.to_string())
+            .add_attribute("migratable_escrows", state.migratable_escrows.to_string()))
+    }
+
+    /// Only escrows spawned with the factory as admin can be migrated, the
+    /// chain rejects the message for everything else.
+    pub fn migrate_escrow(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrow: String,
+        code_id: u64,
+        msg: Binary,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+        let escrow = deps.api.addr_validate(&escrow)?;
+
+        let migrate_msg = WasmMsg::Migrate {
+            contract_addr: escrow.to_string(),
+            new_code_id: code_id,
+            msg,
+        };
+
+        Ok(Response::new()
+            .add_message(migrate_msg)
+            .add_attribute("action", "migrate_escrow")
+            .add_attribute("escrow", escrow)
+            .add_attribute("code_id", code_id.to_string()))
+    }
+
+    /// Escrows already spawned or held by a previous vault are unaffected
+    pub fn set_escrow_vault(
+        deps: DepsMut,
+        info: MessageInfo,
+        vault: Option<String>,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        state.escrow_vault = vault.map(|vault| deps.api.addr_validate(&vault)).transpose()?;
+        STATE.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_escrow_vault")
+            .add_attribute("escrow_vault", display_addr(state.escrow_vault)))
+    }
+
+    /// Pausing only stops new escrows from being deployed; escrows that already
+    /// exist are separate contracts and can always be withdrawn or cancelled.
+    pub fn set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+        PAUSED.save(deps.storage, &paused)?;
+
+        Ok(Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" }))
+    }
+
+    /// Only the escrow deployed for `order_hash` can record its secret. The escrow
+    /// already checked it against the hashlock before releasing the funds.
+    pub fn record_secret(
+        deps: DepsMut,
+        info: MessageInfo,
+        order_hash: String,
+        secret: Binary,
+    ) -> Result<Response, ContractError> {
+        let order = ORDERS.may_load(deps.storage, order_hash.clone())?;
+        if order.and_then(|order| order.escrow) != Some(info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if REVEALED_SECRETS.has(deps.storage, order_hash.clone()) {
+            return Err(ContractError::SecretAlreadyRecorded { order_hash });
+        }
+        REVEALED_SECRETS.save(deps.storage, order_hash.clone(), &secret)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "record_secret")
+            .add_attribute("order_hash", order_hash))
+    }
+
+    pub fn batch_withdraw(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrows: Vec<(String, Binary)>,
+    ) -> Result<Response, ContractError> {
+        if escrows.is_empty() {
+            return Err(ContractError::EmptyBatch);
+        }
+
+        let mut res = Response::new().add_attribute("action", "batch_withdraw");
+        for (index, (escrow, secret)) in escrows.into_iter().enumerate() {
+            let item = registered_order(deps.as_ref(), &escrow).map(|(holder, order)| {
+                let withdraw = EscrowWithdrawMsg { secret };
+                let msg = if order.taker == Some(info.sender.clone()) {
+                    EscrowExecuteMsg::Withdraw(withdraw)
+                } else {
+                    EscrowExecuteMsg::PublicWithdraw(withdraw)
+                };
+                (holder, msg)
+            });
+            res = add_batch_item(res, index, &escrow, item, None)?;
+        }
+        Ok(res)
+    }
+
+    pub fn batch_cancel(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrows: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        if escrows.is_empty() {
+            return Err(ContractError::EmptyBatch);
+        }
+
+        let mut res = Response::new().add_attribute("action", "batch_cancel");
+        for (index, escrow) in escrows.into_iter().enumerate() {
+            let item = registered_order(deps.as_ref(), &escrow).and_then(|(holder, order)| {
+                if order.taker != Some(info.sender.clone()) {
+                    return Err("only the order's taker can cancel".to_string());
+                }
+                Ok((holder, EscrowExecuteMsg::Cancel))
+            });
+            res = add_batch_item(res, index, &escrow, item, None)?;
+        }
+        Ok(res)
+    }
+
+    /// Cancels escrows whose cancellation stage opened, oldest first. Escrows
+    /// that revealed their secret are only dropped from the index, the others
+    /// are cancelled and report their outcome in `batch_result`, a failed
+    /// cancellation is put back in the index to be retried, unless the escrow
+    /// was settled outside the sweep meanwhile.
+    pub fn cancel_expired(
+        deps: DepsMut,
+        env: Env,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let now = env.block.time.seconds();
+        let limit = limit.unwrap_or(DEFAULT_SWEEP_LIMIT).min(MAX_SWEEP_LIMIT) as usize;
+        let expired = EXPIRIES
+            .keys(deps.storage, None, None, Order::Ascending)
+            .take_while(|key| key.as_ref().map_or(true, |(cancel_at, _)| *cancel_at <= now))
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut res = Response::new().add_attribute("action", "cancel_expired");
+        let mut settled = 0;
+        for (index, (cancel_at, order_hash)) in expired.into_iter().enumerate() {
+            EXPIRIES.remove(deps.storage, (cancel_at, order_hash.clone()));
+            let escrow = ORDERS
+                .may_load(deps.storage, order_hash.clone())?
+                .and_then(|order| order.escrow);
+            match escrow {
+                Some(escrow) if !REVEALED_SECRETS.has(deps.storage, order_hash.clone()) => {
+                    let holder = holder(deps.as_ref(), order_hash.clone(), escrow);
+                    // named the way `BatchCancel` takes it
+                    let name = match &holder {
+                        Holder::Escrow(escrow) => escrow.to_string(),
+                        Holder::Vault { order_hash, .. } => order_hash.clone(),
+                    };
+                    let item = Ok((holder, EscrowExecuteMsg::Cancel));
+                    res = add_batch_item(res, index, &name, item, Some(order_hash))?;
+                }
+                _ => settled += 1,
+            }
+        }
+
+        Ok(res.add_attribute("settled", settled.to_string()))
+    }
+
+    /// Contract holding an order the factory deployed
+    enum Holder {
+        /// `escrow_dst` spawned for the order alone
+        Escrow(Addr),
+        /// vault holding the order among others, calls name the order
+        Vault { vault: Addr, order_hash: String },
+    }
+
+    /// The contract holding the order `item` names and the order's record.
+    /// `item` is a spawned escrow's address or an order hash, the vault holds
+    /// many orders so they can only be named by hash.
+    fn registered_order(deps: Deps, item: &str) -> Result<(Holder, OrderRecord), String> {
+        let order_hash = if ORDERS.has(deps.storage, item.to_string()) {
+            item.to_string()
+        } else {
+            let escrow = deps.api.addr_validate(item).map_err(|e| e.to_string())?;
+            ESCROW_ORDERS
+                .may_load(deps.storage, &escrow)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "escrow was not deployed by the factory".to_string())?
+        };
+        let order = ORDERS
+            .may_load(deps.storage, order_hash.clone())
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "escrow was not deployed by the factory".to_string())?;
+        let escrow = order
+            .escrow
+            .clone()
+            .ok_or_else(|| "order has no known escrow".to_string())?;
+        Ok((holder(deps, order_hash, escrow), order))
+    }
+
+    /// How calls reach the order held by `escrow`, only spawned escrows are
+    /// indexed by address
+    fn holder(deps: Deps, order_hash: String, escrow: Addr) -> Holder {
+        if ESCROW_ORDERS.has(deps.storage, &escrow) {
+            Holder::Escrow(escrow)
+        } else {
+            Holder::Vault {
+                vault: escrow,
+                order_hash,
+            }
+        }
+    }
+
+    /// Items that can't be dispatched are reported right away, the others once
+    /// the escrow replied. A failing escrow call only reverts itself.
+    /// `swept` is the order hash of a sweep cancellation, requeued if it fails
+    /// on an escrow that isn't settled.
+    fn add_batch_item(
+        res: Response,
+        index: usize,
+        escrow: &str,
+        item: Result<(Holder, EscrowExecuteMsg), String>,
+        swept: Option<String>,
+    ) -> StdResult<Response> {
+        let (holder, msg) = match item {
+            Ok(item) => item,
+            Err(reason) => {
+                return Ok(res.add_event(batch_result_event(index, escrow, Err(reason))));
+            }
+        };
+        let (contract, msg) = match holder {
+            Holder::Escrow(escrow) => (escrow, to_json_binary(&msg)?),
+            Holder::Vault { vault, order_hash } => {
+                (vault, to_json_binary(&vault_msg(order_hash, msg))?)
+            }
+        };
+        let call = WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg,
+            funds: vec![],
+        };
+        // reported under the name the caller used, the vault is shared by many orders
+        let payload = to_json_binary(&(index, escrow, swept))?;
+        Ok(res.add_submessage(SubMsg::reply_always(call, BATCH_ITEM_REPLY).with_payload(payload)))
+    }
+
+    /// Whether the escrow of `order_hash` already paid out, through the sweep
+    /// or outside of it. Calls on it can only fail from then on.
+    pub(super) fn is_settled(deps: Deps, order_hash: &str) -> StdResult<bool> {
+        let escrow = ORDERS
+            .may_load(deps.storage, order_hash.to_string())?
+            .and_then(|order| order.escrow);
+        let Some(escrow) = escrow else {
+            return Ok(false);
+        };
+        let response: StdResult<EscrowStatusResponse> =
+            match holder(deps, order_hash.to_string(), escrow) {
+                Holder::Escrow(escrow) => deps
+                    .querier
+                    .query_wasm_smart(escrow, &EscrowQueryMsg::Status {}),
+                Holder::Vault { vault, order_hash } => deps
+                    .querier
+                    .query_wasm_smart(vault, &VaultQueryMsg::Escrow { order_hash }),
+            };
+        // an escrow that can't tell is retried
+        Ok(response.is_ok_and(|response| response.status.is_settled()))
+    }
+
+    /// `escrow_dst` call in the shape the vault takes it for `order_hash`
+    fn vault_msg(order_hash: String, msg: EscrowExecuteMsg) -> VaultExecuteMsg {
+        match msg {
+            EscrowExecuteMsg::Withdraw(EscrowWithdrawMsg { secret }) => {
+                VaultExecuteMsg::Withdraw { order_hash, secret }
+            }
+            EscrowExecuteMsg::PublicWithdraw(EscrowWithdrawMsg { secret }) => {
+                VaultExecuteMsg::PublicWithdraw { order_hash, secret }
+            }
+            EscrowExecuteMsg::Cancel => VaultExecuteMsg::Cancel { order_hash },
+        }
+    }
+
+    fn display_addr(addr: Option<cosmwasm_std::Addr>) -> String {
+        addr.map(String::from).unwrap_or_else(|| "none".to_string())
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::ClockEndBlock {} => execute::cancel_expired(deps, env, None),
+        SudoMsg::CancelExpired { limit } => execute::cancel_expired(deps, env, limit),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
         ESCROW_DEPLOY_REPLY => {
-            if msg.result.is_err() {
-                return Err(ContractError::EscrowContractError);
-            }
+            let response = msg
+                .result
+                .into_result()
+                .map_err(|reason| ContractError::EscrowContractError { reason })?;
+            let escrow_msg: EscrowInstantiateMsg = from_json(&msg.payload)?;
+            let order_hash = escrow_msg.order_hash.clone();
+
+            let data = instantiate_response_data(response)
+                .ok_or_else(|| StdError::generic_err("Missing instantiate response data"))?;
+            let init_res = parse_instantiate_response_data(data.as_slice())
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            let escrow = deps.api.addr_validate(&init_res.contract_address)?;
+
+            ORDERS.update(deps.storage, order_hash.clone(), |order| -> StdResult<_> {
+                let mut order = order.unwrap_or_default();
+                order.escrow = Some(escrow.clone());
+                Ok(order)
+            })?;
+            ESCROW_ORDERS.save(deps.storage, &escrow, &order_hash)?;
 
-            let init_res = parse_instantiate_response_data(msg.payload.as_slice()).unwrap();
+            Ok(Response::new().add_event(escrow_created_event(&escrow_msg, &escrow)))
+        }
 
-            let event = Event::new("escrow_contract")
-                .add_attribute("contract_address", init_res.contract_address);
-            Ok(Response::new().add_event(event))
+        BATCH_ITEM_REPLY => {
+            let (index, escrow, swept): (usize, String, Option<String>) =
+                from_json(&msg.payload)?;
+            let result = msg.result.into_result().map(|_| ());
+            if let (Err(_), Some(order_hash)) = (&result, swept) {
+                // escrows cancelled, rescued or withdrawn outside the sweep
+                // would fail on every retry
+                if !execute::is_settled(deps.as_ref(), &order_hash)? {
+                    let retry_at = env.block.time.seconds() + SWEEP_RETRY_DELAY;
+                    EXPIRIES.save(deps.storage, (retry_at, order_hash), &Empty {})?;
+                }
+            }
+            Ok(Response::new().add_event(batch_result_event(index, &escrow, result)))
         }
 
         _ => Ok(Response::new()),
     }
 }
 
+/// `escrow_created` for an escrow the factory deployed, `escrow` is the spawned
+/// contract or the vault holding it
+fn escrow_created_event(escrow_msg: &EscrowInstantiateMsg, escrow: &Addr) -> Event {
+    let event: Event = EscrowEvent {
+        kind: EscrowEventKind::Created,
+        order_hash: escrow_msg.order_hash.clone(),
+        hashlock: escrow_msg.hashlock.clone(),
+        maker: escrow_msg.maker.clone(),
+        taker: escrow_msg.taker.clone(),
+        amount: escrow_msg.tokens.clone(),
+        stage: ESCROW_CREATED_STAGE.to_string(),
+        secret: None,
+    }
+    .into();
+    event.add_attribute("escrow", escrow)
+}
+
+/// Outcome of one escrow of a `BatchWithdraw` or `BatchCancel`
+fn batch_result_event(index: usize, escrow: &str, result: Result<(), String>) -> Event {
+    let event = Event::new("batch_result")
+        .add_attribute("index", index.to_string())
+        .add_attribute("escrow", escrow);
+    match result {
+        Ok(()) => event.add_attribute("result", "ok"),
+        Err(reason) => event
+            .add_attribute("result", "failed")
+            .add_attribute("error", reason),
+    }
+}
+
+#[allow(deprecated)]
+fn instantiate_response_data(response: SubMsgResponse) -> Option<Binary> {
+    response
+        .msg_responses
+        .into_iter()
+        .next()
+        .map(|res| res.value)
+        .or(response.data)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    unimplemented!()
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::Ownership {} => to_json_binary(&get_ownership(deps.storage)?),
+        QueryMsg::Order { order_hash } => to_json_binary(&query::order(deps, order_hash)?),
+        QueryMsg::RevealedSecret { order_hash } => {
+            to_json_binary(&query::revealed_secret(deps, order_hash)?)
+        }
+        QueryMsg::IsHashlockUsed { hashlock } => {
+            to_json_binary(&query::is_hashlock_used(deps, hashlock)?)
+        }
+        QueryMsg::RevealedSecrets { start_after, limit } => {
+            to_json_binary(&query::revealed_secrets(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let state = STATE.load(deps.storage)?;
+        Ok(ConfigResponse {
+            escrow_code_id: state.escrow_code_id,
+            migratable_escrows: state.migratable_escrows,
+            escrow_vault: state.escrow_vault,
+            paused: PAUSED.load(deps.storage)?,
+        })
+    }
+
+    pub fn order(deps: Deps, order_hash: String) -> StdResult<Option<OrderRecord>> {
+        ORDERS.may_load(deps.storage, order_hash)
+    }
+
+    pub fn is_hashlock_used(deps: Deps, hashlock: String) -> StdResult<IsHashlockUsedResponse> {
+        Ok(IsHashlockUsedResponse {
+            used: USED_HASHLOCKS.has(deps.storage, hashlock.to_ascii_lowercase()),
+        })
+    }
+
+    pub fn revealed_secret(deps: Deps, order_hash: String) -> StdResult<RevealedSecretResponse> {
+        let secret = REVEALED_SECRETS.may_load(deps.storage, order_hash.clone())?;
+        Ok(RevealedSecretResponse { order_hash, secret })
+    }
+
+    pub fn revealed_secrets(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RevealedSecretsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let secrets = REVEALED_SECRETS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(order_hash, secret)| RevealedSecretResponse {
+                    order_hash,
+                    secret: Some(secret),
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(RevealedSecretsResponse { secrets })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    // v0.2.0 replaced the `completed_orders` flags with order records and
+    // introduced ownership and pausing
+    if from_version < Version::new(0, 2, 0) {
+        let migrated_orders = migrate_completed_orders(deps.storage)?;
+        if !OWNERSHIP.exists(deps.storage) {
+            let owner = msg
+                .owner
+                .map(|owner| deps.api.addr_validate(&owner))
+                .transpose()?;
+            OWNERSHIP.save(
+                deps.storage,
+                &crate::state::Ownership {
+                    owner,
+                    pending_owner: None,
+                    pending_expiry: None,
+                },
+            )?;
+        }
+        if !PAUSED.exists(deps.storage) {
+            PAUSED.save(deps.storage, &false)?;
+        }
+        res = res.add_attribute("migrated_orders", migrated_orders.to_string());
+    }
+
+    // v0.3.0 indexes escrows by address for the batch messages
+    if from_version < Version::new(0, 3, 0) {
+        let indexed = index_escrows(deps.storage)?;
+        res = res.add_attribute("indexed_escrows", indexed.to_string());
+    }
+
+    Ok(res)
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use cosmic_common::ownership::OwnershipError;
+    use cosmic_common::funds::FundsError;
+    use crate::msg::{OwnershipAction, Timelocks, VaultExecuteMsg, VaultSide};
+    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::{Addr, Coin};
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn setup(deps: DepsMut) {
+        let owner = addr("owner");
+        instantiate(
+            deps,
+            mock_env(),
+            message_info(&owner, &[]),
+            InstantiateMsg {
+                escrow_code_id: 1,
+                owner: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn escrow_msg() -> EscrowInstantiateMsg {
+        EscrowInstantiateMsg {
+            rescue_delay: 1,
+            order_hash: "aa".to_string(),
+            hashlock: "bb".to_string(),
+            maker: Addr::unchecked("maker"),
+            taker: Addr::unchecked("taker"),
+            tokens: vec![Coin::new(1000u32, "stake")],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 20,
+                dest_cancellation: 30,
+                src_cancellation: 40,
+                src_withdrawal: 10,
+                src_public_withdrawal: 20,
+                src_public_cancellation: 50,
+            },
+            hash_algorithm: Default::default(),
+            factory: None,
+            refund_surplus: false,
+            receiver: None,
+        }
+    }
+
+    /// protobuf encoded `MsgInstantiateContractResponse`
+    fn instantiate_reply(id: u64, payload: Binary, contract_address: &str) -> Reply {
+        let mut data = vec![0x0a, contract_address.len() as u8];
+        data.extend_from_slice(contract_address.as_bytes());
+
+        #[allow(deprecated)]
+        Reply {
+            id,
+            payload,
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+                msg_responses: vec![cosmwasm_std::MsgResponse {
+                    type_url: "/cosmwasm.wasm.v1.MsgInstantiateContractResponse".to_string(),
+                    value: data.into(),
+                }],
+            }),
+        }
+    }
+
+    fn deploy_escrow(mut deps: DepsMut, escrow: &Addr) {
+        let res = execute(
+            deps.branch(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        let submsg = &res.messages[0];
+        reply(
+            deps,
+            mock_env(),
+            instantiate_reply(submsg.id, submsg.payload.clone(), escrow.as_str()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn instantiator_is_default_owner() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap();
+        let ownership: crate::state::Ownership = from_json(res).unwrap();
+        assert_eq!(ownership.owner, Some(addr("owner")));
+        assert_eq!(ownership.pending_owner, None);
+    }
+
+    #[test]
+    fn two_step_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let owner = addr("owner");
+        let new_owner = addr("new_owner");
+
+        let transfer = ExecuteMsg::UpdateOwnership(OwnershipAction::TransferOwnership {
+            new_owner: new_owner.to_string(),
+            expiry: None,
+        });
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), transfer).unwrap();
+
+        // old owner keeps control until the transfer is accepted
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&new_owner, &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotOwner)));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&owner, &[]),
+            ExecuteMsg::UpdateOwnership(OwnershipAction::AcceptOwnership),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotPendingOwner)));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&new_owner, &[]),
+            ExecuteMsg::UpdateOwnership(OwnershipAction::AcceptOwnership),
+        )
+        .unwrap();
+
+        let ownership = get_ownership(deps.as_ref().storage).unwrap();
+        assert_eq!(ownership.owner, Some(new_owner));
+        assert_eq!(ownership.pending_owner, None);
+    }
+
+    #[test]
+    fn expired_transfer_cannot_be_accepted() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let owner = addr("owner");
+        let new_owner = addr("new_owner");
+        let env = mock_env();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ExecuteMsg::UpdateOwnership(OwnershipAction::TransferOwnership {
+                new_owner: new_owner.to_string(),
+                expiry: Some(env.block.time.seconds() + 10),
+            }),
+        )
+        .unwrap();
+
+        let mut later = mock_env();
+        later.block.time = env.block.time.plus_seconds(10);
+        let err = execute(
+            deps.as_mut(),
+            later,
+            message_info(&new_owner, &[]),
+            ExecuteMsg::UpdateOwnership(OwnershipAction::AcceptOwnership),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::TransferExpired)));
+    }
+
+    #[test]
+    fn owner_updates_config() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let owner = addr("owner");
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("anyone"), &[]),
+            ExecuteMsg::UpdateConfig {
+                escrow_code_id: Some(7),
+                migratable_escrows: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotOwner)));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&owner, &[]),
+            ExecuteMsg::UpdateConfig {
+                escrow_code_id: Some(7),
+                migratable_escrows: None,
+            },
+        )
+        .unwrap();
+
+        let config = query::config(deps.as_ref()).unwrap();
+        assert_eq!(config.escrow_code_id, 7);
+    }
+
+    #[test]
+    fn pause_blocks_deploy_escrow() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let owner = addr("owner");
+        let resolver = addr("resolver");
+        let funds = [Coin::new(1000u32, "stake")];
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&owner, &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        assert!(query::config(deps.as_ref()).unwrap().paused);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&resolver, &funds),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Paused));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&owner, &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&resolver, &funds),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn migratable_escrows_get_factory_as_admin() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let env = mock_env();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("owner"), &[]),
+            ExecuteMsg::UpdateConfig {
+                escrow_code_id: None,
+                migratable_escrows: Some(true),
+            },
+        )
+        .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Instantiate { admin, .. }) => {
+                assert_eq!(admin.as_deref(), Some(env.contract.address.as_str()))
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        let order = query::order(deps.as_ref(), "aa".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(order.created_at, Some(env.block.time.seconds()));
+    }
+
+    #[test]
+    fn basket_is_forwarded_to_escrow() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let basket = vec![Coin::new(1000u32, "stake"), Coin::new(5u32, "uatom")];
+        let msg = EscrowInstantiateMsg {
+            tokens: basket.clone(),
+            ..escrow_msg()
+        };
+
+        // partial payment
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(msg.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::InsufficientFunds { denom, .. }) if denom == "uatom"));
+
+        // funds in a different order are fine
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(
+                &addr("resolver"),
+                &[Coin::new(5u32, "uatom"), Coin::new(1000u32, "stake")],
+            ),
+            ExecuteMsg::DeployEscrow(msg),
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Instantiate { funds, msg, .. }) => {
+                assert_eq!(funds, &basket);
+                let forwarded: EscrowInstantiateMsg = from_json(msg).unwrap();
+                assert_eq!(forwarded.tokens, basket);
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn repeated_hashlock_is_rejected() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let used = |deps: Deps, hashlock: &str| {
+            query::is_hashlock_used(deps, hashlock.to_string()).unwrap().used
+        };
+        assert!(!used(deps.as_ref(), "bb"));
+        deploy_escrow(deps.as_mut(), &addr("escrow"));
+        assert!(used(deps.as_ref(), "bb"));
+        assert!(used(deps.as_ref(), "BB"));
+
+        // the same secret would unlock both escrows
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(EscrowInstantiateMsg {
+                order_hash: "cc".to_string(),
+                hashlock: "BB".to_string(),
+                ..escrow_msg()
+            }),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HashlockAlreadyUsed { hashlock } if hashlock == "BB"));
+        assert!(query::order(deps.as_ref(), "cc".to_string()).unwrap().is_none());
+    }
+
+    #[test]
+    fn receiver_is_forwarded_to_the_escrow() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let receiver = addr("receiver").to_string();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(EscrowInstantiateMsg {
+                receiver: Some(receiver.clone()),
+                ..escrow_msg()
+            }),
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+                let forwarded: EscrowInstantiateMsg = from_json(msg).unwrap();
+                assert_eq!(forwarded.receiver, Some(receiver));
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn surplus_deposit_is_refunded_by_factory() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let funds = [Coin::new(1200u32, "stake"), Coin::new(1u32, "uatom")];
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &funds),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::ExtraDenom { denom }) if denom == "uatom"));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &funds),
+            ExecuteMsg::DeployEscrow(EscrowInstantiateMsg {
+                refund_surplus: true,
+                ..escrow_msg()
+            }),
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Instantiate { funds, msg, .. }) => {
+                assert_eq!(funds, &[Coin::new(1000u32, "stake")]);
+                let forwarded: EscrowInstantiateMsg = from_json(msg).unwrap();
+                assert!(!forwarded.refund_surplus);
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+        let refund = BankMsg::Send {
+            to_address: addr("resolver").to_string(),
+            amount: vec![Coin::new(200u32, "stake"), Coin::new(1u32, "uatom")],
+        };
+        assert_eq!(res.messages[1].msg, refund.into());
+    }
+
+    #[test]
+    fn deployment_emits_escrow_created() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        // the escrow's address is only known in the reply
+        assert!(res.events.is_empty());
+        let submsg = &res.messages[0];
+        let escrow = addr("escrow");
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(submsg.id, submsg.payload.clone(), escrow.as_str()),
+        )
+        .unwrap();
+
+        let events = cosmic_common::events::parse_escrow_events(&res.events).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EscrowEventKind::Created);
+        assert_eq!(events[0].order_hash, "aa");
+        assert_eq!(events[0].amount, vec![Coin::new(1000u32, "stake")]);
+        assert_eq!(events[0].stage, ESCROW_CREATED_STAGE);
+        let attr = res.events[0].attributes.iter().find(|a| a.key == "escrow");
+        assert_eq!(attr.map(|a| a.value.as_str()), Some(escrow.as_str()));
+    }
+
+    #[test]
+    fn deployed_escrow_records_revealed_secret() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let escrow = addr("escrow");
+        deploy_escrow(deps.as_mut(), &escrow);
+
+        let order = query::order(deps.as_ref(), "aa".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(order.escrow, Some(escrow.clone()));
+
+        let record = ExecuteMsg::RecordSecret {
+            order_hash: "aa".to_string(),
+            secret: Binary::from(b"secret"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("anyone"), &[]),
+            record.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(deps.as_mut(), mock_env(), message_info(&escrow, &[]), record.clone()).unwrap();
+        let err =
+            execute(deps.as_mut(), mock_env(), message_info(&escrow, &[]), record).unwrap_err();
+        assert!(matches!(err, ContractError::SecretAlreadyRecorded { .. }));
+
+        let res = query::revealed_secret(deps.as_ref(), "aa".to_string()).unwrap();
+        assert_eq!(res.secret, Some(Binary::from(b"secret")));
+        let res = query::revealed_secret(deps.as_ref(), "cc".to_string()).unwrap();
+        assert_eq!(res.secret, None);
+
+        let res = query::revealed_secrets(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.secrets.len(), 1);
+        let res = query::revealed_secrets(deps.as_ref(), Some("aa".to_string()), None).unwrap();
+        assert!(res.secrets.is_empty());
+    }
+
+    #[test]
+    fn failed_deploy_reply_keeps_the_reason() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        let submsg = &res.messages[0];
+
+        #[allow(deprecated)]
+        let failed = Reply {
+            id: submsg.id,
+            payload: submsg.payload.clone(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        let err = reply(deps.as_mut(), mock_env(), failed).unwrap_err();
+        assert!(
+            matches!(err, ContractError::EscrowContractError { ref reason } if reason == "insufficient funds")
+        );
+
+        // malformed instantiate data is an error rather than a panic
+        let mut malformed = instantiate_reply(submsg.id, submsg.payload.clone(), "escrow");
+        if let cosmwasm_std::SubMsgResult::Ok(response) = &mut malformed.result {
+            response.msg_responses[0].value = Binary::from(b"\xff");
+        }
+        reply(deps.as_mut(), mock_env(), malformed).unwrap_err();
+    }
+
+    #[test]
+    fn escrows_are_told_about_their_factory() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let env = mock_env();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(EscrowInstantiateMsg {
+                factory: Some(addr("somebody_else")),
+                ..escrow_msg()
+            }),
+        )
+        .unwrap();
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+                let msg: EscrowInstantiateMsg = from_json(msg).unwrap();
+                assert_eq!(msg.factory, Some(env.contract.address));
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn vault_mode_locks_deposits_in_the_vault() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let env = mock_env();
+        let vault = addr("vault");
+
+        let set_vault = ExecuteMsg::SetEscrowVault {
+            vault: Some(vault.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("anyone"), &[]),
+            set_vault.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotOwner)));
+        execute(deps.as_mut(), env.clone(), message_info(&addr("owner"), &[]), set_vault).unwrap();
+        assert_eq!(query::config(deps.as_ref()).unwrap().escrow_vault, Some(vault.clone()));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Never);
+        let events = cosmic_common::events::parse_escrow_events(&res.events).unwrap();
+        assert_eq!(events[0].kind, EscrowEventKind::Created);
+        let attr = res.events[0].attributes.iter().find(|a| a.key == "escrow");
+        assert_eq!(attr.map(|a| a.value.as_str()), Some(vault.as_str()));
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, vault.as_str());
+                assert_eq!(funds, &[Coin::new(1000u32, "stake")]);
+                let VaultExecuteMsg::Create(create) = from_json(msg).unwrap() else {
+                    panic!("unexpected vault message {msg:?}");
+                };
+                assert_eq!(create.side, VaultSide::Dst);
+                assert_eq!(create.factory, Some(env.contract.address));
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        // the vault is the order's escrow right away, so it may record the secret
+        let order = query::order(deps.as_ref(), "aa".to_string()).unwrap().unwrap();
+        assert_eq!(order.escrow, Some(vault.clone()));
+        let record = ExecuteMsg::RecordSecret {
+            order_hash: "aa".to_string(),
+            secret: Binary::from(b"secret"),
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&vault, &[]), record).unwrap();
+    }
+
+    /// Escrow message and payload of every dispatched batch item
+    fn batch_calls(res: &Response) -> Vec<(String, Binary)> {
+        res
+            .messages
+            .iter()
+            .map(|submsg| {
+                assert_eq!(submsg.id, BATCH_ITEM_REPLY);
+                match &submsg.msg {
+                    cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+                        (String::from_utf8(msg.to_vec()).unwrap(), submsg.payload.clone())
+                    }
+                    msg => panic!("unexpected message {msg:?}"),
+                }
+            })
+            .collect()
+    }
+
+    /// `result` and `error` of the items reported right away
+    fn reported(res: &Response) -> Vec<(&str, Option<&str>)> {
+        res.events
+            .iter()
+            .map(|event| {
+                let attr = |key| {
+                    let attr = event.attributes.iter().find(|attr| attr.key == key);
+                    attr.map(|attr| attr.value.as_str())
+                };
+                (attr("result").unwrap(), attr("error"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn batch_withdraw_dispatches_to_registered_escrows() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let escrow = addr("escrow");
+        deploy_escrow(deps.as_mut(), &escrow);
+        let secret = Binary::from(b"secret");
+        let batch = ExecuteMsg::BatchWithdraw {
+            escrows: vec![
+                (escrow.to_string(), secret.clone()),
+                (addr("stranger").to_string(), secret),
+            ],
+        };
+
+        let taker = message_info(&Addr::unchecked("taker"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), taker, batch.clone()).unwrap();
+        let calls = batch_calls(&res);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, r#"{"withdraw":{"secret":"c2VjcmV0"}}"#);
+        assert_eq!(
+            reported(&res),
+            vec![("failed", Some("escrow was not deployed by the factory"))]
+        );
+
+        // anyone else can only withdraw publicly
+        let anyone = message_info(&addr("anyone"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), anyone, batch).unwrap();
+        let calls = batch_calls(&res);
+        assert_eq!(calls[0].0, r#"{"public_withdraw":{"secret":"c2VjcmV0"}}"#);
+
+        #[allow(deprecated)]
+        let failed = Reply {
+            id: BATCH_ITEM_REPLY,
+            payload: calls[0].1.clone(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Err("Invalid secret".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        let attributes: Vec<_> = res.events[0]
+            .attributes
+            .iter()
+            .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                ("index", "0"),
+                ("escrow", escrow.as_str()),
+                ("result", "failed"),
+                ("error", "Invalid secret")
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_cancel_is_for_the_taker() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let escrow = addr("escrow");
+        deploy_escrow(deps.as_mut(), &escrow);
+        let batch = ExecuteMsg::BatchCancel {
+            escrows: vec![escrow.to_string()],
+        };
+
+        let anyone = message_info(&addr("anyone"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), anyone, batch.clone()).unwrap();
+        assert!(batch_calls(&res).is_empty());
+        assert_eq!(
+            reported(&res),
+            vec![("failed", Some("only the order's taker can cancel"))]
+        );
+
+        let taker = message_info(&Addr::unchecked("taker"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), taker.clone(), batch).unwrap();
+        assert_eq!(batch_calls(&res)[0].0, r#""cancel""#);
+
+        // a spawned escrow can be named by its order hash too
+        let by_hash = ExecuteMsg::BatchCancel {
+            escrows: vec!["aa".to_string()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), taker.clone(), by_hash).unwrap();
+        assert_eq!(batch_calls(&res)[0].0, r#""cancel""#);
+
+        let empty = ExecuteMsg::BatchCancel { escrows: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), taker, empty).unwrap_err();
+        assert!(matches!(err, ContractError::EmptyBatch));
+    }
+
+    #[test]
+    fn batches_reach_vault_held_orders_by_hash() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let vault = addr("vault");
+        let set_vault = ExecuteMsg::SetEscrowVault {
+            vault: Some(vault.to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&addr("owner"), &[]), set_vault).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+
+        // the vault's address is shared, it doesn't name an order
+        let taker = message_info(&Addr::unchecked("taker"), &[]);
+        let batch = ExecuteMsg::BatchCancel {
+            escrows: vec![vault.to_string(), "aa".to_string()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), taker.clone(), batch).unwrap();
+        assert_eq!(
+            reported(&res),
+            vec![("failed", Some("escrow was not deployed by the factory"))]
+        );
+        let calls = batch_calls(&res);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, r#"{"cancel":{"order_hash":"aa"}}"#);
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, vault.as_str());
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+        let (index, item, swept): (usize, String, Option<String>) =
+            from_json(&calls[0].1).unwrap();
+        assert_eq!((index, item.as_str(), swept), (1, "aa", None));
+
+        let batch = ExecuteMsg::BatchWithdraw {
+            escrows: vec![("aa".to_string(), Binary::from(b"secret"))],
+        };
+        let res = execute(deps.as_mut(), mock_env(), taker, batch.clone()).unwrap();
+        assert_eq!(
+            batch_calls(&res)[0].0,
+            r#"{"withdraw":{"order_hash":"aa","secret":"c2VjcmV0"}}"#
+        );
+        let anyone = message_info(&addr("anyone"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), anyone, batch).unwrap();
+        assert_eq!(
+            batch_calls(&res)[0].0,
+            r#"{"public_withdraw":{"order_hash":"aa","secret":"c2VjcmV0"}}"#
+        );
+    }
+
+    #[test]
+    fn sudo_cancels_expired_escrows_once() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let escrow = addr("escrow");
+        deploy_escrow(deps.as_mut(), &escrow);
+        let mut env = mock_env();
+
+        // `escrow_msg` opens cancellation 30s after the deployment
+        env.block.time = env.block.time.plus_seconds(29);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        assert!(res.messages.is_empty());
+
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        assert_eq!(batch_calls(&res)[0].0, r#""cancel""#);
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, escrow.as_str());
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        let res = sudo(deps.as_mut(), env, SudoMsg::ClockEndBlock {}).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn sudo_skips_withdrawn_escrows_and_respects_the_limit() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let mut env = mock_env();
+        for (order_hash, escrow) in [("aa", "escrow_a"), ("bb", "escrow_b"), ("cc", "escrow_c")] {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+                ExecuteMsg::DeployEscrow(EscrowInstantiateMsg {
+                    order_hash: order_hash.to_string(),
+                    hashlock: order_hash.to_string(),
+                    ..escrow_msg()
+                }),
+            )
+            .unwrap();
+            let submsg = &res.messages[0];
+            let reply_msg = instantiate_reply(submsg.id, submsg.payload.clone(), addr(escrow).as_str());
+            reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+        }
+        let record = ExecuteMsg::RecordSecret {
+            order_hash: "aa".to_string(),
+            secret: Binary::from(b"secret"),
+        };
+        execute(deps.as_mut(), env.clone(), message_info(&addr("escrow_a"), &[]), record).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(30);
+        let sweep = SudoMsg::CancelExpired { limit: Some(2) };
+        let res = sudo(deps.as_mut(), env.clone(), sweep.clone()).unwrap();
+        // "aa" was withdrawn, so only "bb" is cancelled in this run
+        assert_eq!(batch_calls(&res).len(), 1);
+        assert!(res.attributes.iter().any(|attr| attr.key == "settled" && attr.value == "1"));
+
+        let res = sudo(deps.as_mut(), env, sweep).unwrap();
+        assert_eq!(batch_calls(&res).len(), 1);
+        assert!(EXPIRIES.is_empty(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn sudo_cancels_vault_held_orders_and_retries_failures() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let vault = addr("vault");
+        let set_vault = ExecuteMsg::SetEscrowVault {
+            vault: Some(vault.to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&addr("owner"), &[]), set_vault).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(30);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        let calls = batch_calls(&res);
+        assert_eq!(calls[0].0, r#"{"cancel":{"order_hash":"aa"}}"#);
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, vault.as_str());
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        // a failed cancellation goes back in the index instead of being lost
+        #[allow(deprecated)]
+        let failed = Reply {
+            id: BATCH_ITEM_REPLY,
+            payload: calls[0].1.clone(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Err("out of gas".to_string()),
+        };
+        reply(deps.as_mut(), env.clone(), failed).unwrap();
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        assert!(res.messages.is_empty());
+
+        env.block.time = env.block.time.plus_seconds(SWEEP_RETRY_DELAY);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        let calls = batch_calls(&res);
+        assert_eq!(calls[0].0, r#"{"cancel":{"order_hash":"aa"}}"#);
+        #[allow(deprecated)]
+        let succeeded = Reply {
+            id: BATCH_ITEM_REPLY,
+            payload: calls[0].1.clone(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Ok(cosmwasm_std::SubMsgResponse {
+                events: vec![],
+                data: None,
+                msg_responses: vec![],
+            }),
+        };
+        reply(deps.as_mut(), env, succeeded).unwrap();
+        assert!(EXPIRIES.is_empty(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn sudo_drops_escrows_settled_outside_the_sweep() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let mut env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("resolver"), &[Coin::new(1000u32, "stake")]),
+            ExecuteMsg::DeployEscrow(escrow_msg()),
+        )
+        .unwrap();
+        let submsg = &res.messages[0];
+        let reply_msg =
+            instantiate_reply(submsg.id, submsg.payload.clone(), addr("escrow").as_str());
+        reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(30);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ClockEndBlock {}).unwrap();
+        let calls = batch_calls(&res);
+        assert_eq!(calls.len(), 1);
+
+        // the taker cancelled it directly, so the sweep's cancel fails for good
+        deps.querier.update_wasm(|query| match query {
+            cosmwasm_std::WasmQuery::Smart { msg, .. } => {
+                assert_eq!(msg.as_slice(), br#"{"status":{}}"#);
+                cosmwasm_std::SystemResult::Ok(cosmwasm_std::ContractResult::Ok(Binary::from(
+                    br#"{"status":"cancelled"}"#,
+                )))
+            }
+            query => panic!("unexpected query {query:?}"),
+        });
+        #[allow(deprecated)]
+        let failed = Reply {
+            id: BATCH_ITEM_REPLY,
+            payload: calls[0].1.clone(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        reply(deps.as_mut(), env.clone(), failed).unwrap();
+        assert!(EXPIRIES.is_empty(deps.as_ref().storage));
+
+        env.block.time = env.block.time.plus_seconds(SWEEP_RETRY_DELAY);
+        let res = sudo(deps.as_mut(), env, SudoMsg::ClockEndBlock {}).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn migration_indexes_deployed_escrows() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let escrow = addr("escrow");
+        let order = OrderRecord {
+            escrow: Some(escrow.clone()),
+            ..Default::default()
+        };
+        ORDERS.save(deps.as_mut().storage, "aa".to_string(), &order).unwrap();
+        ORDERS
+            .save(deps.as_mut().storage, "bb".to_string(), &OrderRecord::default())
+            .unwrap();
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "indexed_escrows" && attr.value == "1"));
+        let order_hash = ESCROW_ORDERS.load(deps.as_ref().storage, &escrow).unwrap();
+        assert_eq!(order_hash, "aa");
+    }
+
+    #[test]
+    fn migrate_legacy_instance() {
+        let mut deps = mock_dependencies();
+        // v0.1.0 layout: no cw2 info, no owner, completed order flags
+        STATE
+            .save(
+                deps.as_mut().storage,
+                &State {
+                    escrow_code_id: 1,
+                    migratable_escrows: false,
+                    escrow_vault: None,
+                },
+            )
+            .unwrap();
+        crate::migrations::save_legacy_completed_order(deps.as_mut().storage, "aa", true)
+            .unwrap();
+        crate::migrations::save_legacy_completed_order(deps.as_mut().storage, "bb", false)
+            .unwrap();
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                owner: Some(addr("owner").to_string()),
+            },
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&cosmwasm_std::attr("migrated_orders", "1")));
+
+        assert!(query::order(deps.as_ref(), "aa".to_string())
+            .unwrap()
+            .is_some());
+        assert!(query::order(deps.as_ref(), "bb".to_string())
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            get_ownership(deps.as_ref().storage).unwrap().owner,
+            Some(addr("owner"))
+        );
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn migrate_rejects_downgrade_and_foreign_contract() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap_err();
+
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "0.1.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap_err();
+    }
+}
@@ -1,3 +1,5 @@
+use cosmic_common::funds::FundsError;
+use cosmic_common::ownership::OwnershipError;
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
@@ -9,12 +11,27 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
     // Add any other custom errors you like here.
-    #[error("Unable to create escrow contract")]
-    EscrowContractError,
+    #[error("Unable to create escrow contract: {reason}")]
+    EscrowContractError { reason: String },
 
     #[error("Order already processed")]
     OrderAlreadyProcessed,
 
-    #[error("Denom/Amount does not match")]
-    UnmatchedDenomOrAmount,
+    #[error("{0}")]
+    Funds(#[from] FundsError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Contract is paused")]
+    Paused,
+
+    #[error("Secret already recorded for order {order_hash}")]
+    SecretAlreadyRecorded { order_hash: String },
+
+    #[error("Batch has no items")]
+    EmptyBatch,
+
+    #[error("Hashlock {hashlock} is already used by another order")]
+    HashlockAlreadyUsed { hashlock: String },
 }
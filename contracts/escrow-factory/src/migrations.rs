@@ -0,0 +1,70 @@
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::Map;
+use semver::Version;
+
+use crate::state::{OrderRecord, ESCROW_ORDERS, ORDERS};
+
+/// Version assumed for instances deployed before cw2 info was stored
+pub const LEGACY_VERSION: &str = "0.1.0";
+
+/// Order bookkeeping used up to v0.1.0
+const LEGACY_COMPLETED_ORDERS: Map<String, bool> = Map::new("completed_orders");
+
+/// Same as `cw2::ensure_from_older_version`, but accepts instances that never
+/// stored their contract version.
+pub fn ensure_from_older_version(
+    storage: &mut dyn Storage,
+    name: &str,
+    new_version: &str,
+) -> StdResult<Version> {
+    if cw2::CONTRACT.may_load(storage)?.is_none() {
+        cw2::set_contract_version(storage, name, LEGACY_VERSION)?;
+    }
+    cw2::ensure_from_older_version(storage, name, new_version)
+}
+
+/// Move `completed_orders` flags into `orders` records, returns how many were moved
+pub fn migrate_completed_orders(storage: &mut dyn Storage) -> StdResult<u64> {
+    let completed_orders = LEGACY_COMPLETED_ORDERS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut migrated = 0;
+    for (order_hash, completed) in completed_orders {
+        LEGACY_COMPLETED_ORDERS.remove(storage, order_hash.clone());
+        if completed {
+            ORDERS.save(storage, order_hash, &OrderRecord::default())?;
+            migrated += 1;
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// Fill `escrow_orders` from the order records, returns how many escrows were indexed
+pub fn index_escrows(storage: &mut dyn Storage) -> StdResult<u64> {
+    let escrows = ORDERS
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| {
+            item.map(|(order_hash, order)| order.escrow.map(|escrow| (escrow, order_hash)))
+                .transpose()
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut indexed = 0;
+    for (escrow, order_hash) in escrows {
+        ESCROW_ORDERS.save(storage, &escrow, &order_hash)?;
+        indexed += 1;
+    }
+
+    Ok(indexed)
+}
+
+#[cfg(test)]
+pub(crate) fn save_legacy_completed_order(
+    storage: &mut dyn Storage,
+    order_hash: &str,
+    completed: bool,
+) -> StdResult<()> {
+    LEGACY_COMPLETED_ORDERS.save(storage, order_hash.to_string(), &completed)
+}
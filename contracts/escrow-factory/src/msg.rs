@@ -1,16 +1,54 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Binary, Coin};
+use serde::Deserialize;
+
+use crate::state::{OrderRecord, Ownership};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub escrow_code_id : u64
+    pub escrow_code_id : u64,
+    /// defaults to the instantiator
+    pub owner: Option<String>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    DeployEscrow(EscrowInstantiateMsg)
+    DeployEscrow(EscrowInstantiateMsg),
+    UpdateOwnership(OwnershipAction),
+    UpdateConfig {
+        escrow_code_id: Option<u64>,
+        migratable_escrows: Option<bool>,
+    },
+    /// Lock new deposits in an `escrow-vault` instead of spawning `escrow_dst`
+    /// contracts, `None` switches back to spawning
+    SetEscrowVault { vault: Option<String> },
+    Pause {},
+    Unpause {},
+    /// Upgrade an escrow that was spawned while `migratable_escrows` was set
+    MigrateEscrow {
+        escrow: String,
+        code_id: u64,
+        msg: Binary,
+    },
+    /// Called by a deployed escrow when its secret was revealed on withdrawal
+    RecordSecret { order_hash: String, secret: Binary },
+    /// Withdraw from escrows the factory deployed, with each escrow's secret.
+    /// Items name a spawned escrow by address or any order by its hash, orders
+    /// held by the vault only by hash. The order's taker withdraws in both
+    /// withdrawal stages, anyone else only publicly. Every item reports its
+    /// outcome in a `batch_result` event.
+    BatchWithdraw { escrows: Vec<(String, Binary)> },
+    /// Cancel escrows the factory deployed for orders the sender is the taker
+    /// of, named like in `BatchWithdraw`. Every item reports its outcome in a
+    /// `batch_result` event.
+    BatchCancel { escrows: Vec<String> },
 }
 
+pub use cosmic_common::hashlock::HashAlgorithm;
+pub use cosmic_common::ownership::OwnershipAction;
+pub use cosmic_common::stages::EscrowStatus;
+pub use cosmic_common::timelocks::Timelocks;
+
 #[cw_serde]
 pub struct EscrowInstantiateMsg {
     pub rescue_delay: u64,
@@ -18,21 +56,144 @@ pub struct EscrowInstantiateMsg {
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    /// basket of coins locked in the escrow, sent along with the deploy message
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// overwritten with the factory's address on deployment
+    #[serde(default)]
+    pub factory: Option<Addr>,
+    /// send funds beyond `tokens` back to the sender instead of failing
+    #[serde(default)]
+    pub refund_surplus: bool,
+    /// receiver of a cross-chain order, paid by the escrow instead of the maker
+    #[serde(default)]
+    pub receiver: Option<String>,
+}
+
+/// Subset of the `escrow_dst` `ExecuteMsg` the factory calls into
+#[cw_serde]
+pub enum EscrowExecuteMsg {
+    Withdraw(EscrowWithdrawMsg),
+    PublicWithdraw(EscrowWithdrawMsg),
+    Cancel,
+}
+
+#[cw_serde]
+pub struct EscrowWithdrawMsg {
+    pub secret: Binary,
+}
+
+/// Subset of the `escrow_dst` `QueryMsg` the factory reads
+#[cw_serde]
+pub enum EscrowQueryMsg {
+    Status {},
+}
+
+/// Subset of the escrow vault's `QueryMsg` the factory reads
+#[cw_serde]
+pub enum VaultQueryMsg {
+    Escrow { order_hash: String },
+}
+
+/// Status of an escrow, read from the `escrow_dst` `StatusResponse` or the
+/// vault's `EscrowResponse`, so other fields are ignored
+#[derive(Deserialize, Debug)]
+pub struct EscrowStatusResponse {
+    pub status: EscrowStatus,
+}
+
+/// Subset of the escrow vault's `ExecuteMsg` the factory calls into
+#[cw_serde]
+pub enum VaultExecuteMsg {
+    Create(VaultCreateMsg),
+    Withdraw { order_hash: String, secret: Binary },
+    PublicWithdraw { order_hash: String, secret: Binary },
+    Cancel { order_hash: String },
+}
+
+/// `escrow_dst` instantiate message plus the vault's side of the swap
+#[cw_serde]
+pub struct VaultCreateMsg {
+    pub side: VaultSide,
+    pub rescue_delay: u64,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
+    pub factory: Option<Addr>,
+    pub receiver: Option<String>,
 }
 
 #[cw_serde]
-pub struct Timelocks {
-    pub dest_withdrawal: u64,
-    pub dest_public_withdrawal: u64,
-    pub dest_cancellation: u64,
-    pub src_cancellation: u64,
-    pub src_withdrawal:u64,
-    pub src_public_withdrawal:u64,
-    pub src_public_cancellation : u64
+#[derive(Copy)]
+pub enum VaultSide {
+    Src,
+    Dst,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
-pub enum QueryMsg {}
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(Ownership)]
+    Ownership {},
+    #[returns(Option<OrderRecord>)]
+    Order { order_hash: String },
+    #[returns(RevealedSecretResponse)]
+    RevealedSecret { order_hash: String },
+    /// Revealed secrets ordered by order hash
+    #[returns(RevealedSecretsResponse)]
+    RevealedSecrets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Whether an escrow was already deployed with `hashlock`, deploying
+    /// another one with it fails
+    #[returns(IsHashlockUsedResponse)]
+    IsHashlockUsed { hashlock: String },
+}
+
+#[cw_serde]
+pub struct IsHashlockUsedResponse {
+    pub used: bool,
+}
+
+#[cw_serde]
+pub struct RevealedSecretResponse {
+    pub order_hash: String,
+    pub secret: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct RevealedSecretsResponse {
+    pub secrets: Vec<RevealedSecretResponse>,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub escrow_code_id: u64,
+    pub migratable_escrows: bool,
+    pub escrow_vault: Option<Addr>,
+    pub paused: bool,
+}
+
+/// Called by the chain, e.g. a clock module every block or a cron schedule
+#[cw_serde]
+pub enum SudoMsg {
+    /// Cancel a bounded number of expired escrows
+    ClockEndBlock {},
+    /// Same as `ClockEndBlock` with an explicit bound
+    CancelExpired { limit: Option<u32> },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// owner to set when migrating an instance that predates ownership
+    pub owner: Option<String>,
+}
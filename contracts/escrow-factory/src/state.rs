@@ -1,11 +1,51 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Empty};
 use cw_storage_plus::{Item, Map};
- 
-pub const COMPLETED_ORDERS: Map<String, bool> = Map::new("completed_orders");
 
 #[cw_serde]
 pub struct State {
     pub escrow_code_id: u64,
+    /// spawn escrows with the factory as their admin, so they can be upgraded
+    /// through `MigrateEscrow`
+    #[serde(default)]
+    pub migratable_escrows: bool,
+    /// lock deposits in this `escrow-vault` instead of spawning an escrow per order
+    #[serde(default)]
+    pub escrow_vault: Option<Addr>,
 }
 
-pub const STATE: Item<State> = Item::new("state");
\ No newline at end of file
+/// Bookkeeping for a processed order, fields are unknown for orders migrated from v0.1.0
+#[cw_serde]
+#[derive(Default)]
+pub struct OrderRecord {
+    pub maker: Option<Addr>,
+    pub taker: Option<Addr>,
+    /// unix timestamp (in sec) of the escrow deployment
+    pub created_at: Option<u64>,
+    pub escrow: Option<Addr>,
+}
+
+pub const ORDERS: Map<String, OrderRecord> = Map::new("orders");
+
+/// spawned escrow -> order hash, orders held by a vault aren't indexed since
+/// the vault's address is shared
+pub const ESCROW_ORDERS: Map<&Addr, String> = Map::new("escrow_orders");
+
+/// (unix timestamp the cancellation stage opens at, order hash) of escrows not
+/// yet swept, spawned or held by the vault, ordered by expiry. A failed
+/// cancellation of an escrow that isn't settled is put back with the time it
+/// is retried at.
+pub const EXPIRIES: Map<(u64, String), Empty> = Map::new("expiries");
+
+/// lowercased hashlocks of every deployed escrow, a revealed secret would
+/// unlock all escrows sharing its hashlock
+pub const USED_HASHLOCKS: Map<String, Empty> = Map::new("used_hashlocks");
+
+/// order hash -> secret revealed by the order's escrow
+pub const REVEALED_SECRETS: Map<String, Binary> = Map::new("revealed_secrets");
+
+pub const STATE: Item<State> = Item::new("state");
+
+pub use cosmic_common::ownership::{Ownership, OWNERSHIP};
+
+pub const PAUSED: Item<bool> = Item::new("paused");
@@ -0,0 +1,687 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, BankMsg, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, SubMsgResult,
+};
+use cw2::{ensure_from_older_version, set_contract_version};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use cosmic_common::events::{EscrowEvent, EscrowEventKind};
+use crate::helpers::{check_funds, only_valid_secret, validate_basket};
+use crate::msg::{
+    AllowedActionsResponse, CreateEscrowMsg, CurrentStageResponse, EscrowResponse, EscrowsResponse,
+    CreatorsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RevealedSecretResponse,
+};
+use crate::stages::{self, authorize};
+use crate::state::{
+    Action, Escrow, EscrowStatus, Immutables, CREATORS, ESCROWS, REVEALED_SECRETS,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:escrow-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const RECORD_SECRET_REPLY: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let creators = msg
+        .creators
+        .iter()
+        .map(|creator| deps.api.addr_validate(creator))
+        .collect::<StdResult<Vec<_>>>()?;
+    CREATORS.save(deps.storage, &creators)?;
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("creators", msg.creators.join(",")))
+}
+
+fn escrow_event(
+    kind: EscrowEventKind,
+    escrow: &Escrow,
+    env: &Env,
+    secret: Option<String>,
+) -> Event {
+    let immutables = &escrow.immutables;
+    let stage =
+        immutables
+            .timelocks
+            .stage(escrow.side, escrow.deployed_at, env.block.time.seconds());
+
+    let event: Event = EscrowEvent {
+        kind,
+        order_hash: hex::encode(&immutables.order_hash),
+        hashlock: hex::encode(&immutables.hashlock),
+        maker: immutables.maker.clone(),
+        taker: immutables.taker.clone(),
+        amount: immutables.tokens.clone(),
+        stage: stage.as_str().to_string(),
+        secret,
+    }
+    .into();
+    event.add_attribute("side", escrow.side.as_str())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Create(msg) => execute::create(deps, env, info, msg),
+        ExecuteMsg::Withdraw { order_hash, secret } => {
+            execute::withdraw(deps, env, info, order_hash, Action::Withdraw, secret)
+        }
+        ExecuteMsg::PublicWithdraw { order_hash, secret } => {
+            execute::withdraw(deps, env, info, order_hash, Action::PublicWithdraw, secret)
+        }
+        ExecuteMsg::Cancel { order_hash } => {
+            execute::refund(deps, env, info, order_hash, Action::Cancel)
+        }
+        ExecuteMsg::PublicCancel { order_hash } => {
+            execute::refund(deps, env, info, order_hash, Action::PublicCancel)
+        }
+        ExecuteMsg::RescueFunds { order_hash } => {
+            execute::refund(deps, env, info, order_hash, Action::RescueFunds)
+        }
+    }
+}
+
+pub mod execute {
+    use cosmwasm_std::{SubMsg, WasmMsg};
+
+    use crate::msg::FactoryExecuteMsg;
+
+    use super::*;
+
+    /// Locks the attached funds the way instantiating a standalone escrow
+    /// would, an order hash can only be used once
+    pub fn create(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: CreateEscrowMsg,
+    ) -> Result<Response, ContractError> {
+        if !CREATORS.load(deps.storage)?.contains(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        validate_basket(&msg.tokens)?;
+        let surplus = check_funds(&info.funds, &msg.tokens, msg.refund_surplus)?;
+
+        let hashlock =
+            hex::decode(&msg.hashlock).map_err(|e| StdError::generic_err(e.to_string()))?;
+        let order_hash =
+            hex::decode(&msg.order_hash).map_err(|e| StdError::generic_err(e.to_string()))?;
+        // keyed by the normalized hex so `AB..` and `ab..` can't coexist
+        let key = hex::encode(&order_hash);
+        if ESCROWS.has(deps.storage, key.clone()) {
+            return Err(ContractError::EscrowAlreadyExists { order_hash: key });
+        }
+
+        let escrow = Escrow {
+            side: msg.side,
+            immutables: Immutables {
+                order_hash,
+                hashlock,
+                maker: msg.maker,
+                taker: msg.taker,
+                tokens: msg.tokens,
+                timelocks: msg.timelocks,
+                hash_algorithm: msg.hash_algorithm,
+                receiver: msg
+                    .receiver
+                    .map(|receiver| deps.api.addr_validate(&receiver))
+                    .transpose()?,
+            },
+            deployed_at: env.block.time.seconds(),
+            rescue_delay: msg.rescue_delay,
+            factory: msg.factory,
+            status: EscrowStatus::Active,
+        };
+        ESCROWS.save(deps.storage, key, &escrow)?;
+
+        let event = escrow_event(EscrowEventKind::Created, &escrow, &env, None);
+        let mut res = Response::new().add_event(event);
+        if !surplus.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: info.sender.into(),
+                amount: surplus,
+            });
+        }
+        Ok(res)
+    }
+
+    /// `Withdraw` and `PublicWithdraw`: reveal the secret and pay out
+    pub fn withdraw(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        order_hash: String,
+        action: Action,
+        secret: Binary,
+    ) -> Result<Response, ContractError> {
+        let (key, mut escrow) = load_escrow(deps.as_ref(), &order_hash)?;
+        authorize(&escrow, &info.sender, action, env.block.time.seconds())?;
+        let immutables = &escrow.immutables;
+        only_valid_secret(&secret, &immutables.hashlock, immutables.hash_algorithm)?;
+
+        escrow.status = EscrowStatus::Withdrawn;
+        ESCROWS.save(deps.storage, key.clone(), &escrow)?;
+        REVEALED_SECRETS.save(deps.storage, key.clone(), &secret)?;
+
+        let secret_hex = hex::encode(&secret);
+        let withdrawn = escrow_event(
+            EscrowEventKind::Withdrawn,
+            &escrow,
+            &env,
+            Some(secret_hex.clone()),
+        );
+        let revealed = escrow_event(
+            EscrowEventKind::SecretRevealed,
+            &escrow,
+            &env,
+            Some(secret_hex),
+        );
+        let mut res = Response::new()
+            .add_events([withdrawn, revealed])
+            .add_message(pay_out(&escrow, action));
+
+        // a failing factory call must not block the withdrawal, hence `reply_on_error`
+        if let Some(factory) = &escrow.factory {
+            let msg = WasmMsg::Execute {
+                contract_addr: factory.to_string(),
+                msg: to_json_binary(&FactoryExecuteMsg::RecordSecret {
+                    order_hash: key,
+                    secret,
+                })?,
+                funds: vec![],
+            };
+            res = res.add_submessage(SubMsg::reply_on_error(msg, RECORD_SECRET_REPLY));
+        }
+
+        Ok(res)
+    }
+
+    /// `Cancel`, `PublicCancel` and `RescueFunds`: give the tokens back
+    pub fn refund(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        order_hash: String,
+        action: Action,
+    ) -> Result<Response, ContractError> {
+        let (key, mut escrow) = load_escrow(deps.as_ref(), &order_hash)?;
+        authorize(&escrow, &info.sender, action, env.block.time.seconds())?;
+
+        let (status, kind) = match action {
+            Action::RescueFunds => (EscrowStatus::Rescued, EscrowEventKind::FundsRescued),
+            _ => (EscrowStatus::Cancelled, EscrowEventKind::Cancelled),
+        };
+        escrow.status = status;
+        ESCROWS.save(deps.storage, key, &escrow)?;
+
+        let event = escrow_event(kind, &escrow, &env, None);
+        Ok(Response::new()
+            .add_event(event)
+            .add_message(pay_out(&escrow, action)))
+    }
+
+    /// Only the escrow's own tokens leave the vault, the others' stay untouched
+    fn pay_out(escrow: &Escrow, action: Action) -> BankMsg {
+        BankMsg::Send {
+            to_address: escrow.recipient(action).to_string(),
+            amount: escrow.immutables.tokens.clone(),
+        }
+    }
+}
+
+fn load_escrow(deps: Deps, order_hash: &str) -> Result<(String, Escrow), ContractError> {
+    let key = normalize(order_hash)?;
+    let escrow = ESCROWS
+        .may_load(deps.storage, key.clone())?
+        .ok_or_else(|| ContractError::EscrowNotFound {
+            order_hash: key.clone(),
+        })?;
+    Ok((key, escrow))
+}
+
+fn normalize(order_hash: &str) -> StdResult<String> {
+    let bytes = hex::decode(order_hash).map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(hex::encode(bytes))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Escrow { order_hash } => to_json_binary(&query::escrow(deps, order_hash)?),
+        QueryMsg::Escrows { start_after, limit } => {
+            to_json_binary(&query::escrows(deps, start_after, limit)?)
+        }
+        QueryMsg::CurrentStage { order_hash } => {
+            to_json_binary(&query::current_stage(deps, env, order_hash)?)
+        }
+        QueryMsg::AllowedActions {
+            order_hash,
+            address,
+        } => to_json_binary(&query::allowed_actions(deps, env, order_hash, address)?),
+        QueryMsg::RevealedSecret { order_hash } => {
+            to_json_binary(&query::revealed_secret(deps, order_hash)?)
+        }
+        QueryMsg::Creators {} => to_json_binary(&query::creators(deps)?),
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    fn load(deps: Deps, order_hash: &str) -> StdResult<Escrow> {
+        load_escrow(deps, order_hash)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+            .map(|(_, escrow)| escrow)
+    }
+
+    fn escrow_response(escrow: Escrow) -> EscrowResponse {
+        let immutables = escrow.immutables;
+        EscrowResponse {
+            side: escrow.side,
+            status: escrow.status,
+            deployed_at: escrow.deployed_at,
+            rescue_delay: escrow.rescue_delay,
+            order_hash: hex::encode(immutables.order_hash),
+            hashlock: hex::encode(immutables.hashlock),
+            maker: immutables.maker,
+            taker: immutables.taker,
+            tokens: immutables.tokens,
+            timelocks: immutables.timelocks,
+            hash_algorithm: immutables.hash_algorithm,
+            factory: escrow.factory,
+            receiver: immutables.receiver,
+        }
+    }
+
+    pub fn escrow(deps: Deps, order_hash: String) -> StdResult<EscrowResponse> {
+        load(deps, &order_hash).map(escrow_response)
+    }
+
+    pub fn escrows(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<EscrowsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let escrows = ESCROWS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, escrow)| escrow_response(escrow)))
+            .collect::<StdResult<_>>()?;
+
+        Ok(EscrowsResponse { escrows })
+    }
+
+    pub fn current_stage(
+        deps: Deps,
+        env: Env,
+        order_hash: String,
+    ) -> StdResult<CurrentStageResponse> {
+        let escrow = load(deps, &order_hash)?;
+        let timelocks = &escrow.immutables.timelocks;
+        let now = env.block.time.seconds();
+
+        let stage = timelocks.stage(escrow.side, escrow.deployed_at, now);
+        let (start, end) = timelocks.stage_bounds(escrow.side, stage);
+        let ends_at = end.map(|end| escrow.deployed_at + end);
+        Ok(CurrentStageResponse {
+            stage,
+            starts_at: escrow.deployed_at + start,
+            ends_at,
+            seconds_until_next: ends_at.map(|ends_at| ends_at.saturating_sub(now)),
+        })
+    }
+
+    pub fn allowed_actions(
+        deps: Deps,
+        env: Env,
+        order_hash: String,
+        address: String,
+    ) -> StdResult<AllowedActionsResponse> {
+        let escrow = load(deps, &order_hash)?;
+        let address = deps.api.addr_validate(&address)?;
+        Ok(AllowedActionsResponse {
+            actions: stages::allowed_actions(&escrow, &address, env.block.time.seconds()),
+        })
+    }
+
+    pub fn creators(deps: Deps) -> StdResult<CreatorsResponse> {
+        let creators = CREATORS.load(deps.storage)?;
+        Ok(CreatorsResponse { creators })
+    }
+
+    pub fn revealed_secret(deps: Deps, order_hash: String) -> StdResult<RevealedSecretResponse> {
+        Ok(RevealedSecretResponse {
+            secret: REVEALED_SECRETS.may_load(deps.storage, normalize(&order_hash)?)?,
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        // the secret is still published through events and `RevealedSecret`
+        RECORD_SECRET_REPLY => {
+            let mut res = Response::new().add_attribute("record_secret", "failed");
+            if let SubMsgResult::Err(reason) = msg.result {
+                res = res.add_attribute("record_secret_error", reason);
+            }
+            Ok(res)
+        }
+        _ => Ok(Response::new()),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmic_common::funds::FundsError;
+    use cosmic_common::stages::StageError;
+    use crate::msg::FactoryExecuteMsg;
+    use crate::state::{Side, Timelocks};
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{coin, from_json, Addr, CosmosMsg, OwnedDeps, WasmMsg};
+    use sha3::{Digest, Keccak256};
+
+    const SECRET: &[u8] = b"secret";
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    /// Vault accepting escrows from the resolver and the factory
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            creators: vec![addr("resolver").to_string(), addr("factory").to_string()],
+        };
+        let info = message_info(&addr("creator"), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        deps
+    }
+
+    fn create_msg(side: Side, order_hash: &str) -> CreateEscrowMsg {
+        CreateEscrowMsg {
+            side,
+            rescue_delay: 1000,
+            order_hash: order_hash.to_string(),
+            hashlock: hex::encode(Keccak256::digest(SECRET)),
+            maker: addr("maker"),
+            taker: addr("taker"),
+            tokens: vec![coin(100, "uusdc")],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 100,
+                dest_cancellation: 200,
+                src_cancellation: 300,
+                src_withdrawal: 10,
+                src_public_withdrawal: 100,
+                src_public_cancellation: 400,
+            },
+            hash_algorithm: Default::default(),
+            factory: None,
+            refund_surplus: false,
+            receiver: None,
+        }
+    }
+
+    fn withdraw(order_hash: &str) -> ExecuteMsg {
+        ExecuteMsg::Withdraw {
+            order_hash: order_hash.to_string(),
+            secret: Binary::from(SECRET),
+        }
+    }
+
+    fn bank_send(res: &Response) -> (String, Vec<cosmwasm_std::Coin>) {
+        res.messages
+            .iter()
+            .find_map(|m| match &m.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    Some((to_address.clone(), amount.clone()))
+                }
+                _ => None,
+            })
+            .expect("no bank send")
+    }
+
+    #[test]
+    fn escrows_are_funded_once_per_order_hash() {
+        let mut deps = setup();
+        let env = mock_env();
+        let resolver = message_info(&addr("resolver"), &[coin(100, "uusdc")]);
+        let order_hash = "ab".repeat(32);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("resolver"), &[coin(99, "uusdc")]),
+            ExecuteMsg::Create(create_msg(Side::Dst, &order_hash)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::InsufficientFunds { .. })));
+
+        let msg = ExecuteMsg::Create(create_msg(Side::Dst, &order_hash));
+        execute(deps.as_mut(), env.clone(), resolver.clone(), msg).unwrap();
+
+        // the same order under another spelling of the hash
+        let msg = ExecuteMsg::Create(create_msg(Side::Src, &"AB".repeat(32)));
+        let err = execute(deps.as_mut(), env, resolver, msg).unwrap_err();
+        assert!(
+            matches!(err, ContractError::EscrowAlreadyExists { order_hash: hash } if hash == order_hash)
+        );
+
+        let escrow = query::escrow(deps.as_ref(), order_hash).unwrap();
+        assert_eq!(
+            (escrow.side, escrow.status),
+            (Side::Dst, EscrowStatus::Active)
+        );
+    }
+
+    #[test]
+    fn only_creators_can_take_an_order_hash() {
+        let mut deps = setup();
+        let env = mock_env();
+        let order_hash = "ab".repeat(32);
+
+        // a third party seeing the order first can't squat on its hash
+        let msg = ExecuteMsg::Create(create_msg(Side::Dst, &order_hash));
+        let squatter = message_info(&addr("squatter"), &[coin(100, "uusdc")]);
+        let err = execute(deps.as_mut(), env.clone(), squatter, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        assert!(query::escrow(deps.as_ref(), order_hash.clone()).is_err());
+
+        let msg = ExecuteMsg::Create(create_msg(Side::Dst, &order_hash));
+        let factory = message_info(&addr("factory"), &[coin(100, "uusdc")]);
+        execute(deps.as_mut(), env, factory, msg).unwrap();
+
+        let creators = query::creators(deps.as_ref()).unwrap().creators;
+        assert_eq!(creators, vec![addr("resolver"), addr("factory")]);
+    }
+
+    #[test]
+    fn payouts_follow_the_escrow_side() {
+        let mut deps = setup();
+        let mut env = mock_env();
+        let funds = message_info(&addr("resolver"), &[coin(100, "uusdc")]);
+        let taker = message_info(&addr("taker"), &[]);
+        let (src, dst) = ("01".repeat(32), "02".repeat(32));
+        for (side, order_hash) in [(Side::Src, &src), (Side::Dst, &dst)] {
+            let msg = ExecuteMsg::Create(create_msg(side, order_hash));
+            execute(deps.as_mut(), env.clone(), funds.clone(), msg).unwrap();
+        }
+        let cross_chain = "03".repeat(32);
+        let msg = ExecuteMsg::Create(CreateEscrowMsg {
+            receiver: Some(addr("receiver").to_string()),
+            ..create_msg(Side::Dst, &cross_chain)
+        });
+        execute(deps.as_mut(), env.clone(), funds.clone(), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let res = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw(&src)).unwrap();
+        assert_eq!(
+            bank_send(&res),
+            (addr("taker").to_string(), vec![coin(100, "uusdc")])
+        );
+        let res = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw(&dst)).unwrap();
+        assert_eq!(
+            bank_send(&res),
+            (addr("maker").to_string(), vec![coin(100, "uusdc")])
+        );
+        // a cross-chain order's receiver is paid instead of the maker
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            withdraw(&cross_chain),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_send(&res),
+            (addr("receiver").to_string(), vec![coin(100, "uusdc")])
+        );
+
+        let secret = query::revealed_secret(deps.as_ref(), src.clone())
+            .unwrap()
+            .secret;
+        assert_eq!(secret, Some(Binary::from(SECRET)));
+
+        // a settled escrow can't pay out the other escrows' funds
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw(&src)).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::AlreadySettled { .. })));
+        env.block.time = env.block.time.plus_seconds(400);
+        let cancel = ExecuteMsg::Cancel { order_hash: dst };
+        let err = execute(deps.as_mut(), env, taker, cancel).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Stage(StageError::AlreadySettled { status }) if status == "withdrawn"
+        ));
+    }
+
+    #[test]
+    fn cancel_and_rescue_refund_per_side() {
+        let mut deps = setup();
+        let mut env = mock_env();
+        let funds = message_info(&addr("resolver"), &[coin(100, "uusdc")]);
+        let (src, dst) = ("01".repeat(32), "02".repeat(32));
+        for (side, order_hash) in [(Side::Src, &src), (Side::Dst, &dst)] {
+            let msg = ExecuteMsg::Create(create_msg(side, order_hash));
+            execute(deps.as_mut(), env.clone(), funds.clone(), msg).unwrap();
+        }
+
+        env.block.time = env.block.time.plus_seconds(400);
+        let anyone = message_info(&addr("anyone"), &[]);
+        let msg = ExecuteMsg::PublicCancel {
+            order_hash: dst.clone(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), anyone.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotSupported { .. })));
+
+        let msg = ExecuteMsg::PublicCancel {
+            order_hash: src.clone(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), anyone, msg).unwrap();
+        assert_eq!(bank_send(&res).0, addr("maker").to_string());
+        let status = query::escrow(deps.as_ref(), src).unwrap().status;
+        assert_eq!(status, EscrowStatus::Cancelled);
+
+        env.block.time = env.block.time.plus_seconds(600);
+        let taker = message_info(&addr("taker"), &[]);
+        let msg = ExecuteMsg::RescueFunds {
+            order_hash: dst.clone(),
+        };
+        let res = execute(deps.as_mut(), env, taker, msg).unwrap();
+        assert_eq!(bank_send(&res).0, addr("taker").to_string());
+        let status = query::escrow(deps.as_ref(), dst).unwrap().status;
+        assert_eq!(status, EscrowStatus::Rescued);
+    }
+
+    #[test]
+    fn withdrawal_reports_secret_to_factory() {
+        let mut deps = setup();
+        let mut env = mock_env();
+        let order_hash = "ab".repeat(32);
+        let msg = ExecuteMsg::Create(CreateEscrowMsg {
+            factory: Some(addr("factory")),
+            ..create_msg(Side::Dst, &order_hash)
+        });
+        let funds = message_info(&addr("factory"), &[coin(100, "uusdc")]);
+        execute(deps.as_mut(), env.clone(), funds, msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let taker = message_info(&addr("taker"), &[]);
+        let res = execute(deps.as_mut(), env, taker, withdraw(&order_hash)).unwrap();
+
+        let report = res
+            .messages
+            .iter()
+            .find(|m| m.id == RECORD_SECRET_REPLY)
+            .expect("no report");
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &report.msg
+        else {
+            panic!("unexpected message {:?}", report.msg);
+        };
+        assert_eq!(contract_addr, addr("factory").as_str());
+        let FactoryExecuteMsg::RecordSecret {
+            order_hash: hash,
+            secret,
+        } = from_json(msg).unwrap();
+        assert_eq!((hash, secret), (order_hash, Binary::from(SECRET)));
+    }
+
+    #[test]
+    fn stage_queries_use_the_escrow_side() {
+        let mut deps = setup();
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        let order_hash = "ab".repeat(32);
+        let funds = message_info(&addr("resolver"), &[coin(100, "uusdc")]);
+        let msg = ExecuteMsg::Create(create_msg(Side::Src, &order_hash));
+        execute(deps.as_mut(), env.clone(), funds, msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(250);
+        let res = query::current_stage(deps.as_ref(), env.clone(), order_hash.clone()).unwrap();
+        assert_eq!(res.stage, crate::state::Stage::PublicWithdrawal);
+        assert_eq!(
+            (res.ends_at, res.seconds_until_next),
+            (Some(deployed_at + 300), Some(50))
+        );
+
+        let allowed =
+            query::allowed_actions(deps.as_ref(), env, order_hash, addr("anyone").to_string())
+                .unwrap();
+        assert_eq!(allowed.actions, vec![Action::PublicWithdraw]);
+    }
+}
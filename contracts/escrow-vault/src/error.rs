@@ -0,0 +1,30 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+use cosmic_common::funds::FundsError;
+use cosmic_common::hashlock::SecretError;
+use cosmic_common::stages::StageError;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("{0}")]
+    Funds(#[from] FundsError),
+
+    #[error("Escrow for order {order_hash} already exists")]
+    EscrowAlreadyExists { order_hash: String },
+
+    #[error("No escrow for order {order_hash}")]
+    EscrowNotFound { order_hash: String },
+
+    #[error("{0}")]
+    Secret(#[from] SecretError),
+
+    #[error("{0}")]
+    Stage(#[from] StageError),
+}
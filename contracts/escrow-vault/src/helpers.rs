@@ -0,0 +1,2 @@
+pub use cosmic_common::funds::{check_funds, validate_basket};
+pub use cosmic_common::hashlock::only_valid_secret;
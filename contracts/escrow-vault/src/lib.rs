@@ -0,0 +1,8 @@
+pub mod contract;
+mod error;
+pub mod helpers;
+pub mod msg;
+pub mod stages;
+pub mod state;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,141 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin};
+
+use crate::state::{Action, EscrowStatus, HashAlgorithm, Side, Stage, Timelocks};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// contracts allowed to lock escrows, usually the factory and the limit order protocol
+    pub creators: Vec<String>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Lock the attached funds in a new escrow keyed by `order_hash`, creators only
+    Create(CreateEscrowMsg),
+    Withdraw {
+        order_hash: String,
+        secret: Binary,
+    },
+    PublicWithdraw {
+        order_hash: String,
+        secret: Binary,
+    },
+    Cancel {
+        order_hash: String,
+    },
+    /// Source escrows only
+    PublicCancel {
+        order_hash: String,
+    },
+    /// Destination escrows only
+    RescueFunds {
+        order_hash: String,
+    },
+}
+
+/// Same fields as the standalone escrows' instantiate message, plus the side
+#[cw_serde]
+pub struct CreateEscrowMsg {
+    pub side: Side,
+    pub rescue_delay: u64,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// factory notified of the revealed secret on withdrawal
+    #[serde(default)]
+    pub factory: Option<Addr>,
+    /// send funds beyond `tokens` back to the sender instead of failing
+    #[serde(default)]
+    pub refund_surplus: bool,
+    /// receiver of a cross-chain order, paid instead of the maker on a `Dst` withdrawal
+    #[serde(default)]
+    pub receiver: Option<String>,
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(EscrowResponse)]
+    Escrow { order_hash: String },
+    /// Escrows ordered by order hash
+    #[returns(EscrowsResponse)]
+    Escrows {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Timelock stage of the escrow at the current block time
+    #[returns(CurrentStageResponse)]
+    CurrentStage { order_hash: String },
+    /// Messages `address` could call on the escrow at the current block time,
+    /// withdrawals still need the right secret
+    #[returns(AllowedActionsResponse)]
+    AllowedActions { order_hash: String, address: String },
+    /// The hashlock preimage, once the escrow has been withdrawn
+    #[returns(RevealedSecretResponse)]
+    RevealedSecret { order_hash: String },
+    /// Addresses allowed to call `Create`
+    #[returns(CreatorsResponse)]
+    Creators {},
+}
+
+#[cw_serde]
+pub struct EscrowResponse {
+    pub side: Side,
+    pub status: EscrowStatus,
+    pub deployed_at: u64,
+    pub rescue_delay: u64,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
+    pub factory: Option<Addr>,
+    pub receiver: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct EscrowsResponse {
+    pub escrows: Vec<EscrowResponse>,
+}
+
+#[cw_serde]
+pub struct CurrentStageResponse {
+    pub stage: Stage,
+    /// unix timestamp (in sec) the stage started at
+    pub starts_at: u64,
+    /// unix timestamp (in sec) the next stage starts at, `None` in the last stage
+    pub ends_at: Option<u64>,
+    pub seconds_until_next: Option<u64>,
+}
+
+#[cw_serde]
+pub struct AllowedActionsResponse {
+    pub actions: Vec<Action>,
+}
+
+#[cw_serde]
+pub struct RevealedSecretResponse {
+    pub secret: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct CreatorsResponse {
+    pub creators: Vec<Addr>,
+}
+
+/// Subset of the escrow factory's `ExecuteMsg` the vault calls into
+#[cw_serde]
+pub enum FactoryExecuteMsg {
+    RecordSecret { order_hash: String, secret: Binary },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
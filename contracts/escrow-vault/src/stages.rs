@@ -0,0 +1,218 @@
+use cosmwasm_std::Addr;
+
+use cosmic_common::stages;
+
+use crate::state::{Action, Escrow, Stage};
+use crate::ContractError;
+
+/// Decides whether `caller` may run `action` on `escrow` at `now`, returning
+/// the current stage. Applies the `escrow_src` or `escrow_dst` rules depending
+/// on the escrow's side.
+pub fn authorize(
+    escrow: &Escrow,
+    caller: &Addr,
+    action: Action,
+    now: u64,
+) -> Result<Stage, ContractError> {
+    Ok(stages::authorize(&escrow.terms(), caller, action, now)?)
+}
+
+/// Actions `caller` could run on `escrow` at `now`, in `Action::ALL` order
+pub fn allowed_actions(escrow: &Escrow, caller: &Addr, now: u64) -> Vec<Action> {
+    stages::allowed_actions(&escrow.terms(), caller, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{EscrowStatus, Immutables, Side, Timelocks};
+    use cosmic_common::stages::StageError;
+    use cosmwasm_std::testing::MockApi;
+
+    const DEPLOYED_AT: u64 = 1_000;
+
+    /// Outcome of an `authorize` call
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Expect {
+        Allowed,
+        OnlyTaker,
+        NotInWithdrawal,
+        NotInCancellation,
+        RescueLocked,
+        Unsupported,
+    }
+    use Expect::{
+        Allowed as Y, NotInCancellation as C, NotInWithdrawal as W, OnlyTaker as T,
+        RescueLocked as R, Unsupported as U,
+    };
+
+    /// Columns: withdraw, public_withdraw, cancel, public_cancel and
+    /// rescue_funds, each called by the taker and by a third party. These are
+    /// the `escrow_src` and `escrow_dst` matrices, sampled at the first second
+    /// of every stage, before the rescue delay ends.
+    const MATRIX: [(Side, Stage, u64, [Expect; 10]); 9] = [
+        (
+            Side::Src,
+            Stage::FinalityLock,
+            0,
+            [W, T, W, W, C, T, C, C, U, U],
+        ),
+        (
+            Side::Src,
+            Stage::PrivateWithdrawal,
+            10,
+            [Y, T, W, W, C, T, C, C, U, U],
+        ),
+        (
+            Side::Src,
+            Stage::PublicWithdrawal,
+            100,
+            [Y, T, Y, Y, C, T, C, C, U, U],
+        ),
+        (
+            Side::Src,
+            Stage::PrivateCancellation,
+            300,
+            [W, T, W, W, Y, T, C, C, U, U],
+        ),
+        (
+            Side::Src,
+            Stage::PublicCancellation,
+            400,
+            [W, T, W, W, Y, T, Y, Y, U, U],
+        ),
+        (
+            Side::Dst,
+            Stage::FinalityLock,
+            0,
+            [W, T, W, W, C, T, U, U, R, T],
+        ),
+        (
+            Side::Dst,
+            Stage::PrivateWithdrawal,
+            10,
+            [Y, T, W, W, C, T, U, U, R, T],
+        ),
+        (
+            Side::Dst,
+            Stage::PublicWithdrawal,
+            100,
+            [Y, T, Y, Y, C, T, U, U, R, T],
+        ),
+        (
+            Side::Dst,
+            Stage::Cancellation,
+            200,
+            [W, T, W, W, Y, T, U, U, R, T],
+        ),
+    ];
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn escrow(side: Side) -> Escrow {
+        Escrow {
+            side,
+            immutables: Immutables {
+                order_hash: vec![0xab; 32],
+                hashlock: vec![0xcd; 32],
+                maker: addr("maker"),
+                taker: addr("taker"),
+                tokens: vec![],
+                timelocks: Timelocks {
+                    dest_withdrawal: 10,
+                    dest_public_withdrawal: 100,
+                    dest_cancellation: 200,
+                    src_withdrawal: 10,
+                    src_public_withdrawal: 100,
+                    src_cancellation: 300,
+                    src_public_cancellation: 400,
+                },
+                hash_algorithm: Default::default(),
+                receiver: None,
+            },
+            deployed_at: DEPLOYED_AT,
+            rescue_delay: 1_000,
+            factory: None,
+            status: EscrowStatus::Active,
+        }
+    }
+
+    fn outcome(result: Result<Stage, ContractError>) -> Expect {
+        match result {
+            Ok(_) => Expect::Allowed,
+            Err(ContractError::Stage(err)) => match err {
+                StageError::OnlyTaker => Expect::OnlyTaker,
+                StageError::NotInWithdrawalStage { .. } => Expect::NotInWithdrawal,
+                StageError::NotInCancellationStage { .. } => Expect::NotInCancellation,
+                StageError::RescueTimeLimit => Expect::RescueLocked,
+                StageError::NotSupported { .. } => Expect::Unsupported,
+                err => panic!("unexpected error {err}"),
+            },
+            Err(err) => panic!("unexpected error {err}"),
+        }
+    }
+
+    #[test]
+    fn every_side_stage_action_and_caller() {
+        let callers = [addr("taker"), addr("anyone")];
+
+        for (side, stage, offset, expected) in MATRIX {
+            let escrow = escrow(side);
+            let now = DEPLOYED_AT + offset;
+            assert_eq!(
+                escrow.immutables.timelocks.stage(side, DEPLOYED_AT, now),
+                stage
+            );
+
+            let cells = Action::ALL
+                .iter()
+                .flat_map(|action| callers.iter().map(move |caller| (*action, caller)));
+            for ((action, caller), expected) in cells.zip(expected) {
+                assert_eq!(
+                    outcome(authorize(&escrow, caller, action, now)),
+                    expected,
+                    "{} by {caller} on {} at {}",
+                    action.as_str(),
+                    side.as_str(),
+                    stage.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn factory_withdraws_and_cancels_destination_escrows_for_the_taker() {
+        let factory = addr("factory");
+        let deployed = Escrow {
+            factory: Some(factory.clone()),
+            ..escrow(Side::Dst)
+        };
+        let now = DEPLOYED_AT + 200;
+        assert_eq!(allowed_actions(&deployed, &factory, now), vec![Action::Cancel]);
+        let now = DEPLOYED_AT + 10;
+        assert_eq!(allowed_actions(&deployed, &factory, now), vec![Action::Withdraw]);
+
+        // source escrows only take them from the taker
+        let src = Escrow {
+            factory: Some(factory.clone()),
+            ..escrow(Side::Src)
+        };
+        assert_eq!(allowed_actions(&src, &factory, now), vec![]);
+    }
+
+    #[test]
+    fn settled_escrows_refuse_everything() {
+        let mut escrow = escrow(Side::Dst);
+        escrow.status = EscrowStatus::Withdrawn;
+        let now = DEPLOYED_AT + 5_000;
+
+        let err = authorize(&escrow, &addr("taker"), Action::RescueFunds, now).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Stage(StageError::AlreadySettled { status }) if status == "withdrawn"
+        ));
+        assert_eq!(allowed_actions(&escrow, &addr("taker"), now), vec![]);
+    }
+}
@@ -0,0 +1,70 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Binary, Coin};
+use cw_storage_plus::{Item, Map};
+
+use cosmic_common::stages::EscrowTerms;
+
+pub use cosmic_common::hashlock::HashAlgorithm;
+pub use cosmic_common::stages::{Action, EscrowStatus};
+pub use cosmic_common::timelocks::{Side, Stage, Timelocks};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Immutables {
+    pub order_hash: Vec<u8>,
+    pub hashlock: Vec<u8>,
+    pub maker: Addr,
+    pub taker: Addr,
+    /// basket of coins held for the escrow, paid out in full
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
+    /// paid instead of the maker on a `Dst` withdrawal
+    #[serde(default)]
+    pub receiver: Option<Addr>,
+}
+
+/// One escrow held by the vault, what a standalone escrow keeps in its own storage
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Escrow {
+    pub side: Side,
+    pub immutables: Immutables,
+    pub deployed_at: u64,
+    pub rescue_delay: u64,
+    /// factory notified of the revealed secret on withdrawal
+    pub factory: Option<Addr>,
+    pub status: EscrowStatus,
+}
+
+impl Escrow {
+    pub fn terms(&self) -> EscrowTerms<'_> {
+        EscrowTerms {
+            side: self.side,
+            status: self.status,
+            taker: &self.immutables.taker,
+            factory: self.factory.as_ref(),
+            timelocks: &self.immutables.timelocks,
+            deployed_at: self.deployed_at,
+            rescue_delay: self.rescue_delay,
+        }
+    }
+
+    /// Who receives the tokens when `action` runs
+    pub fn recipient(&self, action: Action) -> &Addr {
+        let immutables = &self.immutables;
+        match (self.side, action.is_withdrawal()) {
+            (Side::Src, true) | (Side::Dst, false) => &immutables.taker,
+            (Side::Dst, true) => immutables.receiver.as_ref().unwrap_or(&immutables.maker),
+            (Side::Src, false) => &immutables.maker,
+        }
+    }
+}
+
+/// Contracts allowed to create escrows. Order hashes are first come first
+/// served, so an open `Create` would let anyone squat on an upcoming order.
+pub const CREATORS: Item<Vec<Addr>> = Item::new("creators");
+/// hex order hash -> escrow
+pub const ESCROWS: Map<String, Escrow> = Map::new("escrows");
+/// hex order hash -> preimage of the hashlock, stored once the escrow has been withdrawn
+pub const REVEALED_SECRETS: Map<String, Binary> = Map::new("revealed_secrets");
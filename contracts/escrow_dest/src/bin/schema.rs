@@ -1,12 +0,0 @@
-use escrow_dest::contract::sv::{ContractExecMsg, ContractQueryMsg, InstantiateMsg};
-use sylvia::cw_schema::write_api;
-use sylvia::cw_std::Empty;
-
-fn main() {
-    write_api! {
-        crate_name: sylvia::cw_schema,
-        instantiate: InstantiateMsg,
-        execute: ContractExecMsg<Empty, Empty>,
-        query: ContractQueryMsg<Empty, Empty>,
-    }
-}
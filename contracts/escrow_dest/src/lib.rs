@@ -1,4 +0,0 @@
-pub mod contract;
-pub mod error;
-pub mod states;
-pub mod utils;
\ No newline at end of file
@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use escrow_dst::msg::{ExecuteMsg, InstantiateMsgData, QueryMsg};
+use escrow_dst::msg::{ExecuteMsg, InstantiateMsgData, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsgData,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
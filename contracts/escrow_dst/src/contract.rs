@@ -1,18 +1,36 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
-// use cw2::set_contract_version;
+use cosmwasm_std::{
+    to_json_binary, BankMsg, Binary, Deps, DepsMut, Env, Event, MessageInfo, Reply, Response,
+    StdError, StdResult, SubMsgResult,
+};
+use cw2::set_contract_version;
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::helpers::{only_after, only_before, only_valid_secret};
-use crate::msg::{ExecuteMsg, InstantiateMsgData, QueryMsg, WithdrawMsg};
-use crate::state::{Immutables, State, IMMUTABLES, STATE};
+use cosmic_common::events::{EscrowEvent, EscrowEventKind};
+use crate::helpers::{
+    check_funds, only_valid_secret, validate_basket,
+};
+use crate::migrations::{
+    ensure_from_older_version, is_escrow_dest, migrate_from_escrow_dest, migrate_immutables,
+    ESCROW_DEST_NAME,
+};
+use crate::msg::{
+    AllowedActionsResponse, BalanceResponse, ConfigResponse, CurrentStageResponse, ExecuteMsg,
+    GetOrderDetailsResponse, InstantiateMsgData, MigrateMsg, QueryMsg, RevealedSecretResponse,
+    StatusResponse, TimelocksResponse, WithdrawMsg,
+};
+use crate::stages::{self, authorize};
+use crate::state::{
+    settled_status, Action, EscrowStatus, Immutables, Side, State, IMMUTABLES, REVEALED_SECRET,
+    SETTLED, STATE,
+};
 
-/*
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:escrow_dst";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-*/
+pub const RECORD_SECRET_REPLY: u64 = 1;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -21,41 +39,70 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsgData,
 ) -> Result<Response, ContractError> {
-    let mut ok = false;
-    for asset in info.funds {
-        if asset.denom == msg.token.denom && asset.amount == msg.token.amount {
-            ok = true;
-        }
-    }
-    if !ok {
-        return Err(ContractError::UnmatchedDenomOrAmount);
-    }
+    validate_basket(&msg.tokens)?;
+    let surplus = check_funds(&info.funds, &msg.tokens, msg.refund_surplus)?;
 
     let hashlock = hex::decode(&msg.hashlock).map_err(|e| StdError::generic_err(e.to_string()))?;
 
     let order_hash =
         hex::decode(&msg.order_hash).map_err(|e| StdError::generic_err(e.to_string()))?;
 
-    STATE.save(
-        deps.storage,
-        &State {
-            deployed_at: env.block.time.seconds(),
-            rescue_delay: msg.rescue_delay,
-        },
-    )?;
-    IMMUTABLES.save(
-        deps.storage,
-        &Immutables {
-            hashlock,
-            order_hash,
-            maker: msg.maker,
-            taker: msg.taker,
-            timelocks: msg.timelocks,
-            token: msg.token,
-        },
-    )?;
-
-    Ok(Response::new())
+    let state = State {
+        deployed_at: env.block.time.seconds(),
+        rescue_delay: msg.rescue_delay,
+        factory: msg.factory,
+    };
+    let immutables = Immutables {
+        hashlock,
+        order_hash,
+        maker: msg.maker,
+        taker: msg.taker,
+        timelocks: msg.timelocks,
+        tokens: msg.tokens,
+        hash_algorithm: msg.hash_algorithm,
+        receiver: msg
+            .receiver
+            .map(|receiver| deps.api.addr_validate(&receiver))
+            .transpose()?,
+    };
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    STATE.save(deps.storage, &state)?;
+    IMMUTABLES.save(deps.storage, &immutables)?;
+
+    let event = escrow_event(EscrowEventKind::Created, &immutables, &state, &env, None);
+    let mut res = Response::new().add_event(event);
+    if !surplus.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: surplus,
+        });
+    }
+    Ok(res)
+}
+
+fn escrow_event(
+    kind: EscrowEventKind,
+    immutables: &Immutables,
+    state: &State,
+    env: &Env,
+    secret: Option<String>,
+) -> Event {
+    let stage = immutables
+        .timelocks
+        .stage(Side::Dst, state.deployed_at, env.block.time.seconds());
+
+    EscrowEvent {
+        kind,
+        order_hash: hex::encode(&immutables.order_hash),
+        hashlock: hex::encode(&immutables.hashlock),
+        maker: immutables.maker.clone(),
+        taker: immutables.taker.clone(),
+        amount: immutables.tokens.clone(),
+        stage: stage.as_str().to_string(),
+        secret,
+    }
+    .into()
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -75,7 +122,9 @@ pub fn execute(
 
 pub mod execute {
 
-    use cosmwasm_std::{Addr, BankMsg, Coin, SubMsg};
+    use cosmwasm_std::{Addr, BankMsg, Coin, SubMsg, WasmMsg};
+
+    use crate::msg::FactoryExecuteMsg;
 
     use super::*;
 
@@ -87,29 +136,14 @@ pub mod execute {
     ) -> Result<Response, ContractError> {
         let state: State = STATE.load(deps.storage)?;
         let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::Withdraw, now)?;
 
-        if info.sender != immutables.taker {
-            return Err(ContractError::OnlyTaker);
-        }
+        only_valid_secret(&msg.secret, &immutables.hashlock, immutables.hash_algorithm)?;
 
-        let current_time_in_secs = env.block.time.seconds();
-        if only_after(
-            current_time_in_secs,
-            state.deployed_at + immutables.timelocks.dest_withdrawal,
-        ) {
-            return Err(ContractError::DestWithrawTimeLimit);
-        }
-
-        if only_before(
-            current_time_in_secs,
-            state.deployed_at + immutables.timelocks.dest_cancellation,
-        ) {
-            return Err(ContractError::DestCancelTimeLimit);
-        }
-
-        only_valid_secret(msg.secret, immutables.hashlock)?;
-
-        Ok(Response::new().add_submessage(send_bank_msg(immutables.maker, immutables.token)))
+        reveal_secret(deps, &immutables, &state, &env, msg.secret)
+            .map(|res| res.add_submessage(send_bank_msg(immutables.payee().clone(), immutables.tokens)))
     }
 
     pub fn public_withdraw(
@@ -120,80 +154,801 @@ pub mod execute {
     ) -> Result<Response, ContractError> {
         let state: State = STATE.load(deps.storage)?;
         let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
-
-        if only_after(
-            current_time_in_secs,
-            state.deployed_at + immutables.timelocks.dest_public_withdrawal,
-        ) {
-            return Err(ContractError::DestWithrawTimeLimit);
-        }
-
-        if only_before(
-            current_time_in_secs,
-            state.deployed_at + immutables.timelocks.dest_cancellation,
-        ) {
-            return Err(ContractError::DestCancelTimeLimit);
-        }
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::PublicWithdraw, now)?;
 
         //Check secret hash
-        only_valid_secret(msg.secret, immutables.hashlock)?;
+        only_valid_secret(&msg.secret, &immutables.hashlock, immutables.hash_algorithm)?;
 
-        Ok(Response::new().add_submessage(send_bank_msg(immutables.maker, immutables.token)))
+        reveal_secret(deps, &immutables, &state, &env, msg.secret)
+            .map(|res| res.add_submessage(send_bank_msg(immutables.payee().clone(), immutables.tokens)))
     }
-   pub fn cancel(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+
+    pub fn cancel(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
         let state: State = STATE.load(deps.storage)?;
         let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::Cancel, now)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Cancelled)?;
+
+        let event = escrow_event(EscrowEventKind::Cancelled, &immutables, &state, &env, None);
+        Ok(Response::new()
+            .add_event(event)
+            .add_submessage(send_bank_msg(immutables.taker, immutables.tokens)))
+    }
 
-        if info.sender != immutables.taker {
-            return Err(ContractError::OnlyTaker);
-        }
+    pub fn rescue_funds(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let state: State = STATE.load(deps.storage)?;
+        let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::RescueFunds, now)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Rescued)?;
+
+        let event = escrow_event(EscrowEventKind::FundsRescued, &immutables, &state, &env, None);
+        Ok(Response::new()
+            .add_event(event)
+            .add_submessage(send_bank_msg(immutables.taker, immutables.tokens)))
+    }
 
-        if only_after(
-            current_time_in_secs,
-            state.deployed_at + immutables.timelocks.dest_cancellation,
-        ) {
-            return Err(ContractError::DestCancelTimeLimit);
+    /// Store the preimage, publish it and report it to the deploying factory.
+    /// A failing factory call must not block the withdrawal, hence `reply_on_error`.
+    fn reveal_secret(
+        deps: DepsMut,
+        immutables: &Immutables,
+        state: &State,
+        env: &Env,
+        secret: Binary,
+    ) -> Result<Response, ContractError> {
+        REVEALED_SECRET.save(deps.storage, &secret)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Withdrawn)?;
+        let secret_hex = hex::encode(&secret);
+
+        let withdrawn = escrow_event(
+            EscrowEventKind::Withdrawn,
+            immutables,
+            state,
+            env,
+            Some(secret_hex.clone()),
+        );
+        let revealed = escrow_event(
+            EscrowEventKind::SecretRevealed,
+            immutables,
+            state,
+            env,
+            Some(secret_hex),
+        );
+        let mut res = Response::new().add_events([withdrawn, revealed]);
+
+        if let Some(factory) = &state.factory {
+            let msg = WasmMsg::Execute {
+                contract_addr: factory.to_string(),
+                msg: to_json_binary(&FactoryExecuteMsg::RecordSecret {
+                    order_hash: hex::encode(&immutables.order_hash),
+                    secret,
+                })?,
+                funds: vec![],
+            };
+            res = res.add_submessage(SubMsg::reply_on_error(msg, RECORD_SECRET_REPLY));
         }
 
-        Ok(Response::new().add_submessage(send_bank_msg(immutables.taker, immutables.token)))
+        Ok(res)
     }
 
-    pub fn rescue_funds(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> { 
-        let state: State = STATE.load(deps.storage)?;
-        let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
+    fn send_bank_msg(to: Addr, amount: Vec<Coin>) -> SubMsg {
+        let msg = BankMsg::Send {
+            to_address: to.into(),
+            amount,
+        };
 
-        if info.sender != immutables.taker {
-            return Err(ContractError::OnlyTaker);
-        }
+        SubMsg::reply_never(msg)
+    }
+}
 
-        if only_after(current_time_in_secs, state.deployed_at+  state.rescue_delay) {
-            return Err(ContractError::RescueTimeLimit);
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::OrderDetails {} => to_json_binary(&query::order_details(deps)?),
+        QueryMsg::Timelocks {} => to_json_binary(&query::timelocks(deps)?),
+        QueryMsg::Balance {} => to_json_binary(&query::balance(deps, env)?),
+        QueryMsg::Status {} => to_json_binary(&query::status(deps)?),
+        QueryMsg::Config {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::RevealedSecret {} => to_json_binary(&query::revealed_secret(deps)?),
+        QueryMsg::CurrentStage {} => to_json_binary(&query::current_stage(deps, env)?),
+        QueryMsg::AllowedActions { address } => {
+            to_json_binary(&query::allowed_actions(deps, env, address)?)
         }
+    }
+}
 
+pub mod query {
+    use super::*;
 
+    pub fn order_details(deps: Deps) -> StdResult<GetOrderDetailsResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        Ok(GetOrderDetailsResponse {
+            deployed_at: state.deployed_at,
+            funded_at: Some(state.deployed_at),
+            rescue_delay: state.rescue_delay,
+            order_hash: hex::encode(immutables.order_hash),
+            hashlock: hex::encode(immutables.hashlock),
+            maker: immutables.maker,
+            taker: immutables.taker,
+            tokens: immutables.tokens,
+            timelocks: immutables.timelocks,
+            hash_algorithm: immutables.hash_algorithm,
+            receiver: immutables.receiver,
+        })
+    }
 
-        Ok(Response::new().add_submessage(send_bank_msg(immutables.taker, immutables.token)))
-
+    pub fn timelocks(deps: Deps) -> StdResult<TimelocksResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        Ok(TimelocksResponse {
+            deployed_at: state.deployed_at,
+            timelocks: immutables.timelocks,
+        })
     }
 
+    pub fn balance(deps: Deps, env: Env) -> StdResult<BalanceResponse> {
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let balance = immutables
+            .tokens
+            .iter()
+            .map(|token| deps.querier.query_balance(&env.contract.address, &token.denom))
+            .collect::<StdResult<_>>()?;
+        Ok(BalanceResponse { balance })
+    }
 
-    fn send_bank_msg(to: Addr, amount: Coin) -> SubMsg {
-        let msg = BankMsg::Send {
-            to_address: to.into(),
-            amount: vec![amount],
+    pub fn status(deps: Deps) -> StdResult<StatusResponse> {
+        let status = match settled_status(deps.storage)? {
+            Some(status) => status,
+            None => EscrowStatus::Active,
         };
+        Ok(StatusResponse { status })
+    }
 
-        SubMsg::reply_never(msg)
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let version = cw2::get_contract_version(deps.storage)?;
+        Ok(ConfigResponse {
+            contract: version.contract,
+            version: version.version,
+            rescue_delay: state.rescue_delay,
+            hash_algorithm: immutables.hash_algorithm,
+            factory: state.factory,
+        })
+    }
+
+    pub fn revealed_secret(deps: Deps) -> StdResult<RevealedSecretResponse> {
+        Ok(RevealedSecretResponse {
+            secret: REVEALED_SECRET.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn current_stage(deps: Deps, env: Env) -> StdResult<CurrentStageResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let now = env.block.time.seconds();
+
+        let stage = immutables.timelocks.stage(Side::Dst, state.deployed_at, now);
+        let (start, end) = immutables.timelocks.stage_bounds(Side::Dst, stage);
+        let ends_at = end.map(|end| state.deployed_at + end);
+        Ok(CurrentStageResponse {
+            stage,
+            starts_at: state.deployed_at + start,
+            ends_at,
+            seconds_until_next: ends_at.map(|ends_at| ends_at.saturating_sub(now)),
+        })
+    }
+
+    pub fn allowed_actions(
+        deps: Deps,
+        env: Env,
+        address: String,
+    ) -> StdResult<AllowedActionsResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let address = deps.api.addr_validate(&address)?;
+        Ok(AllowedActionsResponse {
+            actions: stages::allowed_actions(
+                &immutables,
+                &state,
+                settled_status(deps.storage)?,
+                &address,
+                env.block.time.seconds(),
+            ),
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        // the secret is still published through events and `RevealedSecret`
+        RECORD_SECRET_REPLY => {
+            let mut res = Response::new().add_attribute("record_secret", "failed");
+            if let SubMsgResult::Err(reason) = msg.result {
+                res = res.add_attribute("record_secret_error", reason);
+            }
+            Ok(res)
+        }
+        _ => Ok(Response::new()),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    unimplemented!()
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // escrows deployed from the sylvia `escrow_dest` code are taken over in place
+    if is_escrow_dest(deps.storage) {
+        let from_version = migrate_from_escrow_dest(deps.storage)?;
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        return Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_contract", ESCROW_DEST_NAME)
+            .add_attribute("from_version", from_version.to_string())
+            .add_attribute("to_version", CONTRACT_VERSION));
+    }
+
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // v0.2.0 holds a basket of coins instead of a single token
+    if from_version < Version::new(0, 2, 0) {
+        migrate_immutables(deps.storage)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use cosmic_common::events::parse_escrow_events;
+    use cosmic_common::funds::FundsError;
+    use cosmic_common::stages::StageError;
+    use cosmic_common::hashlock::SecretError;
+    use crate::state::{Stage, Timelocks};
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{coin, from_json, Addr, OwnedDeps};
+    use sha3::{Digest, Keccak256};
+
+    const SECRET: &[u8] = b"secret";
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn instantiate_msg() -> InstantiateMsgData {
+        InstantiateMsgData {
+            rescue_delay: 1000,
+            order_hash: "ab".repeat(32),
+            hashlock: hex::encode(Keccak256::digest(SECRET)),
+            maker: addr("maker"),
+            taker: addr("taker"),
+            tokens: vec![coin(100, "uusdc")],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 100,
+                dest_cancellation: 200,
+                src_cancellation: 300,
+                src_withdrawal: 10,
+                src_public_withdrawal: 100,
+                src_public_cancellation: 400,
+            },
+            hash_algorithm: Default::default(),
+            factory: Some(addr("factory")),
+            refund_surplus: false,
+            receiver: None,
+        }
+    }
+
+    #[test]
+    fn lifecycle_emits_escrow_events() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = message_info(&addr("taker"), &[coin(100, "uusdc")]);
+
+        let res = instantiate(deps.as_mut(), env.clone(), taker.clone(), instantiate_msg()).unwrap();
+        let created = parse_escrow_events(&res.events).unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].kind, EscrowEventKind::Created);
+        assert_eq!(created[0].stage, "finality_lock");
+        assert_eq!(created[0].amount, vec![coin(100, "uusdc")]);
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let msg = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+        let res = execute(deps.as_mut(), env, taker, msg).unwrap();
+        let withdrawn = parse_escrow_events(&res.events).unwrap();
+        assert_eq!(withdrawn.len(), 2);
+        assert_eq!(withdrawn[0].kind, EscrowEventKind::Withdrawn);
+        assert_eq!(withdrawn[0].stage, "private_withdrawal");
+        assert_eq!(withdrawn[0].order_hash, "ab".repeat(32));
+        assert_eq!(withdrawn[0].secret, Some(hex::encode(SECRET)));
+        assert_eq!(withdrawn[1].kind, EscrowEventKind::SecretRevealed);
+        assert_eq!(withdrawn[1].secret, Some(hex::encode(SECRET)));
+    }
+
+    #[test]
+    fn sha256_hashlock_with_raw_secret() {
+        use crate::state::HashAlgorithm;
+        use sha2::Sha256;
+
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = message_info(&addr("taker"), &[coin(100, "uusdc")]);
+        let secret = [7u8; 32];
+
+        let msg = InstantiateMsgData {
+            hashlock: hex::encode(Sha256::digest(secret)),
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(20);
+        // the keccak256 preimage check no longer applies
+        let keccak_only = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), keccak_only).unwrap_err();
+        assert!(matches!(err, ContractError::Secret(SecretError::InvalidSecret)));
+
+        let msg = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(secret),
+        });
+        execute(deps.as_mut(), env, taker, msg).unwrap();
+    }
+
+    #[test]
+    fn withdrawal_reports_secret_to_factory() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = message_info(&addr("taker"), &[coin(100, "uusdc")]);
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), instantiate_msg()).unwrap();
+
+        let revealed = query::revealed_secret(deps.as_ref()).unwrap();
+        assert_eq!(revealed.secret, None);
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let msg = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+        let res = execute(deps.as_mut(), env, taker, msg).unwrap();
+
+        let revealed = query::revealed_secret(deps.as_ref()).unwrap();
+        assert_eq!(revealed.secret, Some(Binary::from(SECRET)));
+
+        let record = res
+            .messages
+            .iter()
+            .find(|m| m.id == RECORD_SECRET_REPLY)
+            .unwrap();
+        assert_eq!(record.reply_on, cosmwasm_std::ReplyOn::Error);
+        let expected = cosmwasm_std::WasmMsg::Execute {
+            contract_addr: addr("factory").to_string(),
+            msg: to_json_binary(&crate::msg::FactoryExecuteMsg::RecordSecret {
+                order_hash: "ab".repeat(32),
+                secret: Binary::from(SECRET),
+            })
+            .unwrap(),
+            funds: vec![],
+        };
+        assert_eq!(record.msg, expected.into());
+    }
+
+    #[test]
+    fn migrate_escrow_without_version_info() {
+        let mut deps = mock_dependencies();
+        crate::migrations::save_legacy_immutables(
+            deps.as_mut().storage,
+            coin(100, "uusdc"),
+            instantiate_msg().timelocks,
+        )
+        .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert!(res.attributes.contains(&cosmwasm_std::attr(
+            "from_version",
+            crate::migrations::LEGACY_VERSION
+        )));
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        let immutables = IMMUTABLES.load(deps.as_ref().storage).unwrap();
+        assert_eq!(immutables.tokens, vec![coin(100, "uusdc")]);
+    }
+
+    #[test]
+    fn basket_must_be_paid_exactly() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsgData {
+            tokens: vec![coin(100, "uusdc"), coin(5, "uatom")],
+            ..instantiate_msg()
+        };
+
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("taker"), &[coin(100, "uusdc"), coin(4, "uatom")]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Funds(FundsError::InsufficientFunds { denom, required, received })
+                if denom == "uatom" && required.u128() == 5 && received.u128() == 4
+        ));
+
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(
+                &addr("taker"),
+                &[coin(100, "uusdc"), coin(5, "uatom"), coin(1, "stake")],
+            ),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::ExtraDenom { denom }) if denom == "stake"));
+
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("taker"), &[coin(101, "uusdc"), coin(5, "uatom")]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::ExcessFunds { denom, .. }) if denom == "uusdc"));
+
+        let mut env = mock_env();
+        let taker = message_info(&addr("taker"), &[coin(5, "uatom"), coin(100, "uusdc")]);
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let withdraw = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+        let res = execute(deps.as_mut(), env, taker, withdraw).unwrap();
+        let payout = cosmwasm_std::BankMsg::Send {
+            to_address: addr("maker").to_string(),
+            amount: vec![coin(100, "uusdc"), coin(5, "uatom")],
+        };
+        assert!(res.messages.iter().any(|m| m.msg == payout.clone().into()));
+    }
+
+    #[test]
+    fn surplus_is_refunded_when_opted_in() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsgData {
+            refund_surplus: true,
+            ..instantiate_msg()
+        };
+
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("taker"), &[coin(150, "uusdc"), coin(1, "stake")]),
+            msg,
+        )
+        .unwrap();
+        let refund = cosmwasm_std::BankMsg::Send {
+            to_address: addr("taker").to_string(),
+            amount: vec![coin(1, "stake"), coin(50, "uusdc")],
+        };
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, refund.into());
+    }
+
+    #[test]
+    fn empty_or_duplicate_basket_is_rejected() {
+        let mut deps = mock_dependencies();
+        for tokens in [vec![], vec![coin(1, "uusdc"), coin(1, "uusdc")]] {
+            let err = instantiate(
+                deps.as_mut(),
+                mock_env(),
+                message_info(&addr("taker"), &[coin(2, "uusdc")]),
+                InstantiateMsgData {
+                    tokens,
+                    ..instantiate_msg()
+                },
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Funds(FundsError::InvalidBasket { .. })));
+        }
+    }
+
+    fn deployed(env: &Env) -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, MessageInfo) {
+        let mut deps = mock_dependencies();
+        let taker = message_info(&addr("taker"), &[coin(100, "uusdc")]);
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), instantiate_msg()).unwrap();
+        (deps, taker)
+    }
+
+    fn withdraw_msg(secret: &[u8]) -> ExecuteMsg {
+        ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(secret),
+        })
+    }
+
+    #[test]
+    fn withdraw_only_by_taker_with_the_secret() {
+        let mut env = mock_env();
+        let (mut deps, taker) = deployed(&env);
+
+        // finality lock
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw_msg(SECRET))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotInWithdrawalStage { .. })));
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let maker = message_info(&addr("maker"), &[]);
+        let err = execute(deps.as_mut(), env.clone(), maker, withdraw_msg(SECRET)).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::OnlyTaker)));
+
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw_msg(b"wrong"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Secret(SecretError::InvalidSecret)));
+
+        execute(deps.as_mut(), env.clone(), taker.clone(), withdraw_msg(SECRET)).unwrap();
+        assert_eq!(query::status(deps.as_ref()).unwrap().status, EscrowStatus::Withdrawn);
+
+        // withdrawals close with the cancellation stage
+        let (mut deps, taker) = deployed(&mock_env());
+        env.block.time = mock_env().block.time.plus_seconds(200);
+        let err = execute(deps.as_mut(), env, taker, withdraw_msg(SECRET)).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotInWithdrawalStage { .. })));
+    }
+
+    #[test]
+    fn anyone_withdraws_publicly_for_the_maker() {
+        let mut env = mock_env();
+        let (mut deps, _) = deployed(&env);
+        let anyone = message_info(&addr("anyone"), &[]);
+        let msg = ExecuteMsg::PublicWithdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let err = execute(deps.as_mut(), env.clone(), anyone.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotInWithdrawalStage { .. })));
+
+        env.block.time = env.block.time.plus_seconds(80);
+        let res = execute(deps.as_mut(), env, anyone, msg).unwrap();
+        let payout = cosmwasm_std::BankMsg::Send {
+            to_address: addr("maker").to_string(),
+            amount: vec![coin(100, "uusdc")],
+        };
+        assert!(res.messages.iter().any(|m| m.msg == payout.clone().into()));
+    }
+
+    #[test]
+    fn withdrawals_pay_the_receiver() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        let taker = message_info(&addr("taker"), &[coin(100, "uusdc")]);
+        let msg = InstantiateMsgData {
+            receiver: Some(addr("receiver").to_string()),
+            ..instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(20);
+        let withdraw = ExecuteMsg::Withdraw(WithdrawMsg {
+            secret: Binary::from(SECRET),
+        });
+        let res = execute(deps.as_mut(), env, taker, withdraw).unwrap();
+        let payout = cosmwasm_std::BankMsg::Send {
+            to_address: addr("receiver").to_string(),
+            amount: vec![coin(100, "uusdc")],
+        };
+        assert!(res.messages.iter().any(|m| m.msg == payout.clone().into()));
+
+        let details = query::order_details(deps.as_ref()).unwrap();
+        assert_eq!(details.receiver, Some(addr("receiver")));
+    }
+
+    #[test]
+    fn taker_cancels_after_the_timelock() {
+        let mut env = mock_env();
+        let (mut deps, taker) = deployed(&env);
+
+        env.block.time = env.block.time.plus_seconds(199);
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), ExecuteMsg::Cancel)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotInCancellationStage { .. })));
+
+        env.block.time = env.block.time.plus_seconds(1);
+        let maker = message_info(&addr("maker"), &[]);
+        let err = execute(deps.as_mut(), env.clone(), maker, ExecuteMsg::Cancel).unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::OnlyTaker)));
+
+        let res = execute(deps.as_mut(), env, taker, ExecuteMsg::Cancel).unwrap();
+        let refund = cosmwasm_std::BankMsg::Send {
+            to_address: addr("taker").to_string(),
+            amount: vec![coin(100, "uusdc")],
+        };
+        assert_eq!(res.messages[0].msg, refund.into());
+    }
+
+    #[test]
+    fn taker_rescues_after_the_delay() {
+        let mut env = mock_env();
+        let (mut deps, taker) = deployed(&env);
+
+        env.block.time = env.block.time.plus_seconds(999);
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), ExecuteMsg::RescueFunds)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::RescueTimeLimit)));
+
+        env.block.time = env.block.time.plus_seconds(1);
+        execute(deps.as_mut(), env, taker, ExecuteMsg::RescueFunds).unwrap();
+    }
+
+    #[test]
+    fn current_stage_and_allowed_actions() {
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        let (deps, _) = deployed(&env);
+        let allowed = |env: &Env, name: &str| {
+            let msg = QueryMsg::AllowedActions { address: addr(name).to_string() };
+            from_json::<AllowedActionsResponse>(query(deps.as_ref(), env.clone(), msg).unwrap())
+                .unwrap()
+                .actions
+        };
+        let current_stage = |env: &Env| -> CurrentStageResponse {
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::CurrentStage {}).unwrap())
+                .unwrap()
+        };
+
+        env.block.time = env.block.time.plus_seconds(150);
+        assert_eq!(
+            current_stage(&env),
+            CurrentStageResponse {
+                stage: Stage::PublicWithdrawal,
+                starts_at: deployed_at + 100,
+                ends_at: Some(deployed_at + 200),
+                seconds_until_next: Some(50),
+            }
+        );
+        assert_eq!(allowed(&env, "taker"), vec![Action::Withdraw, Action::PublicWithdraw]);
+        assert_eq!(allowed(&env, "anyone"), vec![Action::PublicWithdraw]);
+
+        // rescue opens with the delay, long after cancellation
+        env.block.time = env.block.time.plus_seconds(850);
+        let res = current_stage(&env);
+        assert_eq!(res.stage, Stage::Cancellation);
+        assert_eq!((res.starts_at, res.ends_at, res.seconds_until_next), (deployed_at + 200, None, None));
+        assert_eq!(allowed(&env, "taker"), vec![Action::Cancel, Action::RescueFunds]);
+        assert_eq!(allowed(&env, "maker"), vec![]);
+    }
+
+    #[test]
+    fn settled_escrows_allow_nothing() {
+        let mut env = mock_env();
+        let (mut deps, _) = deployed(&env);
+        env.block.time = env.block.time.plus_seconds(1000);
+        let taker = message_info(&addr("taker"), &[]);
+        execute(deps.as_mut(), env.clone(), taker, ExecuteMsg::Cancel).unwrap();
+
+        // rescue would be open by now, but the tokens are gone
+        for name in ["taker", "anyone"] {
+            let msg = QueryMsg::AllowedActions { address: addr(name).to_string() };
+            let res: AllowedActionsResponse =
+                from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            assert_eq!(res.actions, vec![]);
+        }
+    }
+
+    #[test]
+    fn queries_match_the_source_escrow() {
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        let (mut deps, taker) = deployed(&env);
+        deps.querier = MockQuerier::new(&[(env.contract.address.as_str(), &[coin(100, "uusdc")])]);
+
+        let details: GetOrderDetailsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::OrderDetails {}).unwrap())
+                .unwrap();
+        assert_eq!(details.funded_at, Some(deployed_at));
+        assert_eq!(details.order_hash, "ab".repeat(32));
+        assert_eq!(details.tokens, vec![coin(100, "uusdc")]);
+
+        let res: TimelocksResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Timelocks {}).unwrap()).unwrap();
+        assert_eq!(res.deployed_at, deployed_at);
+        assert_eq!(res.timelocks, instantiate_msg().timelocks);
+
+        let res: BalanceResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Balance {}).unwrap()).unwrap();
+        assert_eq!(res.balance, vec![coin(100, "uusdc")]);
+
+        let res: ConfigResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(res.contract, CONTRACT_NAME);
+        assert_eq!(res.rescue_delay, 1000);
+        assert_eq!(res.factory, Some(addr("factory")));
+
+        let status = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            from_json::<StatusResponse>(query(deps.as_ref(), mock_env(), QueryMsg::Status {}).unwrap())
+                .unwrap()
+                .status
+        };
+        assert_eq!(status(&deps), EscrowStatus::Active);
+
+        env.block.time = env.block.time.plus_seconds(200);
+        execute(deps.as_mut(), env, taker, ExecuteMsg::Cancel).unwrap();
+        assert_eq!(status(&deps), EscrowStatus::Cancelled);
+    }
+
+    #[test]
+    fn migrate_from_escrow_dest() {
+        let env = mock_env();
+        let (deployed, _) = deployed(&env);
+        let immutables = IMMUTABLES.load(deployed.as_ref().storage).unwrap();
+        let deployed_at = env.block.time.seconds();
+
+        for version in [Some("0.2.0"), None] {
+            let mut deps = mock_dependencies();
+            crate::migrations::save_escrow_dest(
+                deps.as_mut().storage,
+                version,
+                &immutables,
+                deployed_at,
+                1000,
+            )
+            .unwrap();
+            REVEALED_SECRET
+                .save(deps.as_mut().storage, &Binary::from(SECRET))
+                .unwrap();
+
+            let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+            assert!(res
+                .attributes
+                .contains(&cosmwasm_std::attr("from_contract", ESCROW_DEST_NAME)));
+            assert!(res.attributes.contains(&cosmwasm_std::attr(
+                "from_version",
+                version.unwrap_or(crate::migrations::LEGACY_VERSION)
+            )));
+
+            let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+            assert_eq!(version.contract, CONTRACT_NAME);
+            assert_eq!(version.version, CONTRACT_VERSION);
+            assert_eq!(IMMUTABLES.load(deps.as_ref().storage).unwrap(), immutables);
+            assert_eq!(
+                STATE.load(deps.as_ref().storage).unwrap(),
+                State {
+                    deployed_at,
+                    rescue_delay: 1000,
+                    factory: None,
+                }
+            );
+            assert!(!crate::migrations::is_escrow_dest(deps.as_ref().storage));
+
+            // the secret survives, and the escrow stays withdrawn on the new layout
+            let revealed = query::revealed_secret(deps.as_ref()).unwrap();
+            assert_eq!(revealed.secret, Some(Binary::from(SECRET)));
+            assert_eq!(query::status(deps.as_ref()).unwrap().status, EscrowStatus::Withdrawn);
+            let mut env = env.clone();
+            env.block.time = env.block.time.plus_seconds(1000);
+            let taker = message_info(&addr("taker"), &[]);
+            let err = execute(deps.as_mut(), env, taker, ExecuteMsg::RescueFunds).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::Stage(StageError::AlreadySettled { status }) if status == "withdrawn"
+            ));
+        }
+    }
+}
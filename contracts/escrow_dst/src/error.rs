@@ -1,28 +1,23 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
+use cosmic_common::funds::FundsError;
+use cosmic_common::hashlock::SecretError;
+use cosmic_common::stages::StageError;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Denom/Amount does not match")]
-    UnmatchedDenomOrAmount,
-
-    #[error("Only Taker can call")]
-    OnlyTaker,
-
-    #[error("Invalid Secret")]
-    InvalidSecret,
-
-    #[error("Destinational withdraw time has not passed")]
-    DestWithrawTimeLimit,
+    #[error("{0}")]
+    Secret(#[from] SecretError),
 
-    #[error("Destinational cancellation time has passed")]
-    DestCancelTimeLimit,
+    #[error("{0}")]
+    Stage(#[from] StageError),
 
-    #[error("Rescue time has passed")]
-    RescueTimeLimit
+    #[error("{0}")]
+    Funds(#[from] FundsError),
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -1,9 +1,14 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_json_binary, Addr, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{
+    to_json_binary, Addr, CosmosMsg, CustomQuery, QuerierWrapper, StdResult, WasmMsg,
+};
 
-use crate::msg::ExecuteMsg;
+use crate::msg::{
+    BalanceResponse, ConfigResponse, ExecuteMsg, GetOrderDetailsResponse, QueryMsg,
+    RevealedSecretResponse, StatusResponse, TimelocksResponse,
+};
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -24,33 +29,39 @@ impl CwTemplateContract {
         }
         .into())
     }
-}
-
-
 
-use crate::error::ContractError;
-use sha3::{Digest, Keccak256};
+    // The queries below are answered alike by source and destination escrows
 
-pub fn only_after(  current_time: u64, value: u64) -> bool  {
-     value > current_time      
-}
+    pub fn order_details<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> StdResult<GetOrderDetailsResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::OrderDetails {})
+    }
 
-pub fn only_before(current_time: u64, value: u64) -> bool {
-   value < current_time 
-}
+    pub fn timelocks<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<TimelocksResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Timelocks {})
+    }
 
-pub fn only_valid_secret(
-    secret: String,
-    hashlock: Vec<u8>
-) -> Result<(), ContractError> {
+    pub fn balance<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<BalanceResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Balance {})
+    }
 
-    let mut hasher = Keccak256::new();
-    hasher.update(secret.as_bytes());
-    let computed_hash = hasher.finalize();
+    pub fn status<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<StatusResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Status {})
+    }
 
-    if computed_hash.to_vec() != hashlock {
-        return Err(ContractError::InvalidSecret);
+    pub fn config<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<ConfigResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Config {})
     }
 
-    Ok(())
+    pub fn revealed_secret<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> StdResult<RevealedSecretResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::RevealedSecret {})
+    }
 }
+
+pub use cosmic_common::funds::{check_funds, validate_basket};
+pub use cosmic_common::hashlock::only_valid_secret;
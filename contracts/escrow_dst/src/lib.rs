@@ -1,7 +1,9 @@
 pub mod contract;
 mod error;
 pub mod helpers;
+pub mod migrations;
 pub mod msg;
+pub mod stages;
 pub mod state;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,165 @@
+use cosmwasm_std::{Addr, Coin, StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
+
+use semver::Version;
+
+use crate::state::{HashAlgorithm, Immutables, State, Timelocks, IMMUTABLES, STATE};
+
+/// Version assumed for escrows deployed before cw2 info was stored
+pub const LEGACY_VERSION: &str = "0.1.0";
+
+/// Immutables layout up to v0.1.0, holding a single coin
+#[derive(Serialize, Deserialize)]
+struct LegacyImmutables {
+    order_hash: Vec<u8>,
+    hashlock: Vec<u8>,
+    maker: Addr,
+    taker: Addr,
+    token: Coin,
+    timelocks: Timelocks,
+    #[serde(default)]
+    hash_algorithm: HashAlgorithm,
+}
+
+impl From<LegacyImmutables> for Immutables {
+    fn from(legacy: LegacyImmutables) -> Self {
+        Immutables {
+            order_hash: legacy.order_hash,
+            hashlock: legacy.hashlock,
+            maker: legacy.maker,
+            taker: legacy.taker,
+            tokens: vec![legacy.token],
+            timelocks: legacy.timelocks,
+            hash_algorithm: legacy.hash_algorithm,
+            receiver: None,
+        }
+    }
+}
+
+const LEGACY_IMMUTABLES: Item<LegacyImmutables> = Item::new("Immutables");
+
+/// cw2 name of the sylvia destination escrow that was merged into this contract
+pub const ESCROW_DEST_NAME: &str = "crates.io:escrow_dest";
+
+/// Layout of the sylvia `escrow_dest`, one item per field. Its
+/// `revealed_secret` item already matches `REVEALED_SECRET`.
+const DEST_DEPLOYED_AT: Item<u64> = Item::new("deployed_at");
+const DEST_RESCUE_DELAY: Item<u64> = Item::new("rescue_delay");
+const DEST_IMMUTABLES: Item<Immutables> = Item::new("immutables");
+const DEST_LEGACY_IMMUTABLES: Item<LegacyImmutables> = Item::new("immutables");
+
+/// Same as `cw2::ensure_from_older_version`, but accepts escrows that never
+/// stored their contract version.
+pub fn ensure_from_older_version(
+    storage: &mut dyn Storage,
+    name: &str,
+    new_version: &str,
+) -> StdResult<Version> {
+    if cw2::CONTRACT.may_load(storage)?.is_none() {
+        cw2::set_contract_version(storage, name, LEGACY_VERSION)?;
+    }
+    cw2::ensure_from_older_version(storage, name, new_version)
+}
+
+/// Turn the single `token` of a v0.1.0 escrow into a one-coin basket
+pub fn migrate_immutables(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_IMMUTABLES.load(storage)?;
+    IMMUTABLES.save(storage, &legacy.into())
+}
+
+/// Whether the escrow was deployed from the sylvia `escrow_dest` code
+pub fn is_escrow_dest(storage: &dyn Storage) -> bool {
+    DEST_DEPLOYED_AT.exists(storage)
+}
+
+/// Move an `escrow_dest` instance onto this contract's layout, returning the
+/// `escrow_dest` version it was deployed with. `escrow_dest` never knew its
+/// factory, so secrets revealed after the migration aren't reported to one.
+pub fn migrate_from_escrow_dest(storage: &mut dyn Storage) -> StdResult<Version> {
+    let version = match cw2::CONTRACT.may_load(storage)? {
+        Some(info) if info.contract == ESCROW_DEST_NAME => info.version,
+        Some(info) => {
+            return Err(StdError::generic_err(format!(
+                "Cannot migrate from {} as escrow_dest",
+                info.contract
+            )))
+        }
+        None => LEGACY_VERSION.to_string(),
+    };
+    let version: Version = version
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Invalid escrow_dest version {version}")))?;
+
+    // escrow_dest v0.1.0 held a single token as well
+    let immutables = if version < Version::new(0, 2, 0) {
+        DEST_LEGACY_IMMUTABLES.load(storage)?.into()
+    } else {
+        DEST_IMMUTABLES.load(storage)?
+    };
+    let state = State {
+        deployed_at: DEST_DEPLOYED_AT.load(storage)?,
+        rescue_delay: DEST_RESCUE_DELAY.load(storage)?,
+        factory: None,
+    };
+
+    STATE.save(storage, &state)?;
+    IMMUTABLES.save(storage, &immutables)?;
+    DEST_DEPLOYED_AT.remove(storage);
+    DEST_RESCUE_DELAY.remove(storage);
+    DEST_IMMUTABLES.remove(storage);
+
+    Ok(version)
+}
+
+#[cfg(test)]
+pub(crate) fn save_legacy_immutables(
+    storage: &mut dyn Storage,
+    token: Coin,
+    timelocks: Timelocks,
+) -> StdResult<()> {
+    LEGACY_IMMUTABLES.save(
+        storage,
+        &LegacyImmutables {
+            order_hash: vec![0xab; 32],
+            hashlock: vec![0xcd; 32],
+            maker: Addr::unchecked("maker"),
+            taker: Addr::unchecked("taker"),
+            token,
+            timelocks,
+            hash_algorithm: HashAlgorithm::Keccak256,
+        },
+    )
+}
+
+/// Storage of an `escrow_dest` instance, `version` is `None` for escrows
+/// deployed before cw2 info was stored and hold a single token
+#[cfg(test)]
+pub(crate) fn save_escrow_dest(
+    storage: &mut dyn Storage,
+    version: Option<&str>,
+    immutables: &Immutables,
+    deployed_at: u64,
+    rescue_delay: u64,
+) -> StdResult<()> {
+    match version {
+        Some(version) => {
+            cw2::set_contract_version(storage, ESCROW_DEST_NAME, version)?;
+            DEST_IMMUTABLES.save(storage, immutables)?;
+        }
+        None => DEST_LEGACY_IMMUTABLES.save(
+            storage,
+            &LegacyImmutables {
+                order_hash: immutables.order_hash.clone(),
+                hashlock: immutables.hashlock.clone(),
+                maker: immutables.maker.clone(),
+                taker: immutables.taker.clone(),
+                token: immutables.tokens[0].clone(),
+                timelocks: immutables.timelocks.clone(),
+                hash_algorithm: immutables.hash_algorithm,
+            },
+        )?,
+    }
+    DEST_DEPLOYED_AT.save(storage, &deployed_at)?;
+    DEST_RESCUE_DELAY.save(storage, &rescue_delay)
+}
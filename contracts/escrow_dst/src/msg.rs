@@ -1,7 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Binary, Coin};
 
-use crate::state::Timelocks;
+use crate::state::{Action, EscrowStatus, HashAlgorithm, Stage, Timelocks};
 #[cw_serde]
 pub struct InstantiateMsgData {
     pub rescue_delay: u64,
@@ -9,8 +9,19 @@ pub struct InstantiateMsgData {
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// set by the escrow factory to its own address
+    #[serde(default)]
+    pub factory: Option<Addr>,
+    /// send funds beyond `tokens` back to the sender instead of failing
+    #[serde(default)]
+    pub refund_surplus: bool,
+    /// receiver of a cross-chain order, paid instead of the maker
+    #[serde(default)]
+    pub receiver: Option<String>,
 }
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -22,10 +33,108 @@ pub enum ExecuteMsg {
 
 #[cw_serde]
 pub struct WithdrawMsg {
-    pub secret: String,
+    /// preimage of the hashlock, raw bytes
+    pub secret: Binary,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
-pub enum QueryMsg {}
+pub enum QueryMsg {
+    #[returns(GetOrderDetailsResponse)]
+    OrderDetails {},
+    #[returns(TimelocksResponse)]
+    Timelocks {},
+    /// What the escrow currently holds of its tokens
+    #[returns(BalanceResponse)]
+    Balance {},
+    #[returns(StatusResponse)]
+    Status {},
+    #[returns(ConfigResponse)]
+    Config {},
+    /// The hashlock preimage, once the escrow has been withdrawn
+    #[returns(RevealedSecretResponse)]
+    RevealedSecret {},
+    /// Timelock stage at the current block time
+    #[returns(CurrentStageResponse)]
+    CurrentStage {},
+    /// Messages `address` could call at the current block time, withdrawals
+    /// still need the right secret
+    #[returns(AllowedActionsResponse)]
+    AllowedActions { address: String },
+}
+
+// Response types mirror the source escrow so both legs are queried alike
+#[cw_serde]
+pub struct GetOrderDetailsResponse {
+    pub deployed_at: u64,
+    /// always `deployed_at`, a destination escrow is funded on creation
+    pub funded_at: Option<u64>,
+    pub rescue_delay: u64,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
+    /// paid on withdrawal instead of the maker
+    pub receiver: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct TimelocksResponse {
+    /// unix timestamp (in sec) the timelock offsets count from
+    pub deployed_at: u64,
+    pub timelocks: Timelocks,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    /// one coin per escrowed denom, zero once paid out
+    pub balance: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct StatusResponse {
+    pub status: EscrowStatus,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub contract: String,
+    pub version: String,
+    pub rescue_delay: u64,
+    pub hash_algorithm: HashAlgorithm,
+    /// factory notified of the revealed secret, if any
+    pub factory: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RevealedSecretResponse {
+    pub secret: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct CurrentStageResponse {
+    pub stage: Stage,
+    /// unix timestamp (in sec) the stage started at
+    pub starts_at: u64,
+    /// unix timestamp (in sec) the next stage starts at, `None` in the last stage
+    pub ends_at: Option<u64>,
+    pub seconds_until_next: Option<u64>,
+}
+
+#[cw_serde]
+pub struct AllowedActionsResponse {
+    pub actions: Vec<Action>,
+}
+
+/// Subset of the escrow factory's `ExecuteMsg` the escrow calls into
+#[cw_serde]
+pub enum FactoryExecuteMsg {
+    RecordSecret { order_hash: String, secret: Binary },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
 
@@ -0,0 +1,193 @@
+use cosmwasm_std::Addr;
+
+use cosmic_common::stages::{self, EscrowTerms};
+
+use crate::state::{Action, EscrowStatus, Immutables, Side, Stage, State};
+use crate::ContractError;
+
+/// The escrow's terms for the shared stage rules
+fn terms<'a>(
+    immutables: &'a Immutables,
+    state: &'a State,
+    settled: Option<EscrowStatus>,
+) -> EscrowTerms<'a> {
+    EscrowTerms {
+        side: Side::Dst,
+        status: settled.unwrap_or(EscrowStatus::Active),
+        taker: &immutables.taker,
+        factory: state.factory.as_ref(),
+        timelocks: &immutables.timelocks,
+        deployed_at: state.deployed_at,
+        rescue_delay: state.rescue_delay,
+    }
+}
+
+/// Decides whether `caller` may run `action` at `now`, returning the current
+/// stage. Every timelocked execute handler goes through here, nothing runs
+/// once the escrow is `settled`.
+pub fn authorize(
+    immutables: &Immutables,
+    state: &State,
+    settled: Option<EscrowStatus>,
+    caller: &Addr,
+    action: Action,
+    now: u64,
+) -> Result<Stage, ContractError> {
+    let terms = terms(immutables, state, settled);
+    Ok(stages::authorize(&terms, caller, action, now)?)
+}
+
+/// Timelocked actions `caller` could run at `now`, in `Action::ALL` order
+pub fn allowed_actions(
+    immutables: &Immutables,
+    state: &State,
+    settled: Option<EscrowStatus>,
+    caller: &Addr,
+    now: u64,
+) -> Vec<Action> {
+    stages::allowed_actions(&terms(immutables, state, settled), caller, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Timelocks;
+    use cosmic_common::stages::StageError;
+    use cosmwasm_std::testing::MockApi;
+
+    const DEPLOYED_AT: u64 = 1_000;
+    const RESCUE_DELAY: u64 = 150;
+
+    /// Outcome of an `authorize` call
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Expect {
+        Allowed,
+        OnlyTaker,
+        NotInWithdrawal,
+        NotInCancellation,
+        RescueLocked,
+        Settled,
+    }
+    use Expect::{
+        Allowed as Y, NotInCancellation as C, NotInWithdrawal as W, OnlyTaker as T,
+        RescueLocked as R, Settled as S,
+    };
+
+    /// Columns: withdraw, public_withdraw, cancel and rescue_funds, each
+    /// called by the taker, the maker and a third party. Stages are sampled
+    /// at their first second, the rescue delay ends within public withdrawal.
+    /// A settled escrow refuses everything.
+    const MATRIX: [(Stage, Option<EscrowStatus>, [Expect; 12]); 5] = [
+        (Stage::FinalityLock, None, [W, T, T, W, W, W, C, T, T, R, T, T]),
+        (Stage::PrivateWithdrawal, None, [Y, T, T, W, W, W, C, T, T, R, T, T]),
+        (Stage::PublicWithdrawal, None, [Y, T, T, Y, Y, Y, C, T, T, R, T, T]),
+        (Stage::Cancellation, None, [W, T, T, W, W, W, Y, T, T, Y, T, T]),
+        (Stage::Cancellation, Some(EscrowStatus::Withdrawn), [S; 12]),
+    ];
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn immutables() -> Immutables {
+        Immutables {
+            order_hash: vec![0xab; 32],
+            hashlock: vec![0xcd; 32],
+            maker: addr("maker"),
+            taker: addr("taker"),
+            tokens: vec![],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 100,
+                dest_cancellation: 200,
+                src_withdrawal: 10,
+                src_public_withdrawal: 100,
+                src_cancellation: 300,
+                src_public_cancellation: 400,
+            },
+            hash_algorithm: Default::default(),
+            receiver: None,
+        }
+    }
+
+    fn state() -> State {
+        State {
+            deployed_at: DEPLOYED_AT,
+            rescue_delay: RESCUE_DELAY,
+            factory: None,
+        }
+    }
+
+    fn stage_start(stage: Stage) -> u64 {
+        DEPLOYED_AT + immutables().timelocks.stage_bounds(Side::Dst, stage).0
+    }
+
+    fn outcome(result: Result<Stage, ContractError>) -> Expect {
+        match result {
+            Ok(_) => Expect::Allowed,
+            Err(ContractError::Stage(err)) => match err {
+                StageError::OnlyTaker => Expect::OnlyTaker,
+                StageError::NotInWithdrawalStage { .. } => Expect::NotInWithdrawal,
+                StageError::NotInCancellationStage { .. } => Expect::NotInCancellation,
+                StageError::RescueTimeLimit => Expect::RescueLocked,
+                StageError::AlreadySettled { .. } => Expect::Settled,
+                err => panic!("unexpected error {err}"),
+            },
+            Err(err) => panic!("unexpected error {err}"),
+        }
+    }
+
+    #[test]
+    fn every_stage_action_and_caller() {
+        let (immutables, state) = (immutables(), state());
+        let callers = [addr("taker"), addr("maker"), addr("anyone")];
+
+        for (stage, settled, expected) in MATRIX {
+            let now = stage_start(stage);
+            assert_eq!(immutables.timelocks.stage(Side::Dst, DEPLOYED_AT, now), stage);
+            // and the stage lasts until the next one starts
+            assert_eq!(immutables.timelocks.stage(Side::Dst, DEPLOYED_AT, now + 9), stage);
+
+            let cells = Action::supported(Side::Dst)
+                .flat_map(|action| callers.iter().map(move |caller| (action, caller)));
+            for ((action, caller), expected) in cells.zip(expected) {
+                let result = authorize(&immutables, &state, settled, caller, action, now);
+                assert_eq!(
+                    outcome(result),
+                    expected,
+                    "{} by {caller} at {}",
+                    action.as_str(),
+                    stage.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn factory_withdraws_and_cancels_for_the_taker() {
+        let factory = addr("factory");
+        let deployed = State {
+            factory: Some(factory.clone()),
+            ..state()
+        };
+        let now = stage_start(Stage::Cancellation) + RESCUE_DELAY;
+        let allowed = allowed_actions(&immutables(), &deployed, None, &factory, now);
+        assert_eq!(allowed, vec![Action::Cancel]);
+
+        let now = stage_start(Stage::PrivateWithdrawal);
+        let allowed = allowed_actions(&immutables(), &deployed, None, &factory, now);
+        assert_eq!(allowed, vec![Action::Withdraw]);
+        // without a factory it is just a third party
+        assert_eq!(allowed_actions(&immutables(), &state(), None, &factory, now), vec![]);
+    }
+
+    #[test]
+    fn rescue_opens_with_the_delay() {
+        let taker = addr("taker");
+        let rescue_at = DEPLOYED_AT + RESCUE_DELAY;
+        let result = authorize(&immutables(), &state(), None, &taker, Action::RescueFunds, rescue_at - 1);
+        assert_eq!(outcome(result), Expect::RescueLocked);
+        let result = authorize(&immutables(), &state(), None, &taker, Action::RescueFunds, rescue_at);
+        assert_eq!(result.unwrap(), Stage::PublicWithdrawal);
+    }
+}
@@ -0,0 +1,232 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Binary, Coin, Coins, CosmosMsg, CustomQuery, QuerierWrapper, StdError, StdResult,
+    Timestamp, Uint128,
+};
+use injective_std::{
+    shim::{Any, Timestamp as ProtoTimestamp},
+    types::{
+        cosmos::authz::v1beta1::{Grant, MsgGrant, MsgRevoke, QueryGrantsRequest, QueryGrantsResponse},
+        cosmos::bank::v1beta1::SendAuthorization,
+        cosmos::base::v1beta1::Coin as ProtoCoin,
+    },
+};
+use prost::Message;
+
+use crate::contract::MSG_BANK_SEND;
+use crate::msg::create_stargate_msg;
+
+pub const MSG_GRANT: &str = "/cosmos.authz.v1beta1.MsgGrant";
+pub const MSG_REVOKE: &str = "/cosmos.authz.v1beta1.MsgRevoke";
+pub const SEND_AUTHORIZATION: &str = "/cosmos.bank.v1beta1.SendAuthorization";
+pub const QUERY_GRANTS: &str = "/cosmos.authz.v1beta1.Query/Grants";
+
+/// A bank `SendAuthorization` from a maker to the contract pulling its funds
+#[cw_serde]
+pub struct SendGrant {
+    pub spend_limit: Vec<Coin>,
+    /// recipients the grantee may send to, empty allows any
+    pub allow_list: Vec<String>,
+    pub expiration: Option<Timestamp>,
+}
+
+impl SendGrant {
+    /// Whether `grantee` can move `amount` to itself under this grant at `now`
+    pub fn covers(&self, grantee: &Addr, amount: &[Coin], now: Timestamp) -> bool {
+        if self.expiration.is_some_and(|expiration| expiration <= now) {
+            return false;
+        }
+        if !self.allow_list.is_empty() && !self.allow_list.iter().any(|a| a == grantee.as_str()) {
+            return false;
+        }
+        let Ok(limit) = Coins::try_from(self.spend_limit.clone()) else {
+            return false;
+        };
+
+        amount
+            .iter()
+            .all(|coin| limit.amount_of(&coin.denom) >= coin.amount)
+    }
+
+    /// Decode an authz `Grant`, `None` if it holds something other than a bank send authorization
+    pub fn from_proto(grant: Grant) -> StdResult<Option<Self>> {
+        let Some(authorization) = grant.authorization else {
+            return Ok(None);
+        };
+        if authorization.type_url != SEND_AUTHORIZATION {
+            return Ok(None);
+        }
+        let send = SendAuthorization::decode(authorization.value.as_slice())
+            .map_err(|e| StdError::parse_err("SendAuthorization", e))?;
+
+        Ok(Some(SendGrant {
+            spend_limit: send
+                .spend_limit
+                .into_iter()
+                .map(from_proto_coin)
+                .collect::<StdResult<_>>()?,
+            allow_list: send.allow_list,
+            expiration: grant.expiration.map(from_proto_timestamp),
+        }))
+    }
+
+    pub fn to_proto(&self) -> Grant {
+        let authorization = SendAuthorization {
+            spend_limit: self.spend_limit.iter().map(to_proto_coin).collect(),
+            allow_list: self.allow_list.clone(),
+        };
+
+        Grant {
+            authorization: Some(Any {
+                type_url: SEND_AUTHORIZATION.to_string(),
+                value: authorization.encode_to_vec(),
+            }),
+            expiration: self.expiration.map(to_proto_timestamp),
+        }
+    }
+}
+
+/// `MsgGrant` for the maker to sign, letting `grantee` send up to `spend_limit`
+/// to itself until `expiration`
+pub fn grant_send_msg(
+    granter: &Addr,
+    grantee: &Addr,
+    spend_limit: &[Coin],
+    expiration: Option<Timestamp>,
+) -> StdResult<CosmosMsg> {
+    let grant = SendGrant {
+        spend_limit: spend_limit.to_vec(),
+        allow_list: vec![grantee.to_string()],
+        expiration,
+    };
+    send_grant_msg(granter, grantee, grant)
+}
+
+/// Like [`grant_send_msg`] but without an allow list, for the limit order protocol
+/// which sends the maker's funds straight into the escrow it spawns for each order
+pub fn grant_send_any_msg(
+    granter: &Addr,
+    grantee: &Addr,
+    spend_limit: &[Coin],
+    expiration: Option<Timestamp>,
+) -> StdResult<CosmosMsg> {
+    let grant = SendGrant {
+        spend_limit: spend_limit.to_vec(),
+        allow_list: vec![],
+        expiration,
+    };
+    send_grant_msg(granter, grantee, grant)
+}
+
+fn send_grant_msg(granter: &Addr, grantee: &Addr, grant: SendGrant) -> StdResult<CosmosMsg> {
+    let msg = MsgGrant {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        grant: Some(grant.to_proto()),
+    };
+
+    create_stargate_msg(MSG_GRANT, msg.encode_to_vec())
+}
+
+/// `MsgRevoke` dropping every bank send authorization `granter` gave `grantee`
+pub fn revoke_send_msg(granter: &Addr, grantee: &Addr) -> StdResult<CosmosMsg> {
+    let msg = MsgRevoke {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        msg_type_url: MSG_BANK_SEND.to_string(),
+    };
+
+    create_stargate_msg(MSG_REVOKE, msg.encode_to_vec())
+}
+
+/// Bank send authorizations `granter` gave `grantee`, read through the gRPC querier
+pub fn query_send_grants<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    granter: &Addr,
+    grantee: &Addr,
+) -> StdResult<Vec<SendGrant>> {
+    let request = QueryGrantsRequest {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        msg_type_url: MSG_BANK_SEND.to_string(),
+        pagination: None,
+    };
+    let response = querier.query_grpc(QUERY_GRANTS.to_string(), request.encode_to_vec().into())?;
+
+    decode_send_grants(&response)
+}
+
+/// Decode a protobuf `QueryGrantsResponse`, skipping authorizations other than bank sends
+pub fn decode_send_grants(response: &Binary) -> StdResult<Vec<SendGrant>> {
+    let response = QueryGrantsResponse::decode(response.as_slice())
+        .map_err(|e| StdError::parse_err("QueryGrantsResponse", e))?;
+
+    let grants = response
+        .grants
+        .into_iter()
+        .map(SendGrant::from_proto)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(grants.into_iter().flatten().collect())
+}
+
+/// Encode grants the way the authz module answers `Query/Grants`, for mock queriers
+pub fn encode_send_grants(grants: &[SendGrant]) -> Binary {
+    let response = QueryGrantsResponse {
+        grants: grants.iter().map(SendGrant::to_proto).collect(),
+        pagination: None,
+    };
+
+    response.encode_to_vec().into()
+}
+
+fn to_proto_coin(coin: &Coin) -> ProtoCoin {
+    ProtoCoin {
+        denom: coin.denom.clone(),
+        amount: coin.amount.to_string(),
+    }
+}
+
+fn from_proto_coin(coin: ProtoCoin) -> StdResult<Coin> {
+    Ok(Coin {
+        amount: coin.amount.parse::<Uint128>()?,
+        denom: coin.denom,
+    })
+}
+
+fn to_proto_timestamp(time: Timestamp) -> ProtoTimestamp {
+    ProtoTimestamp {
+        seconds: time.seconds() as i64,
+        nanos: time.subsec_nanos() as i32,
+    }
+}
+
+fn from_proto_timestamp(time: ProtoTimestamp) -> Timestamp {
+    Timestamp::from_seconds(time.seconds.max(0) as u64).plus_nanos(time.nanos.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn grants_round_trip_and_cover_spends() {
+        let grantee = Addr::unchecked("lop");
+        let now = Timestamp::from_seconds(100);
+        let grant = SendGrant {
+            spend_limit: vec![coin(100, "uusdc"), coin(5, "uatom")],
+            allow_list: vec![grantee.to_string()],
+            expiration: Some(Timestamp::from_seconds(200)),
+        };
+
+        let decoded = decode_send_grants(&encode_send_grants(&[grant.clone()])).unwrap();
+        assert_eq!(decoded, vec![grant.clone()]);
+
+        assert!(grant.covers(&grantee, &[coin(100, "uusdc"), coin(5, "uatom")], now));
+        assert!(!grant.covers(&grantee, &[coin(101, "uusdc")], now));
+        assert!(!grant.covers(&grantee, &[coin(1, "stake")], now));
+        assert!(!grant.covers(&Addr::unchecked("other"), &[coin(1, "uusdc")], now));
+        assert!(!grant.covers(&grantee, &[coin(1, "uusdc")], Timestamp::from_seconds(200)));
+    }
+}
@@ -1,15 +1,27 @@
 use crate::encode_helpers::encode_bytes_message;
 use crate::error::ContractError;
+use cosmic_common::events::{EscrowEvent, EscrowEventKind};
 use crate::msg::{
-    create_stargate_msg, ExecuteMsg, GetOrderDetailsResponse, InstantiateMsg, PullFundsMsg,
-    QueryMsg,
+    create_stargate_msg, AllowedActionsResponse, BalanceResponse, ConfigResponse,
+    CurrentStageResponse, ExecuteMsg, GetOrderDetailsResponse, InstantiateMsg, MigrateMsg,
+    QueryMsg, RevealedSecretResponse, StatusResponse, TimelocksResponse,
 };
-use crate::state::{Immutables, State, IMMUTABLES, STATE};
+use crate::helpers::{check_funds, holds_tokens, validate_basket};
+use crate::migrations::migrate_immutables;
+use crate::query::{AuthzGrantsResponse, CosmicQuerier, CosmicQueryWrapper};
+use crate::stages;
+use crate::state::{
+    settled_status, EscrowStatus, Immutables, Side, State, IMMUTABLES, REVEALED_SECRET, SETTLED,
+    STATE,
+};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult, SubMsg,
+    to_json_binary, BankMsg, Binary, CustomQuery, Deps, DepsMut, Env, Event,
+    MessageInfo, Reply, Response, StdError, StdResult, SubMsg, SubMsgResult,
 };
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
+use semver::Version;
 use injective_std::{
     shim::Any,
     types::{
@@ -17,7 +29,6 @@ use injective_std::{
         cosmos::base::v1beta1::Coin as ProtoCoin,
     },
 };
-#[cfg(not(feature = "library"))]
 use prost::Message;
 
 // version info for migration info
@@ -30,14 +41,26 @@ pub const REPLY_WITHDRAW_ERR: u64 = 2;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    deps: DepsMut<CosmicQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    validate_basket(&msg.tokens)?;
+    // the escrow is either funded on creation, prefunded at its instantiate2
+    // address, or pulls the tokens from the maker later
+    let (surplus, funded) = if info.funds.is_empty() {
+        let prefunded = holds_tokens(&deps.querier, &env.contract.address, &msg.tokens)?;
+        (vec![], prefunded)
+    } else {
+        (check_funds(&info.funds, &msg.tokens, msg.refund_surplus)?, true)
+    };
+
+    let now = env.block.time.seconds();
     let state = State {
-        deployed_at: _env.block.time.seconds(),
+        deployed_at: now,
         rescue_delay: msg.rescue_delay,
+        funded_at: funded.then_some(now),
     };
 
     let hashlock = hex::decode(&msg.hashlock)
@@ -52,7 +75,8 @@ pub fn instantiate(
         maker: msg.maker,
         taker: msg.taker,
         timelocks: msg.timelocks,
-        token: msg.token,
+        tokens: msg.tokens,
+        hash_algorithm: msg.hash_algorithm,
     };
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -66,61 +90,119 @@ pub fn instantiate(
      *
      */
 
-    Ok(Response::new())
+    let event = escrow_event(EscrowEventKind::Created, &immutables, &state, &env, None);
+    let mut res = Response::new().add_event(event);
+    if !surplus.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: surplus,
+        });
+    }
+    Ok(res)
+}
+
+fn escrow_event(
+    kind: EscrowEventKind,
+    immutables: &Immutables,
+    state: &State,
+    env: &Env,
+    secret: Option<String>,
+) -> Event {
+    let stage = immutables
+        .timelocks
+        .stage(Side::Src, state.deployed_at, env.block.time.seconds());
+
+    EscrowEvent {
+        kind,
+        order_hash: hex::encode(&immutables.order_hash),
+        hashlock: hex::encode(&immutables.hashlock),
+        maker: immutables.maker.clone(),
+        taker: immutables.taker.clone(),
+        amount: immutables.tokens.clone(),
+        stage: stage.as_str().to_string(),
+        secret,
+    }
+    .into()
+}
+
+/// `escrow_withdrawn` and `secret_revealed`, both carrying the preimage
+fn withdrawal_events(
+    immutables: &Immutables,
+    state: &State,
+    env: &Env,
+    secret: &Binary,
+) -> [Event; 2] {
+    let secret = hex::encode(secret);
+    [
+        escrow_event(EscrowEventKind::Withdrawn, immutables, state, env, Some(secret.clone())),
+        escrow_event(EscrowEventKind::SecretRevealed, immutables, state, env, Some(secret)),
+    ]
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    deps: DepsMut<CosmicQueryWrapper>,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::PullFunds(msg) => execute::pull_funds(deps, env, info, msg),
+        ExecuteMsg::PullFunds() => execute::pull_funds(deps, env, info),
         ExecuteMsg::Withdraw(msg) => execute::withdraw(deps, env, info, msg),
         ExecuteMsg::WithdrawTo(msg) => execute::withdraw_to(deps, env, info, msg),
-        ExecuteMsg::PublicWithdraw(msg) => execute::public_withdraw(deps, env, msg),
+        ExecuteMsg::PublicWithdraw(msg) => execute::public_withdraw(deps, env, info, msg),
         ExecuteMsg::Cancel() => execute::cancel(deps, env, info),
-        ExecuteMsg::PublicCancel() => execute::public_cancel(deps, env),
+        ExecuteMsg::PublicCancel() => execute::public_cancel(deps, env, info),
     }
 }
 
 pub mod execute {
-    use cosmwasm_std::{Addr, BankMsg, Coin, Event};
+    use cosmwasm_std::{Addr, BankMsg, Coin};
 
     use crate::{
-        helpers::{only_after, only_before, only_valid_secret},
+        helpers::only_valid_secret,
         msg::{WithdrawMsg, WithdrawToMsg},
+        stages::authorize,
+        state::Action,
     };
 
     use super::*;
 
-    pub fn pull_funds(
-        deps: DepsMut,
-        _env: Env,
+    /// Anyone may trigger the pull, it only ever moves the committed `tokens`
+    /// from the maker into the escrow. A failed transfer reverts the funding.
+    pub fn pull_funds<Q: CustomQuery>(
+        deps: DepsMut<Q>,
+        env: Env,
         _info: MessageInfo,
-        msg: PullFundsMsg,
     ) -> Result<Response, ContractError> {
         let mut state: State = STATE.load(deps.storage)?;
-        state.deployed_at = _env.block.time.seconds();
+        if state.funded_at.is_some() {
+            return Err(ContractError::AlreadyFunded);
+        }
+        state.funded_at = Some(env.block.time.seconds());
+        let immutables = IMMUTABLES.load(deps.storage)?;
 
-        let giver = msg.from;
-        let amount = ProtoCoin {
-            denom: msg.amount.denom,
-            amount: msg.amount.amount.to_string(),
-        };
+        let giver = immutables.maker;
+        let amount = immutables
+            .tokens
+            .into_iter()
+            .map(|coin| ProtoCoin {
+                denom: coin.denom,
+                amount: coin.amount.to_string(),
+            })
+            .collect();
 
         let bank_send_msg = MsgSend {
-            amount: vec![amount],
+            amount,
             from_address: giver.to_string(),
-            to_address: _env.contract.address.to_string(),
+            to_address: env.contract.address.to_string(),
         };
 
         // let exec_msg
-        let order_bytes = encode_bytes_message(&bank_send_msg).unwrap();
+        let order_bytes = encode_bytes_message(&bank_send_msg)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
         let msg_exec = MsgExec {
-            grantee: _env.contract.address.to_string(),
+            grantee: env.contract.address.to_string(),
             msgs: vec![Any {
                 type_url: MSG_BANK_SEND.to_string(), //"/cosmos.bank.v1beta1.MsgSend" ,
                 value: order_bytes,
@@ -128,141 +210,131 @@ pub mod execute {
         };
 
         let submessage = SubMsg::reply_on_error(
-            create_stargate_msg(MSG_EXEC, msg_exec.encode_to_vec()).unwrap(),
+            create_stargate_msg(MSG_EXEC, msg_exec.encode_to_vec())?,
             REPLY_ID,
         );
 
         STATE.save(deps.storage, &state)?;
 
-        Ok(Response::new().add_submessage(submessage))
+        Ok(Response::new()
+            .add_attribute("action", "pull_funds")
+            .add_attribute("funded_at", env.block.time.seconds().to_string())
+            .add_submessage(submessage))
     }
 
-    pub fn withdraw(
-        deps: DepsMut,
+    pub fn withdraw<Q: CustomQuery>(
+        deps: DepsMut<Q>,
         env: Env,
         info: MessageInfo,
         msg: WithdrawMsg,
     ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
         let immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::Withdraw, now)?;
 
-        if immutables.taker != info.sender {
-            return Err(ContractError::OnlyTaker);
-        }
+        only_valid_secret(&msg.secret, &immutables.hashlock, immutables.hash_algorithm)?;
+        REVEALED_SECRET.save(deps.storage, &msg.secret)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Withdrawn)?;
 
-        if only_after(current_time_in_secs,state.deployed_at+ immutables.timelocks.src_withdrawal) {
-            return Err(ContractError::SrcWithrawTimeLimit);
-        }
+        let [withdrawn, revealed] = withdrawal_events(&immutables, &state, &env, &msg.secret);
+        let sub_msg = _withdraw_to(immutables.taker, immutables.tokens);
 
-        if only_before(current_time_in_secs, state.deployed_at+immutables.timelocks.src_cancellation) {
-            return Err(ContractError::SrcCancelTimeLimit);
-        }
-
-        only_valid_secret(msg.secret, immutables.hashlock)?;
-
-        let sub_msg = _withdraw_to(immutables.taker, immutables.token);
-
-        Ok(Response::new().add_submessage(sub_msg))
+        Ok(Response::new()
+            .add_events([withdrawn, revealed])
+            .add_submessage(sub_msg))
     }
 
-    pub fn withdraw_to(
-        deps: DepsMut,
+    pub fn withdraw_to<Q: CustomQuery>(
+        deps: DepsMut<Q>,
         env: Env,
         info: MessageInfo,
         msg: WithdrawToMsg,
     ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
         let immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
-
-        if immutables.taker != info.sender {
-            return Err(ContractError::OnlyTaker);
-        }
-
-        if only_after(current_time_in_secs, state.deployed_at+ immutables.timelocks.src_withdrawal) {
-            return Err(ContractError::SrcWithrawTimeLimit);
-        }
-
-        if only_before(current_time_in_secs, state.deployed_at+ immutables.timelocks.src_cancellation) {
-            return Err(ContractError::SrcCancelTimeLimit);
-        }
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::Withdraw, now)?;
 
-        only_valid_secret(msg.secret, immutables.hashlock)?;
+        only_valid_secret(&msg.secret, &immutables.hashlock, immutables.hash_algorithm)?;
+        REVEALED_SECRET.save(deps.storage, &msg.secret)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Withdrawn)?;
 
-        let sub_msg = _withdraw_to(msg.tagret, immutables.token);
+        let [withdrawn, revealed] = withdrawal_events(&immutables, &state, &env, &msg.secret);
+        let withdrawn = withdrawn.add_attribute("recipient", msg.tagret.to_string());
+        let sub_msg = _withdraw_to(msg.tagret, immutables.tokens);
 
-        Ok(Response::new().add_submessage(sub_msg))
+        Ok(Response::new()
+            .add_events([withdrawn, revealed])
+            .add_submessage(sub_msg))
     }
 
-    pub fn public_withdraw(
-        deps: DepsMut,
+    pub fn public_withdraw<Q: CustomQuery>(
+        deps: DepsMut<Q>,
         env: Env,
+        info: MessageInfo,
         msg: WithdrawMsg,
     ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
         let immutables: Immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::PublicWithdraw, now)?;
 
-        if only_after(
-            current_time_in_secs,
-            state.deployed_at+   immutables.timelocks.src_public_withdrawal,
-        ) {
-            return Err(ContractError::SrcWithrawTimeLimit);
-        }
+        only_valid_secret(&msg.secret, &immutables.hashlock, immutables.hash_algorithm)?;
+        REVEALED_SECRET.save(deps.storage, &msg.secret)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Withdrawn)?;
 
-        if only_before(current_time_in_secs, state.deployed_at+ immutables.timelocks.src_cancellation) {
-            return Err(ContractError::SrcCancelTimeLimit);
-        }
-        
-        only_valid_secret(msg.secret, immutables.hashlock)?;
-        let sub_msg = _withdraw_to(immutables.taker, immutables.token);
+        let [withdrawn, revealed] = withdrawal_events(&immutables, &state, &env, &msg.secret);
+        let sub_msg = _withdraw_to(immutables.taker, immutables.tokens);
 
-        Ok(Response::new().add_submessage(sub_msg))
-        
+        Ok(Response::new()
+            .add_events([withdrawn, revealed])
+            .add_submessage(sub_msg))
     }
 
-
-    pub fn cancel( deps: DepsMut, env: Env, info: MessageInfo,) -> Result<Response, ContractError>  {
+    pub fn cancel<Q: CustomQuery>(
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
         let immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
-     
-        if immutables.taker != info.sender {
-            return Err(ContractError::OnlyTaker);
-        }
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::Cancel, now)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Cancelled)?;
 
-        if only_after(current_time_in_secs, state.deployed_at+ immutables.timelocks.src_public_cancellation) {
-            return  Err(ContractError::SrcWithrawTimeLimit);
-        }
+        let event = escrow_event(EscrowEventKind::Cancelled, &immutables, &state, &env, None);
+        let sub_msg = _withdraw_to(immutables.maker, immutables.tokens);
 
-       let sub_msg = _withdraw_to(immutables.maker, immutables.token);
-
-        let event = Event::new("cancel");
-        Ok(Response::new().add_event(event).add_submessage(sub_msg) )
-    }  
+        Ok(Response::new().add_event(event).add_submessage(sub_msg))
+    }
 
-    pub fn public_cancel(deps: DepsMut, env: Env)  -> Result<Response, ContractError> {
+    pub fn public_cancel<Q: CustomQuery>(
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
-       let immutables = IMMUTABLES.load(deps.storage)?;
-        let current_time_in_secs = env.block.time.seconds();
-
-        if only_after(current_time_in_secs, state.deployed_at+ immutables.timelocks.src_public_cancellation) {
-            return  Err( ContractError::SrcCancelTimeLimit );
-        }
-
-        let sub_msg = _withdraw_to(immutables.maker, immutables.token);
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let now = env.block.time.seconds();
+        let settled = settled_status(deps.storage)?;
+        authorize(&immutables, &state, settled, &info.sender, Action::PublicCancel, now)?;
+        SETTLED.save(deps.storage, &EscrowStatus::Cancelled)?;
 
-        let event = Event::new("cancel");
-        Ok(Response::new().add_event(event).add_submessage(sub_msg) )
+        let event = escrow_event(EscrowEventKind::Cancelled, &immutables, &state, &env, None);
+        let sub_msg = _withdraw_to(immutables.maker, immutables.tokens);
 
+        Ok(Response::new().add_event(event).add_submessage(sub_msg))
     }
 
-
-    fn _withdraw_to(target: Addr, amount: Coin) -> SubMsg {
+    fn _withdraw_to(target: Addr, amount: Vec<Coin>) -> SubMsg {
         let msg = BankMsg::Send {
             to_address: target.into(),
-            amount: vec![amount],
+            amount,
         };
 
         SubMsg::reply_on_error(msg, REPLY_WITHDRAW_ERR)
@@ -270,36 +342,552 @@ pub mod execute {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<CosmicQueryWrapper>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::OrderDetails {} => to_json_binary(&query::get_order_details(deps)?),
+        QueryMsg::Timelocks {} => to_json_binary(&query::timelocks(deps)?),
+        QueryMsg::Balance {} => to_json_binary(&query::balance(deps, env)?),
+        QueryMsg::Status {} => to_json_binary(&query::status(deps)?),
+        QueryMsg::Config {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::RevealedSecret {} => to_json_binary(&query::revealed_secret(deps)?),
+        QueryMsg::MakerGrants {} => to_json_binary(&query::maker_grants(deps, env)?),
+        QueryMsg::CurrentStage {} => to_json_binary(&query::current_stage(deps, env)?),
+        QueryMsg::AllowedActions { address } => {
+            to_json_binary(&query::allowed_actions(deps, env, address)?)
+        }
     }
 }
 
 pub mod query {
     use super::*;
 
-    pub fn get_order_details(deps: Deps) -> StdResult<GetOrderDetailsResponse> {
+    pub fn get_order_details<Q: CustomQuery>(deps: Deps<Q>) -> StdResult<GetOrderDetailsResponse> {
         let state = STATE.load(deps.storage)?;
         let immutables = IMMUTABLES.load(deps.storage)?;
         Ok(GetOrderDetailsResponse {
             deployed_at: state.deployed_at,
+            funded_at: state.funded_at,
             hashlock: hex::encode(immutables.hashlock),
             maker: immutables.maker,
             order_hash: hex::encode(immutables.order_hash),
             rescue_delay: state.rescue_delay,
             taker: immutables.taker,
             timelocks: immutables.timelocks,
-            token: immutables.token,
+            tokens: immutables.tokens,
+            hash_algorithm: immutables.hash_algorithm,
+        })
+    }
+
+    pub fn timelocks<Q: CustomQuery>(deps: Deps<Q>) -> StdResult<TimelocksResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        Ok(TimelocksResponse {
+            deployed_at: state.deployed_at,
+            timelocks: immutables.timelocks,
+        })
+    }
+
+    pub fn balance<Q: CustomQuery>(deps: Deps<Q>, env: Env) -> StdResult<BalanceResponse> {
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let balance = immutables
+            .tokens
+            .iter()
+            .map(|token| deps.querier.query_balance(&env.contract.address, &token.denom))
+            .collect::<StdResult<_>>()?;
+        Ok(BalanceResponse { balance })
+    }
+
+    pub fn status<Q: CustomQuery>(deps: Deps<Q>) -> StdResult<StatusResponse> {
+        let status = match settled_status(deps.storage)? {
+            Some(status) => status,
+            None if STATE.load(deps.storage)?.funded_at.is_none() => EscrowStatus::Unfunded,
+            None => EscrowStatus::Active,
+        };
+        Ok(StatusResponse { status })
+    }
+
+    pub fn config<Q: CustomQuery>(deps: Deps<Q>) -> StdResult<ConfigResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let version = cw2::get_contract_version(deps.storage)?;
+        Ok(ConfigResponse {
+            contract: version.contract,
+            version: version.version,
+            rescue_delay: state.rescue_delay,
+            hash_algorithm: immutables.hash_algorithm,
+            factory: None,
         })
     }
+
+    pub fn current_stage<Q: CustomQuery>(deps: Deps<Q>, env: Env) -> StdResult<CurrentStageResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let now = env.block.time.seconds();
+
+        let stage = immutables.timelocks.stage(Side::Src, state.deployed_at, now);
+        let (start, end) = immutables.timelocks.stage_bounds(Side::Src, stage);
+        let ends_at = end.map(|end| state.deployed_at + end);
+        Ok(CurrentStageResponse {
+            stage,
+            starts_at: state.deployed_at + start,
+            ends_at,
+            seconds_until_next: ends_at.map(|ends_at| ends_at.saturating_sub(now)),
+        })
+    }
+
+    pub fn allowed_actions<Q: CustomQuery>(
+        deps: Deps<Q>,
+        env: Env,
+        address: String,
+    ) -> StdResult<AllowedActionsResponse> {
+        let state = STATE.load(deps.storage)?;
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        let address = deps.api.addr_validate(&address)?;
+        Ok(AllowedActionsResponse {
+            actions: stages::allowed_actions(
+                &immutables,
+                &state,
+                settled_status(deps.storage)?,
+                &address,
+                env.block.time.seconds(),
+            ),
+        })
+    }
+
+    pub fn revealed_secret<Q: CustomQuery>(deps: Deps<Q>) -> StdResult<RevealedSecretResponse> {
+        Ok(RevealedSecretResponse {
+            secret: REVEALED_SECRET.may_load(deps.storage)?,
+        })
+    }
+
+    /// Send authorizations the maker gave this escrow for `PullFunds`
+    pub fn maker_grants(
+        deps: Deps<CosmicQueryWrapper>,
+        env: Env,
+    ) -> StdResult<AuthzGrantsResponse> {
+        let immutables = IMMUTABLES.load(deps.storage)?;
+        CosmicQuerier::new(&deps.querier).authz_grants(&immutables.maker, &env.contract.address)
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(
+    _deps: DepsMut<CosmicQueryWrapper>,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    // both submessages only reply on error, keep the chain's reason for the caller
+    let reason = match msg.result {
+        SubMsgResult::Err(reason) => reason,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
     match msg.id {
-        REPLY_ID => Err(ContractError::DepositError),
-        REPLY_WITHDRAW_ERR => Err(ContractError::WithdrawError),
+        REPLY_ID => Err(ContractError::DepositError { reason }),
+        REPLY_WITHDRAW_ERR => Err(ContractError::WithdrawError { reason }),
         _ => Ok(Response::new()),
     }
 }
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<CosmicQueryWrapper>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // v0.2.0 holds a basket of coins instead of a single token
+    if from_version < Version::new(0, 2, 0) {
+        migrate_immutables(deps.storage)?;
+    }
+
+    // v0.3.0 records when the escrow was funded, older escrows were funded
+    // by the time they could be withdrawn or cancelled
+    if from_version < Version::new(0, 3, 0) {
+        STATE.update(deps.storage, |mut state| -> StdResult<_> {
+            state.funded_at = state.funded_at.or(Some(state.deployed_at));
+            Ok(state)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authz::SendGrant;
+    use cosmic_common::funds::FundsError;
+    use cosmic_common::stages::StageError;
+    use crate::query::CosmicQuery;
+    use crate::state::{Action, Stage, Timelocks};
+    use cosmwasm_std::testing::{
+        message_info, mock_env, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{coin, from_json, Addr, ContractResult, OwnedDeps, SystemResult};
+
+    type CosmicDeps = OwnedDeps<MockStorage, MockApi, MockQuerier<CosmicQueryWrapper>, CosmicQueryWrapper>;
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn mock_dependencies() -> CosmicDeps {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::new(&[]),
+            custom_query_type: std::marker::PhantomData,
+        }
+    }
+
+    fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            rescue_delay: 1000,
+            order_hash: "ab".repeat(32),
+            hashlock: "cd".repeat(32),
+            maker: addr("maker"),
+            taker: addr("taker"),
+            tokens: vec![coin(100, "uusdc")],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 100,
+                dest_cancellation: 200,
+                src_cancellation: 300,
+                src_withdrawal: 10,
+                src_public_withdrawal: 100,
+                src_public_cancellation: 400,
+            },
+            hash_algorithm: Default::default(),
+            refund_surplus: false,
+        }
+    }
+
+    #[test]
+    fn instantiate_is_unfunded_or_funded_exactly() {
+        // funds pulled from the maker later on
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("lop"), &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+
+        let mut deps = mock_dependencies();
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("lop"), &[coin(100, "uusdc"), coin(1, "stake")]),
+            instantiate_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::ExtraDenom { denom }) if denom == "stake"));
+
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("lop"), &[coin(99, "uusdc")]),
+            instantiate_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Funds(FundsError::InsufficientFunds { .. })));
+
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("lop"), &[coin(120, "uusdc")]),
+            InstantiateMsg {
+                refund_surplus: true,
+                ..instantiate_msg()
+            },
+        )
+        .unwrap();
+        let refund = BankMsg::Send {
+            to_address: addr("lop").to_string(),
+            amount: vec![coin(20, "uusdc")],
+        };
+        assert_eq!(res.messages[0].msg, refund.into());
+    }
+
+    #[test]
+    fn failed_transfers_keep_the_chain_reason() {
+        let mut deps = mock_dependencies();
+
+        #[allow(deprecated)]
+        let failed = |id| Reply {
+            id,
+            payload: Binary::default(),
+            gas_used: 0,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+
+        let err = reply(deps.as_mut(), mock_env(), failed(REPLY_ID)).unwrap_err();
+        assert!(matches!(err, ContractError::DepositError { ref reason } if reason == "insufficient funds"));
+        let err = reply(deps.as_mut(), mock_env(), failed(REPLY_WITHDRAW_ERR)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error while withdraw token from escrow: insufficient funds"
+        );
+    }
+
+    #[test]
+    fn maker_grants_use_authz_route() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let grant = SendGrant {
+            spend_limit: vec![coin(100, "uusdc")],
+            allow_list: vec![env.contract.address.to_string()],
+            expiration: None,
+        };
+        let expected = (addr("maker").to_string(), env.contract.address.to_string());
+        let response = AuthzGrantsResponse {
+            grants: vec![grant],
+        };
+        let answer = to_json_binary(&response).unwrap();
+        deps.querier = MockQuerier::new(&[]).with_custom_handler(move |request: &CosmicQueryWrapper| {
+            match &request.query_data {
+                CosmicQuery::Authz { granter, grantee }
+                    if (granter.clone(), grantee.clone()) == expected =>
+                {
+                    SystemResult::Ok(ContractResult::Ok(answer.clone()))
+                }
+                _ => SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+            }
+        });
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("lop"), &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), env, QueryMsg::MakerGrants {}).unwrap();
+        assert_eq!(from_json::<AuthzGrantsResponse>(&res).unwrap(), response);
+    }
+
+    fn pull_funds_send(res: &Response) -> MsgSend {
+        match &res.messages[0].msg {
+            cosmwasm_std::CosmosMsg::Any(any) => {
+                assert_eq!(any.type_url, MSG_EXEC);
+                let exec = MsgExec::decode(any.value.as_slice()).unwrap();
+                MsgSend::decode(exec.msgs[0].value.as_slice()).unwrap()
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn pull_funds_is_one_shot_for_the_committed_tokens() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("lop"), &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        assert_eq!(query::get_order_details(deps.as_ref()).unwrap().funded_at, None);
+
+        // nothing to pay out or refund yet
+        env.block.time = env.block.time.plus_seconds(400);
+        let taker = message_info(&addr("taker"), &[]);
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), ExecuteMsg::Cancel())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Stage(StageError::NotFunded)));
+
+        let anyone = message_info(&addr("anyone"), &[]);
+        let res = execute(deps.as_mut(), env.clone(), anyone.clone(), ExecuteMsg::PullFunds())
+            .unwrap();
+        let send = pull_funds_send(&res);
+        assert_eq!(send.from_address, addr("maker").to_string());
+        assert_eq!(send.to_address, env.contract.address.to_string());
+        assert_eq!(send.amount.len(), 1);
+        assert_eq!((send.amount[0].denom.as_str(), send.amount[0].amount.as_str()), ("uusdc", "100"));
+
+        // timelocks keep counting from the deployment
+        let details = query::get_order_details(deps.as_ref()).unwrap();
+        assert_eq!(details.deployed_at, deployed_at);
+        assert_eq!(details.funded_at, Some(env.block.time.seconds()));
+
+        let err = execute(deps.as_mut(), env.clone(), anyone, ExecuteMsg::PullFunds()).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyFunded));
+
+        execute(deps.as_mut(), env, taker, ExecuteMsg::Cancel()).unwrap();
+    }
+
+    #[test]
+    fn escrow_funded_on_creation_cannot_pull() {
+        let env = mock_env();
+        // the LOP sends the tokens to the escrow's address before instantiating it
+        let mut prefunded = mock_dependencies();
+        prefunded.querier =
+            MockQuerier::new(&[(env.contract.address.as_str(), &[coin(100, "uusdc")])]);
+        let mut funded = mock_dependencies();
+        let cases = [
+            (&mut funded, vec![coin(100, "uusdc")]),
+            (&mut prefunded, vec![]),
+        ];
+
+        for (deps, funds) in cases {
+            instantiate(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("lop"), &funds),
+                instantiate_msg(),
+            )
+            .unwrap();
+            let details = query::get_order_details(deps.as_ref()).unwrap();
+            assert_eq!(details.funded_at, Some(env.block.time.seconds()));
+
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("anyone"), &[]),
+                ExecuteMsg::PullFunds(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::AlreadyFunded));
+        }
+    }
+
+    #[test]
+    fn current_stage_and_allowed_actions() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("lop"), &[coin(100, "uusdc")]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        let allowed = |deps: &CosmicDeps, env: &Env, name: &str| {
+            let msg = QueryMsg::AllowedActions { address: addr(name).to_string() };
+            from_json::<AllowedActionsResponse>(query(deps.as_ref(), env.clone(), msg).unwrap())
+                .unwrap()
+                .actions
+        };
+
+        env.block.time = env.block.time.plus_seconds(40);
+        let res: CurrentStageResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::CurrentStage {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            CurrentStageResponse {
+                stage: Stage::PrivateWithdrawal,
+                starts_at: deployed_at + 10,
+                ends_at: Some(deployed_at + 100),
+                seconds_until_next: Some(60),
+            }
+        );
+        assert_eq!(allowed(&deps, &env, "taker"), vec![Action::Withdraw]);
+        assert_eq!(allowed(&deps, &env, "anyone"), vec![]);
+
+        env.block.time = env.block.time.plus_seconds(360);
+        let res: CurrentStageResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::CurrentStage {}).unwrap())
+                .unwrap();
+        assert_eq!(res.stage, Stage::PublicCancellation);
+        assert_eq!((res.starts_at, res.ends_at, res.seconds_until_next), (deployed_at + 400, None, None));
+        assert_eq!(
+            allowed(&deps, &env, "taker"),
+            vec![Action::Cancel, Action::PublicCancel]
+        );
+        assert_eq!(allowed(&deps, &env, "anyone"), vec![Action::PublicCancel]);
+    }
+
+    #[test]
+    fn settled_escrows_allow_nothing() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("lop"), &[coin(100, "uusdc")]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(400);
+        let taker = message_info(&addr("taker"), &[]);
+        execute(deps.as_mut(), env.clone(), taker, ExecuteMsg::Cancel()).unwrap();
+
+        for name in ["taker", "anyone"] {
+            let msg = QueryMsg::AllowedActions { address: addr(name).to_string() };
+            let res: AllowedActionsResponse =
+                from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            assert_eq!(res.actions, vec![]);
+        }
+    }
+
+    #[test]
+    fn queries_match_the_destination_escrow() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let deployed_at = env.block.time.seconds();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("lop"), &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        let status = |deps: &CosmicDeps| {
+            from_json::<StatusResponse>(query(deps.as_ref(), mock_env(), QueryMsg::Status {}).unwrap())
+                .unwrap()
+                .status
+        };
+        assert_eq!(status(&deps), EscrowStatus::Unfunded);
+
+        let res: BalanceResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Balance {}).unwrap()).unwrap();
+        assert_eq!(res.balance, vec![coin(0, "uusdc")]);
+
+        let res: TimelocksResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Timelocks {}).unwrap()).unwrap();
+        assert_eq!(res.deployed_at, deployed_at);
+        assert_eq!(res.timelocks, instantiate_msg().timelocks);
+
+        let res: ConfigResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(res.contract, CONTRACT_NAME);
+        assert_eq!((res.rescue_delay, res.factory), (1000, None));
+
+        execute(deps.as_mut(), env.clone(), message_info(&addr("anyone"), &[]), ExecuteMsg::PullFunds())
+            .unwrap();
+        assert_eq!(status(&deps), EscrowStatus::Active);
+
+        env.block.time = env.block.time.plus_seconds(300);
+        execute(deps.as_mut(), env, message_info(&addr("taker"), &[]), ExecuteMsg::Cancel()).unwrap();
+        assert_eq!(status(&deps), EscrowStatus::Cancelled);
+    }
+
+    #[test]
+    fn migrated_escrows_count_as_funded() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+        STATE
+            .save(
+                deps.as_mut().storage,
+                &State {
+                    deployed_at: 42,
+                    rescue_delay: 1000,
+                    funded_at: None,
+                },
+            )
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(STATE.load(deps.as_ref().storage).unwrap().funded_at, Some(42));
+    }
+}
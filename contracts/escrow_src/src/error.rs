@@ -1,6 +1,10 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
+use cosmic_common::funds::FundsError;
+use cosmic_common::hashlock::SecretError;
+use cosmic_common::stages::StageError;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
@@ -11,24 +15,21 @@ pub enum ContractError {
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 
-    #[error("Only Taker can call")]
-    OnlyTaker,
-
-    #[error("Invalid Secret")]
-    InvalidSecret,
+    #[error("{0}")]
+    Secret(#[from] SecretError),
 
-    #[error("Source withdraw time has not passed")]
-    SrcWithrawTimeLimit,
+    #[error("{0}")]
+    Stage(#[from] StageError),
 
-    #[error("Source cancellation time has passed")]
-    SrcCancelTimeLimit,
+    #[error("Error while withdraw token from escrow: {reason}")]
+    WithdrawError { reason: String },
 
-    #[error("Rescue time has passed")]
-    RescueTimeLimit,
+    #[error("Error while deposit token to escrow: {reason}")]
+    DepositError { reason: String },
 
-    #[error("Error while withdraw token from escrow")]
-    WithdrawError,
+    #[error("{0}")]
+    Funds(#[from] FundsError),
 
-    #[error("Error while deposit token to escrow")]
-    DepositError
+    #[error("Escrow is already funded")]
+    AlreadyFunded,
 }
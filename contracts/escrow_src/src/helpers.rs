@@ -2,12 +2,17 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    to_json_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg,
+    to_json_binary, Addr, Coin, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg,
     WasmQuery,
 };
-use sha3::{ Digest,Keccak256};
 
-use crate::{msg::{ExecuteMsg, GetOrderDetailsResponse, QueryMsg}, ContractError};
+use crate::msg::{
+    BalanceResponse, ConfigResponse, ExecuteMsg, GetOrderDetailsResponse, QueryMsg,
+    RevealedSecretResponse, StatusResponse, TimelocksResponse,
+};
+
+pub use cosmic_common::funds::{check_funds, validate_basket};
+pub use cosmic_common::hashlock::only_valid_secret;
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -45,30 +50,50 @@ impl CwTemplateContract {
         let res: GetOrderDetailsResponse= QuerierWrapper::<CQ>::new(querier).query(&query)?;
         Ok(res)
     }
-}
 
-pub fn only_valid_secret(
-    secret: String,
-    hashlock: Vec<u8>
-) -> Result<(), ContractError> {
+    // The queries below are answered alike by source and destination escrows
 
-    let mut hasher = Keccak256::new();
-    hasher.update(secret.as_bytes());
-    let computed_hash = hasher.finalize();
+    pub fn order_details<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> StdResult<GetOrderDetailsResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::OrderDetails {})
+    }
 
-    if computed_hash.to_vec() != hashlock {
-        return Err(ContractError::InvalidSecret);
+    pub fn timelocks<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<TimelocksResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Timelocks {})
     }
 
-    Ok(())
-}
+    pub fn balance<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<BalanceResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Balance {})
+    }
 
-pub fn only_after(  current_time: u64, value: u64) -> bool  {
-     value > current_time      
-}
+    pub fn status<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<StatusResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Status {})
+    }
 
-pub fn only_before(current_time: u64, value: u64) -> bool {
-     value < current_time 
-}
+    pub fn config<Q: CustomQuery>(&self, querier: &QuerierWrapper<Q>) -> StdResult<ConfigResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::Config {})
+    }
 
+    pub fn revealed_secret<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> StdResult<RevealedSecretResponse> {
+        querier.query_wasm_smart(self.addr(), &QueryMsg::RevealedSecret {})
+    }
+}
 
+/// Whether `escrow` already holds every coin of `tokens`
+pub fn holds_tokens<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    escrow: &Addr,
+    tokens: &[Coin],
+) -> StdResult<bool> {
+    for token in tokens {
+        if querier.query_balance(escrow, &token.denom)?.amount < token.amount {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
@@ -1,195 +1,175 @@
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
-
+    use crate::authz::{grant_send_msg, revoke_send_msg};
     use crate::helpers::CwTemplateContract;
-    use crate::msg::{ExecuteMsg, InstantiateMsg, PullFundsMsg};
+    use crate::msg::{ExecuteMsg, GetOrderDetailsResponse, InstantiateMsg, QueryMsg};
+    use crate::multitest::{cosmic_app, escrow_src_contract, CosmicApp};
+    use crate::query::{AuthzGrantsResponse, CosmicQueryWrapper};
     use crate::state::Timelocks;
-    use cosmwasm_std::testing::MockApi;
-    use cosmwasm_std::{
-        coins, instantiate2_address, Addr, AnyMsg, Api, Coin, CosmosMsg, Empty, Uint128,
-    };
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
-    use injective_std::shim::Any;
-    use injective_std::types::cosmos::authz::v1beta1::{Grant, MsgGrant};
-    use injective_std::types::cosmos::bank::v1beta1::SendAuthorization;
-    use injective_std::types::cosmos::base::v1beta1::Coin as InjectiveCoin;
-    use prost::Message;
+    use cosmwasm_std::{coins, Addr, QueryRequest, Uint128};
+    use cw_multi_test::error::AnyResult;
+    use cw_multi_test::Executor;
     use sha3::{Digest, Keccak256};
 
-    pub fn contract_template() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(
-            crate::contract::execute,
-            crate::contract::instantiate,
-            crate::contract::query,
-        );
-        Box::new(contract)
-    }
-
     const MAKER: &str = "MAKER";
     const TAKER: &str = "TAKER";
     const NATIVE_DENOM: &str = "stake";
 
-    fn mock_app() -> App {
-        AppBuilder::new().build(|router, _, storage| {
+    fn mock_app() -> CosmicApp {
+        let mut app = cosmic_app();
+        let maker = app.api().addr_make(MAKER);
+        let taker = app.api().addr_make(TAKER);
+        app.init_modules(|router, _, storage| {
             router
                 .bank
-                .init_balance(
-                    storage,
-                    &MockApi::default().addr_make(TAKER),
-                    vec![Coin {
-                        denom: NATIVE_DENOM.to_string(),
-                        amount: Uint128::new(1000),
-                    }],
-                )
+                .init_balance(storage, &maker, coins(100, NATIVE_DENOM))
                 .unwrap();
-        })
+            router
+                .bank
+                .init_balance(storage, &taker, coins(1000, NATIVE_DENOM))
+                .unwrap();
+        });
+        app
     }
 
-    fn proper_instantiate() -> (App, CwTemplateContract) {
+    fn proper_instantiate() -> (CosmicApp, CwTemplateContract) {
         let mut app = mock_app();
         let maker = app.api().addr_make(MAKER);
         let taker = app.api().addr_make(TAKER);
 
-        let cw_template_id = app.store_code_with_creator(maker.clone(), contract_template());
-
-        app.init_modules(|router, _, storage| {
-            router
-                .bank
-                .init_balance(storage, &maker, coins(100, "stake"))
-                .unwrap();
-        });
-
-        assert_eq!(
-            app.wrap()
-                .query_balance(&maker, NATIVE_DENOM)
-                .unwrap()
-                .amount,
-            Uint128::new(100)
-        );
+        let cw_template_id = app.store_code_with_creator(maker.clone(), escrow_src_contract());
 
         let order_hash = {
             let mut hasher = Keccak256::new();
             hasher.update(b"orderhash");
-            hex::encode(&hasher.finalize()) //.to_ascii_lowercase()
+            hex::encode(hasher.finalize())
         };
-      
 
         let hashlock = {
             let mut hasher = Keccak256::new();
             hasher.update(b"secret");
-            hex::encode(&hasher.finalize())
+            hex::encode(hasher.finalize())
         };
         //Initiate
         let msg = InstantiateMsg {
             rescue_delay: 1,
             hashlock,
             order_hash,
-            maker: Addr::unchecked("maker"),
-            taker: Addr::unchecked("taker"),
+            maker,
+            taker: taker.clone(),
             timelocks: Timelocks {
-                dest_public_withdrawal:1000,
-                dest_withdrawal:1000,
+                dest_public_withdrawal: 1000,
+                dest_withdrawal: 1000,
                 dest_cancellation: 3000,
                 src_cancellation: 3000,
                 src_withdrawal: 5000,
-                src_public_cancellation:1000,
-                src_public_withdrawal:1000,
+                src_public_cancellation: 1000,
+                src_public_withdrawal: 1000,
             },
-            token: Coin::new(1000u32, "stake"),
+            tokens: coins(100, NATIVE_DENOM),
+            hash_algorithm: Default::default(),
+            refund_surplus: false,
         };
 
         let cw_template_contract_addr = app
-            .instantiate_contract(
-                cw_template_id,
-                Addr::unchecked(TAKER),
-                &msg,
-                &[],
-                "test",
-                None,
-            )
+            .instantiate_contract(cw_template_id, taker, &msg, &[], "test", None)
             .unwrap();
-        let cw_template_contract = CwTemplateContract(cw_template_contract_addr);
-
-   /*
-    *
-    *Note: Can not do integration testing since
-    * MsgAuthz Bank SendAuthorization is not supported yet by multi-test
-    *
-    */
-    /* 
-    // ----------------------- D3AD C0DE -----------------------
-        let code_info_response = app.wrap().query_wasm_code_info(cw_template_id).unwrap();
-
-        let contract_addr = instantiate2_address(
-            code_info_response.checksum.as_slice(),
-            &app.api().addr_canonicalize(maker.as_str()).unwrap(),
-            order_hash.as_bytes(),
-        )
-        .unwrap();
-        let bank_spend_auth =  SendAuthorization{
-            allow_list: vec![maker.to_string()],
-            spend_limit: vec![InjectiveCoin {
-                amount: String::from("1"),
-                denom: String::from("stake"),
-            }],
-        };
 
-        let authorization = Any {
-            type_url: "/cosmos.bank.v1beta1.SendAuthorization".to_string(),
-            value: bank_spend_auth.encode_to_vec(),
-        };
+        (app, CwTemplateContract(cw_template_contract_addr))
+    }
 
-        let msg_grant = MsgGrant {
-            granter: maker.to_string(),
-            grantee: cw_template_contract_addr.to_string(),
-            grant: Some(Grant {
-                expiration: None,
-                authorization: Some(authorization),
-            }),
-        };
+    fn balance(app: &CosmicApp, addr: &Addr) -> Uint128 {
+        app.wrap().query_balance(addr, NATIVE_DENOM).unwrap().amount
+    }
 
-        let cosmos_msg_grant = CosmosMsg::Any(AnyMsg {
-            type_url: "/cosmos.authz.v1beta1.MsgGrant".to_string(),
-            value: msg_grant.into(),
-        });
+    fn pull_funds(app: &mut CosmicApp, escrow: &CwTemplateContract) -> AnyResult<()> {
+        let anyone = app.api().addr_make("anyone");
+        app.execute(anyone, escrow.call(ExecuteMsg::PullFunds()).unwrap())
+            .map(|_| ())
+    }
 
-       let err = app.execute(maker.clone(), cosmos_msg_grant).unwrap_err();
+    fn funded_at(app: &CosmicApp, escrow: &CwTemplateContract) -> Option<u64> {
+        let details: GetOrderDetailsResponse = app
+            .wrap()
+            .query_wasm_smart(escrow.addr(), &QueryMsg::OrderDetails {})
+            .unwrap();
+        details.funded_at
+    }
 
-        println!(" hellor there {:?}" , err.to_string() );
+    mod pull_funds {
+        use super::*;
 
-      
+        #[test]
+        fn without_grant_fails() {
+            let (mut app, escrow) = proper_instantiate();
+            pull_funds(&mut app, &escrow).unwrap_err();
+            assert_eq!(funded_at(&app, &escrow), None);
+        }
 
-        let pull_funds_msg = ExecuteMsg::PullFunds(PullFundsMsg {
-            from: maker,
-            amount: Coin {
-                denom: "stake".to_string(),
-                amount: Uint128::from_str("1").unwrap(),
-            },
-        });
+        #[test]
+        fn pulls_the_committed_tokens_once() {
+            let (mut app, escrow) = proper_instantiate();
+            let maker = app.api().addr_make(MAKER);
 
-        let cw_msg = cw_template_contract.call(pull_funds_msg).unwrap();
+            // the grant leaves room for a second pull, the escrow must not use it
+            let grant = grant_send_msg(&maker, &escrow.addr(), &coins(200, NATIVE_DENOM), None)
+                .unwrap();
+            app.execute(maker.clone(), grant).unwrap();
+
+            // visible through the custom route as well
+            let grants: AuthzGrantsResponse = app
+                .wrap()
+                .query(&QueryRequest::Custom(CosmicQueryWrapper::authz(
+                    &maker,
+                    escrow.addr(),
+                )))
+                .unwrap();
+            assert_eq!(grants.grants[0].spend_limit, coins(200, NATIVE_DENOM));
 
-        app.execute(taker, cw_msg);
+            app.update_block(|block| block.time = block.time.plus_seconds(5));
+            pull_funds(&mut app, &escrow).unwrap();
+            assert_eq!(balance(&app, &escrow.addr()), Uint128::new(100));
+            assert_eq!(balance(&app, &maker), Uint128::zero());
+            assert_eq!(funded_at(&app, &escrow), Some(app.block_info().time.seconds()));
 
-        app.router().querier(, storage, block_info)
-          // ----------------------- END -----------------------
-        */
+            pull_funds(&mut app, &escrow).unwrap_err();
+            assert_eq!(balance(&app, &escrow.addr()), Uint128::new(100));
+        }
 
-        (app, cw_template_contract)
-    }
+        #[test]
+        fn revoked_grant_cannot_be_used() {
+            let (mut app, escrow) = proper_instantiate();
+            let maker = app.api().addr_make(MAKER);
 
-    mod count {
-        use super::*;
-        use crate::msg::ExecuteMsg;
+            let grant = grant_send_msg(&maker, &escrow.addr(), &coins(100, NATIVE_DENOM), None)
+                .unwrap();
+            app.execute(maker.clone(), grant).unwrap();
+            app.execute(maker.clone(), revoke_send_msg(&maker, &escrow.addr()).unwrap())
+                .unwrap();
+
+            pull_funds(&mut app, &escrow).unwrap_err();
+            assert_eq!(balance(&app, &maker), Uint128::new(100));
+        }
 
         #[test]
-        fn initiated_successfully() {
-            let (mut app, cw_template_contract) = proper_instantiate();
-            assert!(true);
-            // let msg = ExecuteMsg::Increment {};
-            // let cosmos_msg = cw_template_contract.call(msg).unwrap();
-            // app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        fn expired_grant_cannot_be_used() {
+            let (mut app, escrow) = proper_instantiate();
+            let maker = app.api().addr_make(MAKER);
+
+            let expiration = app.block_info().time.plus_seconds(10);
+            let grant = grant_send_msg(
+                &maker,
+                &escrow.addr(),
+                &coins(100, NATIVE_DENOM),
+                Some(expiration),
+            )
+            .unwrap();
+            app.execute(maker, grant).unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(10));
+            pull_funds(&mut app, &escrow).unwrap_err();
+            // a failed pull leaves the escrow unfunded
+            assert_eq!(funded_at(&app, &escrow), None);
         }
     }
 }
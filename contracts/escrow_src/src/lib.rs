@@ -1,3 +1,4 @@
+pub mod authz;
 pub mod contract;
 mod error;
 pub mod msg;
@@ -6,4 +7,8 @@ pub mod encode_helpers;
 pub mod query;
 pub mod integration_tests;
 pub mod helpers;
+pub mod migrations;
+pub mod stages;
+#[cfg(any(test, feature = "multitest"))]
+pub mod multitest;
 pub use crate::error::ContractError;
@@ -0,0 +1,77 @@
+use cosmwasm_std::{Addr, Coin, StdResult, Storage};
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{HashAlgorithm, Immutables, Timelocks, IMMUTABLES};
+
+/// Immutables layout up to v0.1.0, holding a single coin
+#[derive(Serialize, Deserialize)]
+struct LegacyImmutables {
+    order_hash: Vec<u8>,
+    hashlock: Vec<u8>,
+    maker: Addr,
+    taker: Addr,
+    token: Coin,
+    timelocks: Timelocks,
+    #[serde(default)]
+    hash_algorithm: HashAlgorithm,
+}
+
+const LEGACY_IMMUTABLES: Item<LegacyImmutables> = Item::new("Immutables");
+
+/// Turn the single `token` of a v0.1.0 escrow into a one-coin basket
+pub fn migrate_immutables(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_IMMUTABLES.load(storage)?;
+    IMMUTABLES.save(
+        storage,
+        &Immutables {
+            order_hash: legacy.order_hash,
+            hashlock: legacy.hashlock,
+            maker: legacy.maker,
+            taker: legacy.taker,
+            tokens: vec![legacy.token],
+            timelocks: legacy.timelocks,
+            hash_algorithm: legacy.hash_algorithm,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn single_token_becomes_basket() {
+        let mut storage = MockStorage::new();
+        let timelocks = Timelocks {
+            dest_withdrawal: 1,
+            dest_public_withdrawal: 2,
+            dest_cancellation: 3,
+            src_cancellation: 4,
+            src_withdrawal: 5,
+            src_public_withdrawal: 6,
+            src_public_cancellation: 7,
+        };
+        LEGACY_IMMUTABLES
+            .save(
+                &mut storage,
+                &LegacyImmutables {
+                    order_hash: vec![1],
+                    hashlock: vec![2],
+                    maker: Addr::unchecked("maker"),
+                    taker: Addr::unchecked("taker"),
+                    token: Coin::new(1000u32, "stake"),
+                    timelocks,
+                    hash_algorithm: HashAlgorithm::Keccak256,
+                },
+            )
+            .unwrap();
+
+        migrate_immutables(&mut storage).unwrap();
+
+        let immutables = IMMUTABLES.load(&storage).unwrap();
+        assert_eq!(immutables.tokens, vec![Coin::new(1000u32, "stake")]);
+        assert_eq!(immutables.maker, Addr::unchecked("maker"));
+    }
+}
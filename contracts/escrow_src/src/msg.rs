@@ -1,9 +1,9 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, AnyMsg, Coin, CosmosMsg, StdResult};
+use cosmwasm_std::{Addr, AnyMsg, Binary, Coin, CosmosMsg, StdResult};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
-use crate::state::Timelocks;
+use crate::state::{Action, EscrowStatus, HashAlgorithm, Stage, Timelocks};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -12,13 +12,19 @@ pub struct InstantiateMsg {
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// send funds beyond `tokens` back to the sender instead of failing
+    #[serde(default)]
+    pub refund_surplus: bool,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    PullFunds(PullFundsMsg),
+    /// Pull exactly `tokens` from the maker under its authz grant, once
+    PullFunds(),
     Withdraw(WithdrawMsg),
     WithdrawTo(WithdrawToMsg),
     PublicWithdraw(WithdrawMsg),
@@ -28,12 +34,13 @@ pub enum ExecuteMsg {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct WithdrawMsg {
-    pub secret: String,
+    /// preimage of the hashlock, raw bytes
+    pub secret: Binary,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct WithdrawToMsg {
-    pub secret: String,
+    pub secret: Binary,
     pub tagret: Addr,
 }
 
@@ -43,29 +50,96 @@ pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     #[returns(GetOrderDetailsResponse)]
     OrderDetails {},
+    #[returns(TimelocksResponse)]
+    Timelocks {},
+    /// What the escrow currently holds of its tokens
+    #[returns(BalanceResponse)]
+    Balance {},
+    #[returns(StatusResponse)]
+    Status {},
+    #[returns(ConfigResponse)]
+    Config {},
+    /// The hashlock preimage, once the escrow has been withdrawn
+    #[returns(RevealedSecretResponse)]
+    RevealedSecret {},
+    /// Send authorizations the maker gave this escrow, served by the `authz` custom route
+    #[returns(crate::query::AuthzGrantsResponse)]
+    MakerGrants {},
+    /// Timelock stage at the current block time
+    #[returns(CurrentStageResponse)]
+    CurrentStage {},
+    /// Timelocked messages `address` could call at the current block time,
+    /// withdrawals still need the right secret
+    #[returns(AllowedActionsResponse)]
+    AllowedActions { address: String },
 }
 
 // We define a custom struct for each query response
 #[cw_serde]
 pub struct GetOrderDetailsResponse {
     pub deployed_at: u64 ,
+    pub funded_at: Option<u64>,
     pub rescue_delay: u64,
     pub order_hash: String,
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
-pub struct PullFundsMsg {
-    pub from: Addr,
-    /// the tokens to be sell
-    pub amount: Coin,
-   
+#[cw_serde]
+pub struct TimelocksResponse {
+    /// unix timestamp (in sec) the timelock offsets count from
+    pub deployed_at: u64,
+    pub timelocks: Timelocks,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    /// one coin per escrowed denom, zero before funding and once paid out
+    pub balance: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct StatusResponse {
+    pub status: EscrowStatus,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub contract: String,
+    pub version: String,
+    pub rescue_delay: u64,
+    pub hash_algorithm: HashAlgorithm,
+    /// factory notified of the revealed secret, source escrows have none
+    pub factory: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RevealedSecretResponse {
+    pub secret: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct CurrentStageResponse {
+    pub stage: Stage,
+    /// unix timestamp (in sec) the stage started at
+    pub starts_at: u64,
+    /// unix timestamp (in sec) the next stage starts at, `None` in the last stage
+    pub ends_at: Option<u64>,
+    pub seconds_until_next: Option<u64>,
+}
+
+#[cw_serde]
+pub struct AllowedActionsResponse {
+    pub actions: Vec<Action>,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 pub fn create_stargate_msg(type_url: &str, value: Vec<u8>) -> StdResult<CosmosMsg> {
     Ok(CosmosMsg::Any(AnyMsg {
         type_url: type_url.to_string(),
@@ -0,0 +1,287 @@
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    to_json_binary, Addr, AnyMsg, Api, BankMsg, Binary, BlockInfo, Coin, Coins, CosmosMsg,
+    CustomMsg, CustomQuery, DenomMetadata, Empty, GrpcQuery, Querier, StdResult, Storage,
+};
+use cw_multi_test::error::{anyhow, bail, AnyResult};
+use cw_multi_test::{
+    App, AppResponse, BankKeeper, BasicAppBuilder, Contract, ContractWrapper, CosmosRouter,
+    DistributionKeeper, GovFailingModule, IbcFailingModule, Module, StakeKeeper, Stargate,
+    WasmKeeper,
+};
+use cw_storage_plus::Map;
+use injective_std::types::cosmos::authz::v1beta1::{
+    MsgExec, MsgGrant, MsgRevoke, QueryGrantsRequest,
+};
+use injective_std::types::cosmos::bank::v1beta1::MsgSend;
+use prost::Message;
+use serde::de::DeserializeOwned;
+
+use crate::authz::{encode_send_grants, SendGrant, MSG_GRANT, MSG_REVOKE, QUERY_GRANTS};
+use crate::contract::{MSG_BANK_SEND, MSG_EXEC};
+use crate::query::{AuthzGrantsResponse, CosmicQuery, CosmicQueryWrapper, DenomMetadataResponse};
+
+/// One send authorization per (granter, grantee), like the authz keeper
+const GRANTS: Map<(&str, &str), SendGrant> = Map::new("cosmic_grants");
+const DENOM_METADATA: Map<&str, DenomMetadata> = Map::new("cosmic_denom_metadata");
+
+/// Answers the `cosmic` custom query routes from grants and metadata set up by the test
+#[derive(Default)]
+pub struct CosmicModule;
+
+impl CosmicModule {
+    /// Set the grant `granter` gave `grantee`, replacing any previous one
+    pub fn set_grant(
+        &self,
+        storage: &mut dyn Storage,
+        granter: &Addr,
+        grantee: &Addr,
+        grant: SendGrant,
+    ) -> StdResult<()> {
+        GRANTS.save(storage, (granter.as_str(), grantee.as_str()), &grant)
+    }
+
+    pub fn revoke_grants(&self, storage: &mut dyn Storage, granter: &Addr, grantee: &Addr) {
+        GRANTS.remove(storage, (granter.as_str(), grantee.as_str()));
+    }
+
+    pub fn set_denom_metadata(
+        &self,
+        storage: &mut dyn Storage,
+        metadata: DenomMetadata,
+    ) -> StdResult<()> {
+        DENOM_METADATA.save(storage, &metadata.base.clone(), &metadata)
+    }
+}
+
+impl Module for CosmicModule {
+    type ExecT = Empty;
+    type QueryT = CosmicQueryWrapper;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _sender: Addr,
+        _msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("the cosmic routes only serve queries")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        match request.query_data {
+            CosmicQuery::Authz { granter, grantee } => {
+                let grants = GRANTS
+                    .may_load(storage, (granter.as_str(), grantee.as_str()))?
+                    .into_iter()
+                    .collect();
+                Ok(to_json_binary(&AuthzGrantsResponse { grants })?)
+            }
+            CosmicQuery::DenomMetadata { denom } => match DENOM_METADATA.may_load(storage, &denom)? {
+                Some(metadata) => Ok(to_json_binary(&DenomMetadataResponse { metadata })?),
+                None => bail!("no metadata for denom {denom}"),
+            },
+        }
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("the cosmic routes only serve queries")
+    }
+}
+
+/// Handles authz `MsgGrant`, `MsgRevoke` and `MsgExec` of bank sends, spending
+/// down the grant's limit, and answers `Query/Grants` over gRPC
+#[derive(Default)]
+pub struct AuthzStargate;
+
+impl AuthzStargate {
+    fn exec_send<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        grantee: &Addr,
+        send: MsgSend,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let key = (send.from_address.as_str(), grantee.as_str());
+        let Some(mut grant) = GRANTS.may_load(storage, key)? else {
+            bail!("authorization not found for {} by {}", grantee, send.from_address);
+        };
+        if grant.expiration.is_some_and(|expiration| expiration <= block.time) {
+            bail!("authorization expired");
+        }
+        if !grant.allow_list.is_empty() && !grant.allow_list.contains(&send.to_address) {
+            bail!("cannot send to {} address", send.to_address);
+        }
+
+        let amount = send
+            .amount
+            .into_iter()
+            .map(|coin| -> AnyResult<Coin> {
+                Ok(Coin::new(coin.amount.parse::<u128>()?, coin.denom))
+            })
+            .collect::<AnyResult<Vec<_>>>()?;
+        let mut limit = Coins::try_from(grant.spend_limit.clone())?;
+        for coin in &amount {
+            limit
+                .sub(coin.clone())
+                .map_err(|_| anyhow!("requested amount is more than spend limit"))?;
+        }
+
+        if limit.is_empty() {
+            GRANTS.remove(storage, key);
+        } else {
+            grant.spend_limit = limit.into_vec();
+            GRANTS.save(storage, key, &grant)?;
+        }
+
+        let granter = api.addr_validate(&send.from_address)?;
+        let msg = CosmosMsg::Bank(BankMsg::Send {
+            to_address: send.to_address,
+            amount,
+        });
+        router.execute(api, storage, block, granter, msg)
+    }
+}
+
+impl Stargate for AuthzStargate {
+    fn execute_any<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: AnyMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match msg.type_url.as_str() {
+            MSG_GRANT => {
+                let msg = MsgGrant::decode(msg.value.as_slice())?;
+                if msg.granter != sender.as_str() {
+                    bail!("granter {} is not the signer {}", msg.granter, sender);
+                }
+                let grant = msg.grant.ok_or_else(|| anyhow!("missing grant"))?;
+                let grant = SendGrant::from_proto(grant)?
+                    .ok_or_else(|| anyhow!("only bank send authorizations are supported"))?;
+                GRANTS.save(storage, (msg.granter.as_str(), msg.grantee.as_str()), &grant)?;
+                Ok(AppResponse::default())
+            }
+            MSG_REVOKE => {
+                let msg = MsgRevoke::decode(msg.value.as_slice())?;
+                if msg.granter != sender.as_str() {
+                    bail!("granter {} is not the signer {}", msg.granter, sender);
+                }
+                let key = (msg.granter.as_str(), msg.grantee.as_str());
+                if !GRANTS.has(storage, key) {
+                    bail!("authorization not found");
+                }
+                GRANTS.remove(storage, key);
+                Ok(AppResponse::default())
+            }
+            MSG_EXEC => {
+                let msg = MsgExec::decode(msg.value.as_slice())?;
+                if msg.grantee != sender.as_str() {
+                    bail!("grantee {} is not the signer {}", msg.grantee, sender);
+                }
+                let mut response = AppResponse::default();
+                for inner in msg.msgs {
+                    if inner.type_url != MSG_BANK_SEND {
+                        bail!("unsupported authz message {}", inner.type_url);
+                    }
+                    let send = MsgSend::decode(inner.value.as_slice())?;
+                    let res = self.exec_send(api, storage, router, block, &sender, send)?;
+                    response.events.extend(res.events);
+                }
+                Ok(response)
+            }
+            type_url => bail!("unsupported stargate message {type_url}"),
+        }
+    }
+
+    fn query_grpc(
+        &self,
+        _api: &dyn Api,
+        storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: GrpcQuery,
+    ) -> AnyResult<Binary> {
+        if request.path != QUERY_GRANTS {
+            bail!("unsupported grpc query {}", request.path);
+        }
+        let request = QueryGrantsRequest::decode(request.data.as_slice())?;
+        let grants: Vec<_> = GRANTS
+            .may_load(storage, (request.granter.as_str(), request.grantee.as_str()))?
+            .into_iter()
+            .collect();
+        Ok(encode_send_grants(&grants))
+    }
+}
+
+pub type CosmicApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    CosmicModule,
+    WasmKeeper<Empty, CosmicQueryWrapper>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcFailingModule,
+    GovFailingModule,
+    AuthzStargate,
+>;
+
+/// App with the `cosmic` routes wired in as its custom module and authz bank
+/// sends served by `AuthzStargate`
+pub fn cosmic_app() -> CosmicApp {
+    BasicAppBuilder::<Empty, CosmicQueryWrapper>::new_custom()
+        .with_custom(CosmicModule)
+        .with_stargate(AuthzStargate)
+        .build(|_, _, _| {})
+}
+
+pub fn escrow_src_contract() -> Box<dyn Contract<Empty, CosmicQueryWrapper>> {
+    Box::new(
+        ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        )
+        .with_reply(crate::contract::reply)
+        .with_migrate(crate::contract::migrate),
+    )
+}
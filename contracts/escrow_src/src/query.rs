@@ -1,12 +1,18 @@
-use cosmwasm_std::{CosmosMsg, CustomMsg};
+use cosmwasm_std::{
+    Addr, CosmosMsg, CustomMsg, CustomQuery, DenomMetadata, QuerierWrapper, QueryRequest,
+    StdResult,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::authz::{query_send_grants, SendGrant};
+
 /// InjectiveRoute is enum type to represent injective query route path
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CosmicRoute {
     Authz,
+    Bank,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -20,7 +26,76 @@ pub struct CosmicQueryWrapper {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CosmicQuery {
-    Authz { }
+    /// bank send authorizations `granter` gave `grantee`
+    Authz { granter: String, grantee: String },
+    /// bank metadata (display denom, exponent) of `denom`
+    DenomMetadata { denom: String },
+}
+
+impl CustomQuery for CosmicQueryWrapper {}
+
+impl CosmicQueryWrapper {
+    pub fn authz(granter: impl Into<String>, grantee: impl Into<String>) -> Self {
+        CosmicQueryWrapper {
+            route: CosmicRoute::Authz,
+            query_data: CosmicQuery::Authz {
+                granter: granter.into(),
+                grantee: grantee.into(),
+            },
+        }
+    }
+
+    pub fn denom_metadata(denom: impl Into<String>) -> Self {
+        CosmicQueryWrapper {
+            route: CosmicRoute::Bank,
+            query_data: CosmicQuery::DenomMetadata {
+                denom: denom.into(),
+            },
+        }
+    }
+}
+
+#[cosmwasm_schema::cw_serde]
+pub struct AuthzGrantsResponse {
+    pub grants: Vec<SendGrant>,
+}
+
+#[cosmwasm_schema::cw_serde]
+pub struct DenomMetadataResponse {
+    pub metadata: DenomMetadata,
+}
+
+/// Typed access to the `cosmic` custom query routes
+pub struct CosmicQuerier<'a> {
+    querier: &'a QuerierWrapper<'a, CosmicQueryWrapper>,
+}
+
+impl<'a> CosmicQuerier<'a> {
+    pub fn new(querier: &'a QuerierWrapper<'a, CosmicQueryWrapper>) -> Self {
+        CosmicQuerier { querier }
+    }
+
+    pub fn authz_grants(&self, granter: &Addr, grantee: &Addr) -> StdResult<AuthzGrantsResponse> {
+        let request = CosmicQueryWrapper::authz(granter, grantee);
+        self.querier.query(&QueryRequest::Custom(request))
+    }
+
+    pub fn denom_metadata(&self, denom: impl Into<String>) -> StdResult<DenomMetadataResponse> {
+        let request = CosmicQueryWrapper::denom_metadata(denom);
+        self.querier.query(&QueryRequest::Custom(request))
+    }
+}
+
+/// Answer `CosmicQuery::Authz` from the chain's authz module over gRPC, for
+/// chains without a binding for the custom route
+pub fn authz_grants<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    granter: &Addr,
+    grantee: &Addr,
+) -> StdResult<AuthzGrantsResponse> {
+    Ok(AuthzGrantsResponse {
+        grants: query_send_grants(querier, granter, grantee)?,
+    })
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -0,0 +1,196 @@
+use cosmwasm_std::Addr;
+
+use cosmic_common::stages::{self, EscrowTerms};
+
+use crate::state::{Action, EscrowStatus, Immutables, Side, Stage, State};
+use crate::ContractError;
+
+/// The escrow's terms for the shared stage rules
+fn terms<'a>(
+    immutables: &'a Immutables,
+    state: &'a State,
+    settled: Option<EscrowStatus>,
+) -> EscrowTerms<'a> {
+    let status = match (settled, state.funded_at) {
+        (Some(status), _) => status,
+        (None, Some(_)) => EscrowStatus::Active,
+        (None, None) => EscrowStatus::Unfunded,
+    };
+    EscrowTerms {
+        side: Side::Src,
+        status,
+        taker: &immutables.taker,
+        factory: None,
+        timelocks: &immutables.timelocks,
+        deployed_at: state.deployed_at,
+        rescue_delay: state.rescue_delay,
+    }
+}
+
+/// Decides whether `caller` may run `action` at `now`, returning the current
+/// stage. Every timelocked execute handler goes through here, nothing runs
+/// before the escrow is funded or once it is `settled`.
+pub fn authorize(
+    immutables: &Immutables,
+    state: &State,
+    settled: Option<EscrowStatus>,
+    caller: &Addr,
+    action: Action,
+    now: u64,
+) -> Result<Stage, ContractError> {
+    let terms = terms(immutables, state, settled);
+    Ok(stages::authorize(&terms, caller, action, now)?)
+}
+
+/// Timelocked actions `caller` could run at `now`, in `Action::ALL` order
+pub fn allowed_actions(
+    immutables: &Immutables,
+    state: &State,
+    settled: Option<EscrowStatus>,
+    caller: &Addr,
+    now: u64,
+) -> Vec<Action> {
+    stages::allowed_actions(&terms(immutables, state, settled), caller, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Timelocks;
+    use cosmic_common::stages::StageError;
+    use cosmwasm_std::testing::MockApi;
+
+    const DEPLOYED_AT: u64 = 1_000;
+
+    /// Outcome of an `authorize` call
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Expect {
+        Allowed,
+        OnlyTaker,
+        NotInWithdrawal,
+        NotInCancellation,
+        Settled,
+    }
+    use Expect::{
+        Allowed as Y, NotInCancellation as C, NotInWithdrawal as W, OnlyTaker as T, Settled as S,
+    };
+
+    /// Columns: withdraw, public_withdraw, cancel and public_cancel, each
+    /// called by the taker, the maker and a third party. A settled escrow
+    /// refuses everything.
+    const MATRIX: [(Stage, Option<EscrowStatus>, [Expect; 12]); 6] = [
+        (Stage::FinalityLock, None, [W, T, T, W, W, W, C, T, T, C, C, C]),
+        (Stage::PrivateWithdrawal, None, [Y, T, T, W, W, W, C, T, T, C, C, C]),
+        (Stage::PublicWithdrawal, None, [Y, T, T, Y, Y, Y, C, T, T, C, C, C]),
+        (Stage::PrivateCancellation, None, [W, T, T, W, W, W, Y, T, T, C, C, C]),
+        (Stage::PublicCancellation, None, [W, T, T, W, W, W, Y, T, T, Y, Y, Y]),
+        (Stage::PublicCancellation, Some(EscrowStatus::Cancelled), [S; 12]),
+    ];
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    fn immutables() -> Immutables {
+        Immutables {
+            order_hash: vec![0xab; 32],
+            hashlock: vec![0xcd; 32],
+            maker: addr("maker"),
+            taker: addr("taker"),
+            tokens: vec![],
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 100,
+                dest_cancellation: 200,
+                src_withdrawal: 10,
+                src_public_withdrawal: 100,
+                src_cancellation: 300,
+                src_public_cancellation: 400,
+            },
+            hash_algorithm: Default::default(),
+        }
+    }
+
+    fn state() -> State {
+        State {
+            deployed_at: DEPLOYED_AT,
+            rescue_delay: 1000,
+            funded_at: Some(DEPLOYED_AT),
+        }
+    }
+
+    /// First and last second of every stage
+    fn bounds(stage: Stage) -> [u64; 2] {
+        let [start, end] = match stage {
+            Stage::FinalityLock => [0, 10],
+            Stage::PrivateWithdrawal => [10, 100],
+            Stage::PublicWithdrawal => [100, 300],
+            Stage::PrivateCancellation => [300, 400],
+            Stage::PublicCancellation => [400, 10_000],
+            Stage::Cancellation => unreachable!("destination escrows only"),
+        };
+        [DEPLOYED_AT + start, DEPLOYED_AT + end - 1]
+    }
+
+    fn outcome(result: Result<Stage, ContractError>) -> Expect {
+        match result {
+            Ok(_) => Expect::Allowed,
+            Err(ContractError::Stage(err)) => match err {
+                StageError::OnlyTaker => Expect::OnlyTaker,
+                StageError::NotInWithdrawalStage { .. } => Expect::NotInWithdrawal,
+                StageError::NotInCancellationStage { .. } => Expect::NotInCancellation,
+                StageError::AlreadySettled { .. } => Expect::Settled,
+                err => panic!("unexpected error {err}"),
+            },
+            Err(err) => panic!("unexpected error {err}"),
+        }
+    }
+
+    #[test]
+    fn every_stage_action_and_caller() {
+        let (immutables, state) = (immutables(), state());
+        let callers = [addr("taker"), addr("maker"), addr("anyone")];
+
+        for (stage, settled, expected) in MATRIX {
+            for now in bounds(stage) {
+                assert_eq!(immutables.timelocks.stage(Side::Src, DEPLOYED_AT, now), stage);
+                let cells = Action::supported(Side::Src)
+                    .flat_map(|action| callers.iter().map(move |caller| (action, caller)));
+                for ((action, caller), expected) in cells.zip(expected) {
+                    let result = authorize(&immutables, &state, settled, caller, action, now);
+                    assert_eq!(
+                        outcome(result),
+                        expected,
+                        "{} by {caller} at {}",
+                        action.as_str(),
+                        stage.as_str()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn errors_name_the_action_and_stage() {
+        let now = bounds(Stage::PrivateWithdrawal)[0];
+        let err = authorize(&immutables(), &state(), None, &addr("taker"), Action::Cancel, now)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cancel is not allowed in the private_withdrawal stage, it needs a cancellation stage"
+        );
+    }
+
+    #[test]
+    fn nothing_runs_before_funding() {
+        let state = State {
+            funded_at: None,
+            ..state()
+        };
+        let now = bounds(Stage::PublicCancellation)[0];
+        for action in Action::supported(Side::Src) {
+            let err = authorize(&immutables(), &state, None, &addr("taker"), action, now).unwrap_err();
+            assert!(matches!(err, ContractError::Stage(StageError::NotFunded)));
+        }
+    }
+}
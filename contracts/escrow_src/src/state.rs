@@ -1,36 +1,48 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Binary, Coin, StdResult, Storage};
 use cw_storage_plus::Item;
 
+pub use cosmic_common::hashlock::HashAlgorithm;
+pub use cosmic_common::stages::{Action, EscrowStatus};
+pub use cosmic_common::timelocks::{Side, Stage, Timelocks};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Immutables {
     pub order_hash: Vec<u8>,
     pub hashlock: Vec<u8>,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    /// basket of coins held by the escrow, paid out in full
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    /// escrows deployed before the algorithm was configurable use keccak256
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct Timelocks {
-    pub dest_withdrawal: u64,
-    pub dest_public_withdrawal: u64,
-    pub dest_cancellation: u64,
-    pub src_cancellation: u64,
-    pub src_withdrawal:u64,
-    pub src_public_withdrawal:u64,
-    pub src_public_cancellation : u64
-}
-
-
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
     pub deployed_at: u64,
     pub rescue_delay: u64,
+    /// unix timestamp (in sec) the escrow received its tokens, `None` until funded
+    #[serde(default)]
+    pub funded_at: Option<u64>,
 }
 
 pub const STATE: Item<State> = Item::new("state");
 pub const IMMUTABLES: Item<Immutables> =  Item::new("Immutables");
+/// preimage of the hashlock, stored once the escrow has been withdrawn
+pub const REVEALED_SECRET: Item<Binary> = Item::new("revealed_secret");
+/// how the escrow paid out, unset while it still holds the tokens
+pub const SETTLED: Item<EscrowStatus> = Item::new("settled");
+
+/// How the escrow paid out, `None` while it still holds the tokens
+pub fn settled_status(storage: &dyn Storage) -> StdResult<Option<EscrowStatus>> {
+    if let Some(status) = SETTLED.may_load(storage)? {
+        return Ok(Some(status));
+    }
+    // escrows withdrawn before the status was recorded
+    Ok(REVEALED_SECRET.exists(storage).then_some(EscrowStatus::Withdrawn))
+}
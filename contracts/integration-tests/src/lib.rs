@@ -0,0 +1,336 @@
+//! Two chains wired up for a cross-chain swap.
+//!
+//! The maker sells `SRC_DENOM` on the source chain through the limit order
+//! protocol, the taker (resolver) pays `DST_DENOM` into an escrow deployed by
+//! the factory on the destination chain. Both escrows unlock with the same secret.
+
+use cosmwasm_std::{coins, Addr, Binary, Coin, Empty, Uint128};
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{AppResponse, Contract, ContractWrapper, Executor};
+use escrow_src::authz::grant_send_any_msg;
+use escrow_src::multitest::{cosmic_app, escrow_src_contract, CosmicApp};
+use escrow_src::query::CosmicQueryWrapper;
+use sha3::{Digest, Keccak256};
+
+pub const SRC_DENOM: &str = "usrc";
+pub const DST_DENOM: &str = "udst";
+pub const AMOUNT: u128 = 1000;
+pub const SECRET: &[u8] = b"secret";
+
+/// Timelock offsets (in sec) from each escrow's deployment
+pub const SRC_WITHDRAWAL: u64 = 10;
+pub const SRC_PUBLIC_WITHDRAWAL: u64 = 100;
+pub const SRC_CANCELLATION: u64 = 200;
+pub const SRC_PUBLIC_CANCELLATION: u64 = 300;
+pub const DST_WITHDRAWAL: u64 = 10;
+pub const DST_PUBLIC_WITHDRAWAL: u64 = 100;
+pub const DST_CANCELLATION: u64 = 200;
+pub const RESCUE_DELAY: u64 = 1000;
+
+fn lop_contract() -> Box<dyn Contract<Empty, CosmicQueryWrapper>> {
+    Box::new(
+        ContractWrapper::new_with_empty(
+            limit_order_protocol::contract::execute,
+            limit_order_protocol::contract::instantiate,
+            limit_order_protocol::contract::query,
+        ),
+    )
+}
+
+fn factory_contract() -> Box<dyn Contract<Empty, CosmicQueryWrapper>> {
+    Box::new(
+        ContractWrapper::new_with_empty(
+            escrow_factory::contract::execute,
+            escrow_factory::contract::instantiate,
+            escrow_factory::contract::query,
+        )
+        .with_reply_empty(escrow_factory::contract::reply)
+        .with_sudo_empty(escrow_factory::contract::sudo),
+    )
+}
+
+fn escrow_dst_contract() -> Box<dyn Contract<Empty, CosmicQueryWrapper>> {
+    Box::new(
+        ContractWrapper::new_with_empty(
+            escrow_dst::contract::execute,
+            escrow_dst::contract::instantiate,
+            escrow_dst::contract::query,
+        )
+        .with_reply_empty(escrow_dst::contract::reply),
+    )
+}
+
+fn escrow_code(app: &CosmicApp, code_id: u64) -> limit_order_protocol::msg::EscrowCode {
+    limit_order_protocol::msg::EscrowCode {
+        code_id,
+        checksum: app.wrap().query_wasm_code_info(code_id).unwrap().checksum,
+    }
+}
+
+/// The maker's order, with the hash the LOP computes for it
+fn order_msg(
+    src: &CosmicApp,
+    lop: &Addr,
+    maker: &Addr,
+    taker: &Addr,
+    hashlock: &str,
+) -> limit_order_protocol::msg::FillOrderMsg {
+    let mut order = limit_order_protocol::msg::FillOrderMsg {
+        making_amount: Coin::new(AMOUNT, SRC_DENOM),
+        taking_amount: Coin::new(AMOUNT - 100, DST_DENOM),
+        basket: vec![],
+        auction_params: limit_order_protocol::msg::AuctionParameters {
+            duration: 100,
+            start_time: src.block_info().time.seconds(),
+            initial_rate_bump: 0,
+            points: vec![],
+            gas_cost: limit_order_protocol::msg::GasCost {
+                gas_bump_estimate: 0,
+                gas_price_estimate: 0,
+            },
+        },
+        taker_traits: limit_order_protocol::msg::TakerTraits {
+            threshold_taking_price: Uint128::zero(),
+        },
+        immutables: limit_order_protocol::msg::Immutables {
+            rescue_delay: RESCUE_DELAY,
+            order_hash: String::new(),
+            hashlock: hashlock.to_string(),
+            maker: maker.clone(),
+            taker: taker.clone(),
+            timelocks: limit_order_protocol::msg::Timelocks {
+                dest_withdrawal: DST_WITHDRAWAL,
+                dest_public_withdrawal: DST_PUBLIC_WITHDRAWAL,
+                dest_cancellation: DST_CANCELLATION,
+                src_cancellation: SRC_CANCELLATION,
+                src_withdrawal: SRC_WITHDRAWAL,
+                src_public_withdrawal: SRC_PUBLIC_WITHDRAWAL,
+                src_public_cancellation: SRC_PUBLIC_CANCELLATION,
+            },
+            hash_algorithm: Default::default(),
+        },
+        extension: None,
+    };
+    let res: limit_order_protocol::msg::OrderHashResponse = src
+        .wrap()
+        .query_wasm_smart(
+            lop,
+            &limit_order_protocol::msg::QueryMsg::OrderHash {
+                order: order.clone(),
+            },
+        )
+        .unwrap();
+    order.immutables.order_hash = res.order_hash;
+    order
+}
+
+fn keccak_hex(data: &[u8]) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+pub struct Suite {
+    pub src: CosmicApp,
+    pub dst: CosmicApp,
+    pub lop: Addr,
+    pub factory: Addr,
+    pub maker: Addr,
+    pub taker: Addr,
+    /// filled by `fill_order`, its auction starts with the suite
+    pub order: limit_order_protocol::msg::FillOrderMsg,
+    pub order_hash: String,
+    pub hashlock: String,
+}
+
+impl Suite {
+    /// Maker funded on the source chain, taker on the destination chain
+    pub fn new() -> Self {
+        let mut src = cosmic_app();
+        let mut dst = cosmic_app();
+        let maker = src.api().addr_make("maker");
+        let taker = src.api().addr_make("taker");
+        let owner = src.api().addr_make("owner");
+
+        src.init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &maker, coins(AMOUNT, SRC_DENOM))
+                .unwrap();
+        });
+        dst.init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &taker, coins(AMOUNT, DST_DENOM))
+                .unwrap();
+        });
+
+        let escrow_src_code_id = src.store_code(escrow_src_contract());
+        let escrow_dst_code_id = dst.store_code(escrow_dst_contract());
+        let lop_code_id = src.store_code(lop_contract());
+        let lop = src
+            .instantiate_contract(
+                lop_code_id,
+                owner.clone(),
+                &limit_order_protocol::msg::InstantiateMsg {
+                    escrow_src_code: escrow_code(&src, escrow_src_code_id),
+                    escrow_dst_code_id,
+                    owner: None,
+                },
+                &[],
+                "limit-order-protocol",
+                None,
+            )
+            .unwrap();
+
+        let factory_code_id = dst.store_code(factory_contract());
+        let factory = dst
+            .instantiate_contract(
+                factory_code_id,
+                owner,
+                &escrow_factory::msg::InstantiateMsg {
+                    escrow_code_id: escrow_dst_code_id,
+                    owner: None,
+                },
+                &[],
+                "escrow-factory",
+                None,
+            )
+            .unwrap();
+
+        let hashlock = keccak_hex(SECRET);
+        let order = order_msg(&src, &lop, &maker, &taker, &hashlock);
+        Suite {
+            src,
+            dst,
+            lop,
+            factory,
+            maker,
+            taker,
+            order_hash: order.immutables.order_hash.clone(),
+            order,
+            hashlock,
+        }
+    }
+
+    /// Move both chains' clocks forward by `seconds`
+    pub fn advance(&mut self, seconds: u64) {
+        for app in [&mut self.src, &mut self.dst] {
+            app.update_block(|block| {
+                block.time = block.time.plus_seconds(seconds);
+                block.height += seconds / 5 + 1;
+            });
+        }
+    }
+
+    pub fn src_balance(&self, addr: &Addr) -> Uint128 {
+        self.src.wrap().query_balance(addr, SRC_DENOM).unwrap().amount
+    }
+
+    pub fn dst_balance(&self, addr: &Addr) -> Uint128 {
+        self.dst.wrap().query_balance(addr, DST_DENOM).unwrap().amount
+    }
+
+    /// The maker grants the LOP its tokens and the taker fills the order,
+    /// returning the `escrow_src` holding the maker's funds
+    pub fn fill_order(&mut self) -> Addr {
+        let grant = grant_send_any_msg(&self.maker, &self.lop, &coins(AMOUNT, SRC_DENOM), None)
+            .unwrap();
+        self.src.execute(self.maker.clone(), grant).unwrap();
+
+        let msg = limit_order_protocol::msg::ExecuteMsg::FillOrder(self.order.clone());
+        self.src
+            .execute_contract(self.taker.clone(), self.lop.clone(), &msg, &[])
+            .unwrap();
+
+        let order: Option<limit_order_protocol::state::OrderRecord> = self
+            .src
+            .wrap()
+            .query_wasm_smart(
+                &self.lop,
+                &limit_order_protocol::msg::QueryMsg::Order {
+                    order_hash: self.order_hash.clone(),
+                },
+            )
+            .unwrap();
+        order.and_then(|order| order.escrow).unwrap()
+    }
+
+    /// The taker locks its tokens for the maker through the factory,
+    /// returning the deployed `escrow_dst`
+    pub fn deploy_dst(&mut self) -> Addr {
+        let msg = escrow_factory::msg::ExecuteMsg::DeployEscrow(
+            escrow_factory::msg::EscrowInstantiateMsg {
+                rescue_delay: RESCUE_DELAY,
+                order_hash: self.order_hash.clone(),
+                hashlock: self.hashlock.clone(),
+                maker: self.maker.clone(),
+                taker: self.taker.clone(),
+                tokens: coins(AMOUNT, DST_DENOM),
+                timelocks: escrow_factory::msg::Timelocks {
+                    dest_withdrawal: DST_WITHDRAWAL,
+                    dest_public_withdrawal: DST_PUBLIC_WITHDRAWAL,
+                    dest_cancellation: DST_CANCELLATION,
+                    src_cancellation: SRC_CANCELLATION,
+                    src_withdrawal: SRC_WITHDRAWAL,
+                    src_public_withdrawal: SRC_PUBLIC_WITHDRAWAL,
+                    src_public_cancellation: SRC_PUBLIC_CANCELLATION,
+                },
+                hash_algorithm: Default::default(),
+                factory: None,
+                refund_surplus: false,
+                receiver: None,
+            },
+        );
+        self.dst
+            .execute_contract(
+                self.taker.clone(),
+                self.factory.clone(),
+                &msg,
+                &coins(AMOUNT, DST_DENOM),
+            )
+            .unwrap();
+
+        let order: Option<escrow_factory::state::OrderRecord> = self
+            .dst
+            .wrap()
+            .query_wasm_smart(
+                &self.factory,
+                &escrow_factory::msg::QueryMsg::Order {
+                    order_hash: self.order_hash.clone(),
+                },
+            )
+            .unwrap();
+        order.and_then(|order| order.escrow).unwrap()
+    }
+
+    pub fn src_execute(
+        &mut self,
+        sender: &Addr,
+        escrow: &Addr,
+        msg: escrow_src::msg::ExecuteMsg,
+    ) -> AnyResult<AppResponse> {
+        self.src
+            .execute_contract(sender.clone(), escrow.clone(), &msg, &[])
+    }
+
+    pub fn dst_execute(
+        &mut self,
+        sender: &Addr,
+        escrow: &Addr,
+        msg: escrow_dst::msg::ExecuteMsg,
+    ) -> AnyResult<AppResponse> {
+        self.dst
+            .execute_contract(sender.clone(), escrow.clone(), &msg, &[])
+    }
+}
+
+impl Default for Suite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn secret() -> Binary {
+    Binary::from(SECRET)
+}
@@ -0,0 +1,204 @@
+use cosmwasm_std::{Binary, Uint128};
+use escrow_dst::msg::{ExecuteMsg as DstExecuteMsg, WithdrawMsg as DstWithdrawMsg};
+use escrow_src::msg::{ExecuteMsg as SrcExecuteMsg, WithdrawMsg as SrcWithdrawMsg};
+use integration_tests::*;
+
+fn src_withdraw() -> SrcExecuteMsg {
+    SrcExecuteMsg::Withdraw(SrcWithdrawMsg { secret: secret() })
+}
+
+fn dst_withdraw() -> DstExecuteMsg {
+    DstExecuteMsg::Withdraw(DstWithdrawMsg { secret: secret() })
+}
+
+const ZERO: Uint128 = Uint128::zero();
+const FULL: Uint128 = Uint128::new(AMOUNT);
+
+#[test]
+fn happy_path() {
+    let mut suite = Suite::new();
+    let (maker, taker) = (suite.maker.clone(), suite.taker.clone());
+
+    let escrow_src = suite.fill_order();
+    let escrow_dst = suite.deploy_dst();
+    assert_eq!(suite.src_balance(&maker), ZERO);
+    assert_eq!(suite.src_balance(&escrow_src), FULL);
+    assert_eq!(suite.dst_balance(&taker), ZERO);
+    assert_eq!(suite.dst_balance(&escrow_dst), FULL);
+
+    // finality lock: nobody can withdraw yet
+    suite.dst_execute(&taker, &escrow_dst, dst_withdraw()).unwrap_err();
+    suite.src_execute(&taker, &escrow_src, src_withdraw()).unwrap_err();
+
+    suite.advance(DST_WITHDRAWAL);
+
+    // a wrong preimage is rejected
+    let wrong = DstExecuteMsg::Withdraw(DstWithdrawMsg {
+        secret: Binary::from(b"wrong"),
+    });
+    suite.dst_execute(&taker, &escrow_dst, wrong).unwrap_err();
+    // only the taker may withdraw during the private window
+    suite.dst_execute(&maker, &escrow_dst, dst_withdraw()).unwrap_err();
+
+    // the taker reveals the secret on the destination chain, paying the maker
+    suite.dst_execute(&taker, &escrow_dst, dst_withdraw()).unwrap();
+    assert_eq!(suite.dst_balance(&maker), FULL);
+    assert_eq!(suite.dst_balance(&escrow_dst), ZERO);
+
+    // the factory recorded the secret for the resolver to pick up
+    let revealed: escrow_factory::msg::RevealedSecretResponse = suite
+        .dst
+        .wrap()
+        .query_wasm_smart(
+            &suite.factory,
+            &escrow_factory::msg::QueryMsg::RevealedSecret {
+                order_hash: suite.order_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(revealed.secret, Some(secret()));
+
+    // and uses it to claim the maker's funds on the source chain
+    suite.src_execute(&taker, &escrow_src, src_withdraw()).unwrap();
+    assert_eq!(suite.src_balance(&taker), FULL);
+    assert_eq!(suite.src_balance(&escrow_src), ZERO);
+}
+
+#[test]
+fn public_withdrawal() {
+    let mut suite = Suite::new();
+    let (maker, taker) = (suite.maker.clone(), suite.taker.clone());
+    let anyone = suite.src.api().addr_make("anyone");
+
+    let escrow_src = suite.fill_order();
+    let escrow_dst = suite.deploy_dst();
+
+    // private withdrawal only
+    suite.advance(DST_WITHDRAWAL);
+    let public_dst = DstExecuteMsg::PublicWithdraw(DstWithdrawMsg { secret: secret() });
+    let public_src = SrcExecuteMsg::PublicWithdraw(SrcWithdrawMsg { secret: secret() });
+    suite
+        .dst_execute(&anyone, &escrow_dst, public_dst.clone())
+        .unwrap_err();
+    suite
+        .src_execute(&anyone, &escrow_src, public_src.clone())
+        .unwrap_err();
+
+    // once the taker stalls, anyone holding the secret completes the swap
+    suite.advance(DST_PUBLIC_WITHDRAWAL - DST_WITHDRAWAL);
+    suite.dst_execute(&anyone, &escrow_dst, public_dst).unwrap();
+    suite.src_execute(&anyone, &escrow_src, public_src).unwrap();
+
+    assert_eq!(suite.dst_balance(&maker), FULL);
+    assert_eq!(suite.src_balance(&taker), FULL);
+    assert_eq!(suite.dst_balance(&anyone), ZERO);
+    assert_eq!(suite.src_balance(&anyone), ZERO);
+}
+
+#[test]
+fn cancellation() {
+    let mut suite = Suite::new();
+    let (maker, taker) = (suite.maker.clone(), suite.taker.clone());
+
+    let escrow_src = suite.fill_order();
+    let escrow_dst = suite.deploy_dst();
+
+    // the secret is never revealed, cancellation isn't open during withdrawal
+    suite.advance(DST_WITHDRAWAL);
+    suite
+        .dst_execute(&taker, &escrow_dst, DstExecuteMsg::Cancel)
+        .unwrap_err();
+    suite
+        .src_execute(&taker, &escrow_src, SrcExecuteMsg::Cancel())
+        .unwrap_err();
+
+    // after the destination cancellation timelock the taker takes its funds back
+    suite.advance(DST_CANCELLATION - DST_WITHDRAWAL);
+    suite
+        .dst_execute(&taker, &escrow_dst, DstExecuteMsg::Cancel)
+        .unwrap();
+    assert_eq!(suite.dst_balance(&taker), FULL);
+    assert_eq!(suite.dst_balance(&escrow_dst), ZERO);
+
+    // withdrawals close with the source cancellation stage
+    suite.advance(SRC_CANCELLATION - DST_CANCELLATION);
+    suite.src_execute(&taker, &escrow_src, src_withdraw()).unwrap_err();
+
+    // and the maker is refunded
+    suite
+        .src_execute(&taker, &escrow_src, SrcExecuteMsg::Cancel())
+        .unwrap();
+    assert_eq!(suite.src_balance(&maker), FULL);
+    assert_eq!(suite.src_balance(&escrow_src), ZERO);
+}
+
+#[test]
+fn public_cancellation() {
+    let mut suite = Suite::new();
+    let maker = suite.maker.clone();
+    let anyone = suite.src.api().addr_make("anyone");
+
+    let escrow_src = suite.fill_order();
+
+    suite.advance(SRC_CANCELLATION);
+    suite
+        .src_execute(&anyone, &escrow_src, SrcExecuteMsg::PublicCancel())
+        .unwrap_err();
+
+    suite.advance(SRC_PUBLIC_CANCELLATION - SRC_CANCELLATION);
+    suite
+        .src_execute(&anyone, &escrow_src, SrcExecuteMsg::PublicCancel())
+        .unwrap();
+    assert_eq!(suite.src_balance(&maker), FULL);
+    assert_eq!(suite.src_balance(&anyone), ZERO);
+}
+
+#[test]
+fn rescue() {
+    let mut suite = Suite::new();
+    let (maker, taker) = (suite.maker.clone(), suite.taker.clone());
+
+    suite.fill_order();
+    let escrow_dst = suite.deploy_dst();
+
+    suite.advance(DST_CANCELLATION);
+    suite
+        .dst_execute(&taker, &escrow_dst, DstExecuteMsg::RescueFunds)
+        .unwrap_err();
+
+    // funds left behind are recoverable by the taker after the rescue delay
+    suite.advance(RESCUE_DELAY - DST_CANCELLATION);
+    suite
+        .dst_execute(&maker, &escrow_dst, DstExecuteMsg::RescueFunds)
+        .unwrap_err();
+    suite
+        .dst_execute(&taker, &escrow_dst, DstExecuteMsg::RescueFunds)
+        .unwrap();
+    assert_eq!(suite.dst_balance(&taker), FULL);
+    assert_eq!(suite.dst_balance(&escrow_dst), ZERO);
+}
+
+#[test]
+fn clock_cancels_expired_escrows() {
+    let mut suite = Suite::new();
+    let taker = suite.taker.clone();
+    let factory = suite.factory.clone();
+    let clock = escrow_factory::msg::SudoMsg::ClockEndBlock {};
+
+    let escrow_dst = suite.deploy_dst();
+
+    // nothing to do before the cancellation stage
+    suite.advance(DST_CANCELLATION - 1);
+    suite.dst.wasm_sudo(factory.clone(), &clock).unwrap();
+    assert_eq!(suite.dst_balance(&escrow_dst), FULL);
+
+    // the chain's clock refunds the taker without anyone sending a cancel
+    suite.advance(1);
+    suite.dst.wasm_sudo(factory.clone(), &clock).unwrap();
+    assert_eq!(suite.dst_balance(&taker), FULL);
+    assert_eq!(suite.dst_balance(&escrow_dst), ZERO);
+
+    // and the escrow left the index
+    let res = suite.dst.wasm_sudo(factory, &clock).unwrap();
+    assert!(res.events.iter().all(|event| event.ty != "wasm-batch_result"));
+}
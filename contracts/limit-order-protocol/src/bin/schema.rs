@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use limit_order_protocol::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use limit_order_protocol::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
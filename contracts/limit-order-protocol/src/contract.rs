@@ -1,34 +1,63 @@
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{from_json, to_json_binary, Event, Reply, ReplyOn, SubMsg, WasmMsg,entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response,  StdResult, };
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{from_json, to_json_binary, Event, WasmMsg, Binary, Deps, DepsMut, Env, MessageInfo, QuerierWrapper, Reply, Response, StdError, StdResult, SubMsgResult, };
+use cw2::set_contract_version;
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{EscrowInstantiateMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, COMPLETED_ORDERS, STATE};
+use cosmic_common::events::{EscrowEvent, EscrowEventKind};
+use crate::migrations::{ensure_from_older_version, migrate_completed_orders};
+use crate::msg::{
+    ConfigResponse, EscrowCode, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+};
+use cosmic_common::ownership::{assert_owner, get_ownership, initialize_owner};
+use crate::state::{OrderRecord, State, ORDERS, OWNERSHIP, PAUSED, STATE, USED_HASHLOCKS};
+
+/// `stage` attribute of `order_filled`, the source escrow timelocks start after it
+pub const ORDER_FILLED_STAGE: &str = "filled";
+
+/// Reply to each fill of a non-atomic `FillOrders` batch
+pub const FILL_ORDER_REPLY: u64 = 1;
 
-pub const PULL_REPLY: u64 = 1;
-pub const ESCROW_DEPLOY_REPLY: u64 = 2;
-/*
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:limit-order-protocol";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-*/
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    // COMPLETED_ORDERS.save(_deps.storage,  )
     let state = State {
-        escrow_code_id: msg.escrow_code_id,
+        escrow_src_code_id: verify_escrow_code(&deps.querier, &msg.escrow_src_code)?,
+        escrow_dst_code_id: msg.escrow_dst_code_id,
+        migratable_escrows: false,
+        escrow_vault: None,
     };
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     STATE.save(deps.storage, &state)?;
+    PAUSED.save(deps.storage, &false)?;
+    initialize_owner(deps.storage, deps.api, msg.owner.as_deref(), &info.sender)?;
 
     Ok(Response::new())
 }
 
+/// Makes sure `code` is stored on chain with the expected checksum, so the
+/// protocol never spawns an unrelated contract
+fn verify_escrow_code(querier: &QuerierWrapper, code: &EscrowCode) -> Result<u64, ContractError> {
+    let actual = querier.query_wasm_code_info(code.code_id)?.checksum;
+    if actual != code.checksum {
+        return Err(ContractError::ChecksumMismatch {
+            code_id: code.code_id,
+            expected: code.checksum.to_hex(),
+            actual: actual.to_hex(),
+        });
+    }
+    Ok(code.code_id)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -38,19 +67,48 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::FillOrder(msg) => execute::fill_order(deps, env, info, msg),
+        ExecuteMsg::FillOrders { orders, atomic } => {
+            execute::fill_orders(deps, env, info, orders, atomic)
+        }
+        ExecuteMsg::UpdateOwnership(action) => execute::update_ownership(deps, env, info, action),
+        ExecuteMsg::UpdateConfig {
+            escrow_src_code,
+            escrow_dst_code_id,
+            migratable_escrows,
+        } => execute::update_config(
+            deps,
+            info,
+            escrow_src_code,
+            escrow_dst_code_id,
+            migratable_escrows,
+        ),
+        ExecuteMsg::SetEscrowVault { vault } => execute::set_escrow_vault(deps, info, vault),
+        ExecuteMsg::Pause {} => execute::set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => execute::set_paused(deps, info, false),
+        ExecuteMsg::MigrateEscrow {
+            escrow,
+            code_id,
+            msg,
+        } => execute::migrate_escrow(deps, info, escrow, code_id, msg),
     }
 }
 
 pub mod execute {
     use super::*;
-    use crate::msg::{AuctionParameters, EscrowInstantiateMsg, FillOrderMsg};
+    use crate::msg::{
+        AuctionParameters, BasketLeg, CrossChainOrder, EscrowInstantiateMsg, FillOrderMsg,
+        OwnershipAction, VaultCreateMsg, VaultExecuteMsg, VaultSide,
+    };
     use crate::{
         error::ContractError,
-        helpers::{create_stargate_msg, encode_bytes_message},
+        helpers::{
+            create_stargate_msg, encode_bytes_message, escrow_address, has_send_grant,
+            order_hash, Domain,
+        },
     };
     use cosmwasm_std::{
-        to_json_binary, Addr, Coin, DepsMut, Env, MessageInfo, Response, StdResult, SubMsg,
-        Uint128,
+        to_json_binary, Addr, Coin, Coins, CosmosMsg, DepsMut, Empty, Env, MessageInfo,
+        Response, StdResult, SubMsg, Uint128,
     };
     use injective_std::{
         shim::Any,
@@ -60,26 +118,58 @@ pub mod execute {
         },
     };
     use prost::Message;
+    use std::collections::BTreeMap;
 
     pub const MSG_EXEC: &str = "/cosmos.authz.v1beta1.MsgExec";
     pub const MSG_BANK_SEND: &str = "/cosmos.bank.v1beta1.MsgSend";
 
+    /// Funds pulled from each maker earlier in the same transaction, they
+    /// draw on the same grants
+    type Pulled = BTreeMap<Addr, Coins>;
+
     pub fn fill_order(
         deps: DepsMut,
         env: Env,
         _info: MessageInfo,
         msg: FillOrderMsg,
     ) -> Result<Response, ContractError> {
-        match COMPLETED_ORDERS.may_load(deps.storage, msg.immutables.order_hash.clone()).unwrap() {
-            Some(v) =>  {
-                if v == true {
-                    return Err(ContractError::OrderAlreadyProcessed);
-                }
-            }
-            None => {}
+        fill_order_after(deps, env, msg, &mut Pulled::new())
+    }
+
+    /// Fills `msg` once the orders behind `pulled` have been filled in the same transaction
+    fn fill_order_after(
+        deps: DepsMut,
+        env: Env,
+        msg: FillOrderMsg,
+        pulled: &mut Pulled,
+    ) -> Result<Response, ContractError> {
+        if PAUSED.load(deps.storage)? {
+            return Err(ContractError::Paused);
+        }
+
+        // consistency check only, no maker signature is verified: the hash is
+        // recomputed under this chain and contract, so one computed for other
+        // fields or another deployment doesn't match
+        let expected = order_hash(&Domain::new(&env), &msg)?;
+        if msg.immutables.order_hash != expected {
+            return Err(ContractError::InvalidOrderHash {
+                expected,
+                actual: msg.immutables.order_hash,
+            });
+        }
+
+        if ORDERS.has(deps.storage, msg.immutables.order_hash.clone()) {
+            return Err(ContractError::OrderAlreadyProcessed);
+        }
+
+        // hex hashlocks are compared case-insensitively, like the escrows decode them
+        let hashlock = msg.immutables.hashlock.to_ascii_lowercase();
+        if USED_HASHLOCKS.has(deps.storage, hashlock.clone()) {
+            return Err(ContractError::HashlockAlreadyUsed {
+                hashlock: msg.immutables.hashlock,
+            });
+        }
 
-        };
-       
         let block_time = env.block.time.seconds();
 
         if block_time < msg.auction_params.start_time {
@@ -91,23 +181,90 @@ pub mod execute {
             return Err(ContractError::AuctionEndedAlready);
         }
 
-        let current_price = calculate_price(
-            msg.making_amount.amount,
-            msg.taking_amount.amount,
-            &msg.auction_params,
-            block_time,
-        )?;
+        if let Some(extension) = &msg.extension {
+            validate_extension(extension, &env)?;
+        }
+
+        let legs: Vec<BasketLeg> = std::iter::once(BasketLeg {
+            making_amount: msg.making_amount,
+            taking_amount: msg.taking_amount,
+        })
+        .chain(msg.basket)
+        .collect();
+
+        let prices = legs
+            .iter()
+            .map(|leg| {
+                calculate_price(
+                    leg.making_amount.amount,
+                    leg.taking_amount.amount,
+                    &msg.auction_params,
+                    block_time,
+                )
+            })
+            .collect::<StdResult<Vec<_>>>()?;
 
-        // Check if current price agreed by taker
-        if current_price <= msg.taker_traits.threshold_taking_price {
+        // Check if current price agreed by taker, the threshold is set on the primary leg
+        if prices[0] <= msg.taker_traits.threshold_taking_price {
             return Err(ContractError::PriceIsAboveThreshold);
         }
-         //Pull funds from maker to LOP
-        let proto_amount = ProtoCoin {
-            amount: current_price.to_string(),
-            denom: msg.making_amount.denom.clone(),
+
+        // the source escrow locks the maker's making asset, the taking side is
+        // paid out on the destination chain
+        let tokens: Vec<Coin> = legs
+            .iter()
+            .zip(&prices)
+            .map(|(leg, price)| Coin::new(*price, leg.making_amount.denom.clone()))
+            .collect();
+        validate_basket(&tokens)?;
+
+        // the escrow's address is known upfront, so the maker's funds go straight into it.
+        // A vault only accepts funds attached to `Create`, so they pass through the protocol.
+        let state = STATE.load(deps.storage)?;
+        let salt = Binary::from(msg.immutables.order_hash.as_bytes());
+        let (escrow, pull_to) = match &state.escrow_vault {
+            Some(vault) => (vault.clone(), env.contract.address.clone()),
+            None => {
+                let escrow = escrow_address(
+                    &deps.querier,
+                    deps.api,
+                    &env.contract.address,
+                    state.escrow_src_code_id,
+                    &salt,
+                )?;
+                (escrow.clone(), escrow)
+            }
         };
 
+        // without a grant the MsgExec below fails with an opaque authz error
+        let maker = &msg.immutables.maker;
+        let mut pulling = pulled.get(maker).cloned().unwrap_or_default();
+        for token in &tokens {
+            pulling.add(token.clone())?;
+        }
+        if !has_send_grant(
+            &deps.querier,
+            maker,
+            &env.contract.address,
+            &pull_to,
+            &pulling.to_vec(),
+            env.block.time,
+        )? {
+            return Err(ContractError::MissingAuthzGrant {
+                maker: maker.to_string(),
+            });
+        }
+        pulled.insert(maker.clone(), pulling);
+
+         //Pull funds from maker to LOP
+        let proto_amount = tokens
+            .iter()
+            .map(|coin| ProtoCoin {
+                amount: coin.amount.to_string(),
+                denom: coin.denom.clone(),
+            })
+            .collect();
+
         let escrow_playload_msg = EscrowInstantiateMsg {
             hashlock: msg.immutables.hashlock,
             maker: msg.immutables.maker.clone(),
@@ -115,41 +272,293 @@ pub mod execute {
             order_hash: msg.immutables.order_hash.clone(),
             rescue_delay: msg.immutables.rescue_delay,
             timelocks: msg.immutables.timelocks,
-            token: Coin {
-                denom: msg.taking_amount.denom.clone(),
-                amount: current_price,
+            hash_algorithm: msg.immutables.hash_algorithm,
+            tokens,
+        };
+
+        let order = OrderRecord {
+            maker: Some(msg.immutables.maker.clone()),
+            taker: Some(escrow_playload_msg.taker.clone()),
+            filled_at: Some(block_time),
+            escrow: Some(escrow.clone()),
+            extension: msg.extension,
+        };
+        ORDERS.save(deps.storage, msg.immutables.order_hash.clone(), &order)?;
+        USED_HASHLOCKS.save(deps.storage, hashlock, &Empty {})?;
+
+        // plain messages: a failing deploy or pull reverts the whole fill, order record included.
+        // The funds land first, so the escrow sees itself funded when it is instantiated.
+        let lock_msg = match state.escrow_vault {
+            Some(vault) => WasmMsg::Execute {
+                contract_addr: vault.to_string(),
+                msg: to_json_binary(&vault_create_msg(&escrow_playload_msg))?,
+                funds: escrow_playload_msg.tokens.clone(),
+            },
+            None => WasmMsg::Instantiate2 {
+                admin: state
+                    .migratable_escrows
+                    .then(|| env.contract.address.to_string()),
+                code_id: state.escrow_src_code_id,
+                label: format!("Escrow Contract for {}", escrow_playload_msg.order_hash),
+                msg: to_json_binary(&escrow_playload_msg)?,
+                funds: vec![],
+                salt,
             },
         };
+        let pull_msg = pull_funds(proto_amount, maker, &env.contract.address, &pull_to)?;
+
+        let mut event: Event = EscrowEvent {
+            kind: EscrowEventKind::OrderFilled,
+            order_hash: escrow_playload_msg.order_hash,
+            hashlock: escrow_playload_msg.hashlock,
+            maker: escrow_playload_msg.maker,
+            taker: escrow_playload_msg.taker,
+            amount: escrow_playload_msg.tokens,
+            stage: ORDER_FILLED_STAGE.to_string(),
+            secret: None,
+        }
+        .into();
+        // resolvers deploy the destination escrow from these
+        if let Some(extension) = &order.extension {
+            event = event.add_attributes([
+                ("dst_chain_id", extension.dst_chain_id.clone()),
+                ("dst_asset", extension.dst_asset.clone()),
+                ("receiver", extension.receiver.clone()),
+                ("dst_safety_deposit", extension.dst_safety_deposit.to_string()),
+            ]);
+        }
+
+        Ok(Response::new()
+            .add_message(pull_msg)
+            .add_message(lock_msg)
+            .add_event(event.add_attribute("escrow", escrow)))
+    }
+
+    /// Atomic batches are filled in place, so any failing order reverts the
+    /// whole transaction. Otherwise every order is filled through a call back
+    /// into the protocol, and a failing fill only reverts itself.
+    pub fn fill_orders(
+        mut deps: DepsMut,
+        env: Env,
+        _info: MessageInfo,
+        orders: Vec<FillOrderMsg>,
+        atomic: bool,
+    ) -> Result<Response, ContractError> {
+        if PAUSED.load(deps.storage)? {
+            return Err(ContractError::Paused);
+        }
+        if orders.is_empty() {
+            return Err(ContractError::EmptyBatch);
+        }
+
+        let mut res = Response::new()
+            .add_attribute("action", "fill_orders")
+            .add_attribute("atomic", atomic.to_string());
+        let mut pulled = Pulled::new();
+        for (index, msg) in orders.into_iter().enumerate() {
+            let order_hash = msg.immutables.order_hash.clone();
+            if atomic {
+                let fill = fill_order_after(deps.branch(), env.clone(), msg, &mut pulled)?;
+                res = res
+                    .add_submessages(fill.messages)
+                    .add_events(fill.events)
+                    .add_event(fill_result_event(index, order_hash, Ok(())));
+            } else {
+                let fill = WasmMsg::Execute {
+                    contract_addr: env.contract.address.to_string(),
+                    msg: to_json_binary(&ExecuteMsg::FillOrder(msg))?,
+                    funds: vec![],
+                };
+                let payload = to_json_binary(&(index, order_hash))?;
+                res = res.add_submessage(
+                    SubMsg::reply_always(fill, FILL_ORDER_REPLY).with_payload(payload),
+                );
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn update_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: OwnershipAction,
+    ) -> Result<Response, ContractError> {
+        let ownership = cosmic_common::ownership::update_ownership(
+            deps.storage,
+            deps.api,
+            &env.block,
+            &info.sender,
+            action,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_ownership")
+            .add_attribute("owner", display_addr(ownership.owner))
+            .add_attribute("pending_owner", display_addr(ownership.pending_owner)))
+    }
+
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrow_src_code: Option<EscrowCode>,
+        escrow_dst_code_id: Option<u64>,
+        migratable_escrows: Option<bool>,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        if let Some(code) = escrow_src_code {
+            state.escrow_src_code_id = verify_escrow_code(&deps.querier, &code)?;
+        }
+        if let Some(escrow_dst_code_id) = escrow_dst_code_id {
+            state.escrow_dst_code_id = escrow_dst_code_id;
+        }
+        if let Some(migratable_escrows) = migratable_escrows {
+            state.migratable_escrows = migratable_escrows;
+        }
+        STATE.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("escrow_src_code_id", state.escrow_src_code_id.to_string())
+            .add_attribute("escrow_dst_code_id", state.escrow_dst_code_id.to_string())
+            .add_attribute("migratable_escrows", state.migratable_escrows.to_string()))
+    }
+
+    /// The vault holds the escrow under the same order hash and rules as a spawned `escrow_src`
+    fn vault_create_msg(escrow: &EscrowInstantiateMsg) -> VaultExecuteMsg {
+        VaultExecuteMsg::Create(VaultCreateMsg {
+            side: VaultSide::Src,
+            rescue_delay: escrow.rescue_delay,
+            order_hash: escrow.order_hash.clone(),
+            hashlock: escrow.hashlock.clone(),
+            maker: escrow.maker.clone(),
+            taker: escrow.taker.clone(),
+            tokens: escrow.tokens.clone(),
+            timelocks: escrow.timelocks.clone(),
+            hash_algorithm: escrow.hash_algorithm,
+        })
+    }
+
+    /// Escrows already spawned or held by a previous vault are unaffected
+    pub fn set_escrow_vault(
+        deps: DepsMut,
+        info: MessageInfo,
+        vault: Option<String>,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+
+        let mut state = STATE.load(deps.storage)?;
+        state.escrow_vault = vault.map(|vault| deps.api.addr_validate(&vault)).transpose()?;
+        STATE.save(deps.storage, &state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_escrow_vault")
+            .add_attribute("escrow_vault", display_addr(state.escrow_vault)))
+    }
+
+    /// Only escrows spawned with the protocol as admin can be migrated, the
+    /// chain rejects the message for everything else.
+    pub fn migrate_escrow(
+        deps: DepsMut,
+        info: MessageInfo,
+        escrow: String,
+        code_id: u64,
+        msg: Binary,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+        let escrow = deps.api.addr_validate(&escrow)?;
+
+        let migrate_msg = WasmMsg::Migrate {
+            contract_addr: escrow.to_string(),
+            new_code_id: code_id,
+            msg,
+        };
 
-        let pull_sub_msg = pull_funds(proto_amount, msg.immutables.maker, env.contract.address);
+        Ok(Response::new()
+            .add_message(migrate_msg)
+            .add_attribute("action", "migrate_escrow")
+            .add_attribute("escrow", escrow)
+            .add_attribute("code_id", code_id.to_string()))
+    }
 
-        COMPLETED_ORDERS.save(deps.storage, msg.immutables.order_hash.clone(), &true)?;
+    /// Pausing only stops new orders from being filled; escrows that already
+    /// exist are separate contracts and can always be withdrawn or cancelled.
+    pub fn set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        assert_owner(deps.storage, &info.sender)?;
+        PAUSED.save(deps.storage, &paused)?;
 
         Ok(Response::new()
-            .add_submessage(pull_sub_msg.with_payload(to_json_binary(&escrow_playload_msg)?)))
+            .add_attribute("action", if paused { "pause" } else { "unpause" }))
+    }
+
+    fn display_addr(addr: Option<Addr>) -> String {
+        addr.map(String::from).unwrap_or_else(|| "none".to_string())
+    }
+
+    /// The order has to be filled on its source chain and name where the maker is paid
+    fn validate_extension(extension: &CrossChainOrder, env: &Env) -> Result<(), ContractError> {
+        let invalid = |reason: &str| ContractError::InvalidCrossChainOrder {
+            reason: reason.to_string(),
+        };
+        if extension.src_chain_id != env.block.chain_id {
+            return Err(invalid("source chain is not this chain"));
+        }
+        if extension.dst_chain_id.is_empty() || extension.dst_chain_id == extension.src_chain_id {
+            return Err(invalid("destination chain has to differ from the source chain"));
+        }
+        if extension.dst_asset.is_empty() {
+            return Err(invalid("destination asset is empty"));
+        }
+        if extension.receiver.is_empty() {
+            return Err(invalid("receiver is empty"));
+        }
+        Ok(())
     }
 
-    fn pull_funds(token: ProtoCoin, from_address: Addr, to_address: Addr) -> SubMsg {
+    /// Every leg has a distinct denom and a non-zero price
+    fn validate_basket(coins: &[Coin]) -> Result<(), ContractError> {
+        let invalid = |reason: String| ContractError::InvalidBasket { reason };
+
+        if let Some(coin) = coins.iter().find(|coin| coin.amount.is_zero()) {
+            return Err(invalid(format!("zero amount of {}", coin.denom)));
+        }
+        Coins::try_from(coins.to_vec()).map_err(|e| invalid(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// `MsgExec` moving `tokens` from the maker to `to_address` under the maker's grant
+    fn pull_funds(
+        tokens: Vec<ProtoCoin>,
+        from_address: &Addr,
+        grantee: &Addr,
+        to_address: &Addr,
+    ) -> StdResult<CosmosMsg> {
         let bank_send_msg = MsgSend {
-            amount: vec![token],
+            amount: tokens,
             from_address: from_address.to_string(),
             to_address: to_address.to_string(),
         };
 
         // let exec_msg
-        let order_bytes = encode_bytes_message(&bank_send_msg).unwrap();
+        let order_bytes = encode_bytes_message(&bank_send_msg)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
         let msg_exec = MsgExec {
-            grantee: to_address.to_string(),
+            grantee: grantee.to_string(),
             msgs: vec![Any {
                 type_url: MSG_BANK_SEND.to_string(), //"/cosmos.bank.v1beta1.MsgSend" ,
                 value: order_bytes,
             }],
         };
 
-        SubMsg::reply_always(
-            create_stargate_msg(MSG_EXEC, msg_exec.encode_to_vec()).unwrap(),
-            PULL_REPLY,
-        )
+        create_stargate_msg(MSG_EXEC, msg_exec.encode_to_vec())
     }
 
     fn calculate_price(
@@ -221,51 +630,1041 @@ pub mod execute {
     }
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    unimplemented!()
+/// Outcome of one order of a `FillOrders` batch
+fn fill_result_event(index: usize, order_hash: String, result: Result<(), String>) -> Event {
+    let event = Event::new("fill_order_result")
+        .add_attribute("index", index.to_string())
+        .add_attribute("order_hash", order_hash);
+    match result {
+        Ok(()) => event.add_attribute("result", "filled"),
+        Err(reason) => event
+            .add_attribute("result", "failed")
+            .add_attribute("error", reason),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
-        PULL_REPLY => {
-            if msg.result.is_err() {
-                return Err(ContractError::PullFundsError);
-            }
-
-            let escrow_init_msg: EscrowInstantiateMsg = from_json(&msg.payload)?;
-            let instantiate_child_msg = WasmMsg::Instantiate {
-                admin: None,
-                code_id: state.escrow_code_id,
-                msg: to_json_binary(&escrow_init_msg)?,
-                funds: vec![escrow_init_msg.token],
-                label: format!(
-                    "Escrow Contract for {}",
-                    escrow_init_msg.order_hash.as_str()
-                ),
-            };
-            let submsg = SubMsg {
-                payload: Binary::new(vec![]),
-                msg: instantiate_child_msg.into(),
-                gas_limit: None,
-                id: ESCROW_DEPLOY_REPLY, // assign an ID to catch the reply
-                reply_on: ReplyOn::Always,
+        FILL_ORDER_REPLY => {
+            let (index, order_hash): (usize, String) = from_json(&msg.payload)?;
+            let result = match msg.result {
+                SubMsgResult::Ok(_) => Ok(()),
+                SubMsgResult::Err(reason) => Err(reason),
             };
-            let event = Event::new("escrow_contract").add_attribute("order_hash", escrow_init_msg.order_hash);
-            Ok(Response::new().add_submessage(submsg).add_event(event))
+            Ok(Response::new().add_event(fill_result_event(index, order_hash, result)))
         }
+        _ => Ok(Response::new()),
+    }
+}
 
-        ESCROW_DEPLOY_REPLY => {
-            if msg.result.is_err() {
-                return Err(ContractError::EscrowContractError);
-            }
-            Ok(Response::new())
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::Ownership {} => to_json_binary(&get_ownership(deps.storage)?),
+        QueryMsg::Order { order_hash } => to_json_binary(&query::order(deps, order_hash)?),
+        QueryMsg::OrderHash { order } => to_json_binary(&query::order_hash(&env, &order)?),
+        QueryMsg::DomainSeparator {} => to_json_binary(&query::domain_separator(&env)?),
+        QueryMsg::IsHashlockUsed { hashlock } => {
+            to_json_binary(&query::is_hashlock_used(deps, hashlock)?)
         }
-        _ => Ok(Response::new()),
     }
 }
 
+pub mod query {
+    use super::*;
+    use crate::helpers::Domain;
+    use crate::msg::{
+        DomainSeparatorResponse, FillOrderMsg, IsHashlockUsedResponse, OrderHashResponse,
+    };
+    use cosmwasm_std::HexBinary;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let state = STATE.load(deps.storage)?;
+        Ok(ConfigResponse {
+            escrow_src_code_id: state.escrow_src_code_id,
+            escrow_dst_code_id: state.escrow_dst_code_id,
+            migratable_escrows: state.migratable_escrows,
+            escrow_vault: state.escrow_vault,
+            paused: PAUSED.load(deps.storage)?,
+        })
+    }
+
+    pub fn order(deps: Deps, order_hash: String) -> StdResult<Option<OrderRecord>> {
+        ORDERS.may_load(deps.storage, order_hash)
+    }
+
+    pub fn order_hash(env: &Env, order: &FillOrderMsg) -> StdResult<OrderHashResponse> {
+        Ok(OrderHashResponse {
+            order_hash: crate::helpers::order_hash(&Domain::new(env), order)?,
+        })
+    }
+
+    pub fn is_hashlock_used(deps: Deps, hashlock: String) -> StdResult<IsHashlockUsedResponse> {
+        Ok(IsHashlockUsedResponse {
+            used: USED_HASHLOCKS.has(deps.storage, hashlock.to_ascii_lowercase()),
+        })
+    }
+
+    pub fn domain_separator(env: &Env) -> StdResult<DomainSeparatorResponse> {
+        let domain = Domain::new(env);
+        Ok(DomainSeparatorResponse {
+            domain_separator: HexBinary::from(domain.separator()?).to_hex(),
+            name: domain.name.to_string(),
+            version: domain.version.to_string(),
+            chain_id: domain.chain_id.to_string(),
+            verifying_contract: domain.verifying_contract.clone(),
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    // v0.2.0 replaced the `completed_orders` flags with order records and
+    // introduced ownership and pausing
+    if from_version < Version::new(0, 2, 0) {
+        let migrated_orders = migrate_completed_orders(deps.storage)?;
+        if !OWNERSHIP.exists(deps.storage) {
+            let owner = msg
+                .owner
+                .map(|owner| deps.api.addr_validate(&owner))
+                .transpose()?;
+            OWNERSHIP.save(
+                deps.storage,
+                &crate::state::Ownership {
+                    owner,
+                    pending_owner: None,
+                    pending_expiry: None,
+                },
+            )?;
+        }
+        if !PAUSED.exists(deps.storage) {
+            PAUSED.save(deps.storage, &false)?;
+        }
+        res = res.add_attribute("migrated_orders", migrated_orders.to_string());
+    }
+
+    // v0.3.0 split the escrow code into the source and destination escrows,
+    // the legacy `escrow_code_id` is read back as the source one
+    if from_version < Version::new(0, 3, 0) {
+        if let Some(escrow_dst_code_id) = msg.escrow_dst_code_id {
+            let mut state = STATE.load(deps.storage)?;
+            state.escrow_dst_code_id = escrow_dst_code_id;
+            STATE.save(deps.storage, &state)?;
+        }
+    }
+
+    Ok(res)
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use cosmic_common::ownership::OwnershipError;
+    use crate::msg::{
+        AuctionParameters, BasketLeg, CrossChainOrder, EscrowInstantiateMsg, FillOrderMsg, GasCost,
+        Immutables, OwnershipAction, TakerTraits, Timelocks, VaultExecuteMsg, VaultSide,
+    };
+    use crate::helpers::{GENERIC_AUTHORIZATION, QUERY_GRANTS, SEND_AUTHORIZATION};
+    use cosmwasm_std::testing::{
+        message_info, mock_env, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        from_json, Addr, Checksum, CodeInfoResponse, Coin, ContractResult, CosmosMsg, Empty,
+        GrpcQuery, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult,
+        Uint128, WasmQuery,
+    };
+    use injective_std::shim::Any;
+    use injective_std::types::cosmos::authz::v1beta1::{
+        GenericAuthorization, Grant, MsgExec, QueryGrantsResponse,
+    };
+    use injective_std::types::cosmos::bank::v1beta1::{MsgSend, SendAuthorization};
+    use injective_std::types::cosmos::base::v1beta1::Coin as ProtoCoin;
+    use prost::Message;
+
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    /// Answers the authz `Query/Grants` gRPC query with fixed grants
+    struct GrantQuerier {
+        base: MockQuerier,
+        grants: Vec<Grant>,
+    }
+
+    impl Querier for GrantQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_json::<QueryRequest<Empty>>(bin_request) {
+                Ok(QueryRequest::Grpc(GrpcQuery { path, .. })) if path == QUERY_GRANTS => {
+                    let response = QueryGrantsResponse {
+                        grants: self.grants.clone(),
+                        pagination: None,
+                    };
+                    SystemResult::Ok(ContractResult::Ok(response.encode_to_vec().into()))
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    /// Checksum of the code stored under `code_id` in the mocked chain
+    fn checksum(code_id: u64) -> Checksum {
+        Checksum::generate(&code_id.to_be_bytes())
+    }
+
+    fn escrow_code(code_id: u64) -> EscrowCode {
+        EscrowCode {
+            code_id,
+            checksum: checksum(code_id),
+        }
+    }
+
+    /// Answers `CodeInfo` for any code id
+    fn code_querier() -> MockQuerier {
+        let mut querier = MockQuerier::default();
+        querier.update_wasm(|query| match query {
+            WasmQuery::CodeInfo { code_id } => {
+                let info = CodeInfoResponse::new(*code_id, addr("deployer"), checksum(*code_id));
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        querier
+    }
+
+    fn mock_deps() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: code_querier(),
+            custom_query_type: std::marker::PhantomData,
+        }
+    }
+
+    /// Dependencies where the maker granted the protocol a send authorization
+    fn deps_with_grant(spend_limit: &[Coin]) -> OwnedDeps<MockStorage, MockApi, GrantQuerier> {
+        let authorization = SendAuthorization {
+            spend_limit: spend_limit
+                .iter()
+                .map(|coin| ProtoCoin {
+                    denom: coin.denom.clone(),
+                    amount: coin.amount.to_string(),
+                })
+                .collect(),
+            // escrows are spawned per order, so the grant can't name them upfront
+            allow_list: vec![],
+        };
+        deps_with_authorization(Any {
+            type_url: SEND_AUTHORIZATION.to_string(),
+            value: authorization.encode_to_vec(),
+        })
+    }
+
+    /// Dependencies where the maker granted the protocol `authorization`
+    fn deps_with_authorization(authorization: Any) -> OwnedDeps<MockStorage, MockApi, GrantQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: GrantQuerier {
+                base: code_querier(),
+                grants: vec![Grant {
+                    authorization: Some(authorization),
+                    expiration: None,
+                }],
+            },
+            custom_query_type: std::marker::PhantomData,
+        }
+    }
+
+    /// The escrow's instantiate message and salt, and the bank send pulling the maker's funds
+    fn fill_messages(res: &Response) -> (EscrowInstantiateMsg, Binary, MsgSend) {
+        let (escrow_msg, salt) = match &res.messages[1].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate2 {
+                msg, funds, salt, ..
+            }) => {
+                assert!(funds.is_empty());
+                (from_json(msg).unwrap(), salt.clone())
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        let send = match &res.messages[0].msg {
+            CosmosMsg::Any(any) => {
+                assert_eq!(any.type_url, execute::MSG_EXEC);
+                let exec = MsgExec::decode(any.value.as_slice()).unwrap();
+                assert_eq!(exec.grantee, mock_env().contract.address.to_string());
+                MsgSend::decode(exec.msgs[0].value.as_slice()).unwrap()
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        (escrow_msg, salt, send)
+    }
+
+    fn setup(deps: DepsMut) {
+        instantiate(
+            deps,
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            InstantiateMsg {
+                escrow_src_code: escrow_code(1),
+                escrow_dst_code_id: 2,
+                owner: None,
+            },
+        )
+        .unwrap();
+    }
+
+    /// The order with its hash under the protocol's domain in `env`
+    fn hashed(env: &Env, mut msg: FillOrderMsg) -> FillOrderMsg {
+        msg.immutables.order_hash = query::order_hash(env, &msg).unwrap().order_hash;
+        msg
+    }
+
+    /// Orders differing in their hashlock only
+    fn order_with_hashlock(env: &Env, hashlock: &str) -> FillOrderMsg {
+        let mut msg = fill_order_msg(env);
+        msg.immutables.hashlock = hashlock.to_string();
+        hashed(env, msg)
+    }
+
+    fn fill_order_msg(env: &Env) -> FillOrderMsg {
+        let msg = FillOrderMsg {
+            making_amount: Coin::new(1000u32, "stake"),
+            taking_amount: Coin::new(900u32, "uusdc"),
+            basket: vec![],
+            auction_params: AuctionParameters {
+                duration: 100,
+                start_time: env.block.time.seconds() - 10,
+                initial_rate_bump: 0,
+                points: vec![],
+                gas_cost: GasCost {
+                    gas_bump_estimate: 0,
+                    gas_price_estimate: 0,
+                },
+            },
+            taker_traits: TakerTraits {
+                threshold_taking_price: Uint128::zero(),
+            },
+            immutables: Immutables {
+                rescue_delay: 1,
+                order_hash: String::new(),
+                hashlock: "bb".to_string(),
+                maker: addr("maker"),
+                taker: addr("taker"),
+                timelocks: Timelocks {
+                    dest_withdrawal: 10,
+                    dest_public_withdrawal: 20,
+                    dest_cancellation: 30,
+                    src_cancellation: 40,
+                    src_withdrawal: 10,
+                    src_public_withdrawal: 20,
+                    src_public_cancellation: 50,
+                },
+                hash_algorithm: Default::default(),
+            },
+            extension: None,
+        };
+        hashed(env, msg)
+    }
+
+    fn cross_chain_order(env: &Env) -> CrossChainOrder {
+        CrossChainOrder {
+            src_chain_id: env.block.chain_id.clone(),
+            dst_chain_id: "1".to_string(),
+            dst_asset: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+            receiver: "0x00000000000000000000000000000000000000aa".to_string(),
+            dst_safety_deposit: Uint128::new(5),
+        }
+    }
+
+    #[test]
+    fn only_owner_can_pause() {
+        let mut deps = mock_deps();
+        setup(deps.as_mut());
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("anyone"), &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotOwner)));
+    }
+
+    #[test]
+    fn pause_blocks_fill_order() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake"), Coin::new(100u32, "uatom")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("owner"), &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(fill_order_msg(&env)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Paused));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("owner"), &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(fill_order_msg(&env)),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn fill_order_requires_authz_grant() {
+        let env = mock_env();
+        for spend_limit in [vec![], vec![Coin::new(999u32, "stake")]] {
+            let mut deps = deps_with_grant(&spend_limit);
+            setup(deps.as_mut());
+
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("taker"), &[]),
+                ExecuteMsg::FillOrder(fill_order_msg(&env)),
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, ContractError::MissingAuthzGrant { maker } if maker == addr("maker").as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn generic_grants_cover_bank_sends_only() {
+        let env = mock_env();
+        for (msg, granted) in [
+            (execute::MSG_BANK_SEND, true),
+            ("/cosmos.staking.v1beta1.MsgDelegate", false),
+        ] {
+            let mut deps = deps_with_authorization(Any {
+                type_url: GENERIC_AUTHORIZATION.to_string(),
+                value: GenericAuthorization {
+                    msg: msg.to_string(),
+                }
+                .encode_to_vec(),
+            });
+            setup(deps.as_mut());
+
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("taker"), &[]),
+                ExecuteMsg::FillOrder(fill_order_msg(&env)),
+            );
+            assert_eq!(res.is_ok(), granted, "generic grant for {msg}");
+        }
+    }
+
+    #[test]
+    fn atomic_batches_spend_the_grant_once() {
+        // enough for one order of 1000stake, not two
+        let mut deps = deps_with_grant(&[Coin::new(1500u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let fill = |orders| ExecuteMsg::FillOrders {
+            orders,
+            atomic: true,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            fill(vec![order_with_hashlock(&env, "aa"), order_with_hashlock(&env, "bb")]),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::MissingAuthzGrant { maker } if maker == addr("maker").as_str())
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            fill(vec![order_with_hashlock(&env, "cc")]),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn fill_order_deploys_escrow_and_pulls_into_it() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake"), Coin::new(100u32, "uatom")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let order_hash = fill_order_msg(&env).immutables.order_hash;
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(fill_order_msg(&env)),
+        )
+        .unwrap();
+        let (escrow_msg, salt, send) = fill_messages(&res);
+        assert_eq!(salt, Binary::from(order_hash.as_bytes()));
+        assert_eq!(escrow_msg.tokens, vec![Coin::new(1000u32, "stake")]);
+
+        // the funds land in the address instantiate2 assigns to the escrow
+        let escrow = crate::helpers::escrow_address(
+            &deps.as_ref().querier,
+            &deps.api,
+            &env.contract.address,
+            1,
+            &salt,
+        )
+        .unwrap();
+        assert_eq!(send.from_address, addr("maker").to_string());
+        assert_eq!(send.to_address, escrow.to_string());
+        assert_eq!(send.amount[0].amount, "1000");
+
+        let order = query::order(deps.as_ref(), order_hash.clone()).unwrap().unwrap();
+        assert_eq!(order.escrow, Some(escrow));
+
+        let events = cosmic_common::events::parse_escrow_events(&res.events).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EscrowEventKind::OrderFilled);
+        assert_eq!(events[0].order_hash, order_hash);
+        assert_eq!(events[0].maker, addr("maker"));
+        assert_eq!(events[0].stage, ORDER_FILLED_STAGE);
+    }
+
+    #[test]
+    fn order_hashes_are_bound_to_the_chain_and_contract() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let msg = fill_order_msg(&env);
+
+        let mut other_chain = env.clone();
+        other_chain.block.chain_id = "other-chain".to_string();
+        let mut other_contract = env.clone();
+        other_contract.contract.address = addr("other-protocol");
+        for other in [&other_chain, &other_contract] {
+            let other_hash = query::order_hash(other, &msg).unwrap().order_hash;
+            assert_ne!(other_hash, msg.immutables.order_hash);
+            assert_ne!(
+                query::domain_separator(other).unwrap().domain_separator,
+                query::domain_separator(&env).unwrap().domain_separator
+            );
+        }
+        let domain = query::domain_separator(&env).unwrap();
+        assert_eq!(domain.chain_id, env.block.chain_id);
+        assert_eq!(domain.verifying_contract, env.contract.address);
+
+        // an order hashed for another chain, or a made up hash, is rejected
+        for actual in [hashed(&other_chain, msg.clone()).immutables.order_hash, "aa".to_string()] {
+            let mut mismatched = msg.clone();
+            mismatched.immutables.order_hash = actual.clone();
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&addr("taker"), &[]),
+                ExecuteMsg::FillOrder(mismatched),
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::InvalidOrderHash { expected, actual: got }
+                    if expected == msg.immutables.order_hash && got == actual
+            ));
+        }
+
+        // so does an order changed after it was hashed
+        let mut tampered = msg.clone();
+        tampered.making_amount = Coin::new(1u32, "stake");
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(tampered),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOrderHash { .. }));
+
+        execute(
+            deps.as_mut(),
+            env,
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(msg),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn repeated_hashlock_is_rejected() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let used = |deps: Deps, hashlock: &str| {
+            query::is_hashlock_used(deps, hashlock.to_string()).unwrap().used
+        };
+        assert!(!used(deps.as_ref(), "bb"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(fill_order_msg(&env)),
+        )
+        .unwrap();
+        assert!(used(deps.as_ref(), "bb"));
+        assert!(used(deps.as_ref(), "BB"));
+
+        // a different order locked with the same secret
+        let mut msg = fill_order_msg(&env);
+        msg.immutables.hashlock = "BB".to_string();
+        msg.making_amount = Coin::new(500u32, "stake");
+        let msg = hashed(&env, msg);
+        let order_hash = msg.immutables.order_hash.clone();
+        let err = execute(
+            deps.as_mut(),
+            env,
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(msg),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HashlockAlreadyUsed { hashlock } if hashlock == "BB"));
+        assert!(query::order(deps.as_ref(), order_hash).unwrap().is_none());
+    }
+
+    #[test]
+    fn cross_chain_order_is_recorded_and_hashed() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let taker = message_info(&addr("taker"), &[]);
+
+        // only fillable on its source chain
+        let mut msg = fill_order_msg(&env);
+        msg.extension = Some(CrossChainOrder {
+            src_chain_id: "other-chain".to_string(),
+            ..cross_chain_order(&env)
+        });
+        let msg = hashed(&env, msg);
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), ExecuteMsg::FillOrder(msg))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCrossChainOrder { .. }));
+
+        let mut msg = fill_order_msg(&env);
+        msg.extension = Some(cross_chain_order(&env));
+        let msg = hashed(&env, msg);
+        let res = execute(deps.as_mut(), env.clone(), taker, ExecuteMsg::FillOrder(msg.clone()))
+            .unwrap();
+        let event = res.events.iter().find(|e| e.ty == "order_filled").unwrap();
+        let attr = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|a| a.key == key)
+                .map(|a| a.value.clone())
+        };
+        assert_eq!(attr("dst_chain_id").as_deref(), Some("1"));
+        assert_eq!(attr("receiver"), Some(cross_chain_order(&env).receiver));
+        assert_eq!(attr("dst_safety_deposit").as_deref(), Some("5"));
+
+        let hash = msg.immutables.order_hash.clone();
+        let order = query::order(deps.as_ref(), hash.clone()).unwrap().unwrap();
+        assert_eq!(order.extension, Some(cross_chain_order(&env)));
+
+        // the extension is part of the order hash
+        assert_eq!(hash.len(), 64);
+        let mut redirected = msg.clone();
+        redirected.extension.as_mut().unwrap().receiver = "0xbb".to_string();
+        assert_ne!(query::order_hash(&env, &redirected).unwrap().order_hash, hash);
+        // the taker's side isn't
+        let mut other_taker = msg;
+        other_taker.immutables.taker = addr("other");
+        assert_eq!(query::order_hash(&env, &other_taker).unwrap().order_hash, hash);
+    }
+
+    #[test]
+    fn basket_order_locks_every_leg() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake"), Coin::new(100u32, "uatom")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+
+        let mut msg = fill_order_msg(&env);
+        msg.basket = vec![BasketLeg {
+            making_amount: Coin::new(50u32, "uatom"),
+            taking_amount: Coin::new(40u32, "uosmo"),
+        }];
+        let msg = hashed(&env, msg);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(msg),
+        )
+        .unwrap();
+        let (escrow_msg, _, send) = fill_messages(&res);
+        assert_eq!(
+            escrow_msg.tokens,
+            vec![Coin::new(1000u32, "stake"), Coin::new(50u32, "uatom")]
+        );
+        let pulled: Vec<_> = send
+            .amount
+            .iter()
+            .map(|coin| (coin.denom.as_str(), coin.amount.as_str()))
+            .collect();
+        assert_eq!(pulled, vec![("stake", "1000"), ("uatom", "50")]);
+
+        // two legs locking the same denom are rejected
+        let mut msg = order_with_hashlock(&env, "cc");
+        msg.basket = vec![BasketLeg {
+            making_amount: Coin::new(50u32, "stake"),
+            taking_amount: Coin::new(40u32, "uosmo"),
+        }];
+        let msg = hashed(&env, msg);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(msg),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBasket { .. }));
+    }
+
+    #[test]
+    fn fill_order_locks_funds_in_the_vault() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let set_vault = |vault: Option<&str>| ExecuteMsg::SetEscrowVault {
+            vault: vault.map(String::from),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("anyone"), &[]),
+            set_vault(Some(addr("vault").as_str())),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NotOwner)));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("owner"), &[]),
+            set_vault(Some(addr("vault").as_str())),
+        )
+        .unwrap();
+        assert_eq!(query::config(deps.as_ref()).unwrap().escrow_vault, Some(addr("vault")));
+        let order_hash = fill_order_msg(&env).immutables.order_hash;
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(fill_order_msg(&env)),
+        )
+        .unwrap();
+
+        // the maker's funds pass through the protocol into `Create`
+        let send = match &res.messages[0].msg {
+            CosmosMsg::Any(any) => {
+                let exec = MsgExec::decode(any.value.as_slice()).unwrap();
+                MsgSend::decode(exec.msgs[0].value.as_slice()).unwrap()
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        assert_eq!(send.to_address, env.contract.address.to_string());
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) = &res.messages[1].msg
+        else {
+            panic!("unexpected message {:?}", res.messages[1].msg);
+        };
+        assert_eq!(contract_addr, addr("vault").as_str());
+        assert_eq!(funds, &vec![Coin::new(1000u32, "stake")]);
+        let VaultExecuteMsg::Create(create) = from_json(msg).unwrap();
+        assert_eq!(create.side, VaultSide::Src);
+        assert_eq!((&create.order_hash, create.tokens), (&order_hash, funds.clone()));
+
+        let order = query::order(deps.as_ref(), order_hash).unwrap().unwrap();
+        assert_eq!(order.escrow, Some(addr("vault")));
+
+        // switching back spawns escrows again
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("owner"), &[]),
+            set_vault(None),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(order_with_hashlock(&env, "cc")),
+        )
+        .unwrap();
+        fill_messages(&res);
+    }
+
+    #[test]
+    fn atomic_batch_fails_as_a_whole() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let order = |hashlock: &str| order_with_hashlock(&env, hashlock);
+        let fill = |orders| ExecuteMsg::FillOrders {
+            orders,
+            atomic: true,
+        };
+
+        // the second "cc" is already filled by the first one. The mocked
+        // storage isn't rolled back, so the batch uses its own orders.
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            fill(vec![order("cc"), order("dd"), order("cc")]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OrderAlreadyProcessed));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            fill(vec![order("aa"), order("bb")]),
+        )
+        .unwrap();
+        // a pull and an escrow per order
+        assert_eq!(res.messages.len(), 4);
+        let results: Vec<_> = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "fill_order_result")
+            .map(|event| event.attributes[1].value.as_str())
+            .collect();
+        let second = order("bb").immutables.order_hash;
+        assert_eq!(results, vec![order("aa").immutables.order_hash, second.clone()]);
+        assert!(ORDERS.has(deps.as_ref().storage, second));
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            message_info(&addr("taker"), &[]),
+            fill(vec![]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EmptyBatch));
+    }
+
+    #[test]
+    fn independent_fills_report_their_outcome() {
+        let mut deps = deps_with_grant(&[Coin::new(10_000u32, "stake")]);
+        setup(deps.as_mut());
+        let env = mock_env();
+        let first = fill_order_msg(&env);
+        let second = order_with_hashlock(&env, "cc");
+        let second_hash = second.immutables.order_hash.clone();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrders {
+                orders: vec![first.clone(), second],
+                atomic: false,
+            },
+        )
+        .unwrap();
+        // nothing is filled yet, every order is a call back into the protocol
+        assert!(!ORDERS.has(deps.as_ref().storage, first.immutables.order_hash));
+        assert_eq!(res.messages.len(), 2);
+        let submsg = &res.messages[1];
+        assert_eq!(submsg.id, FILL_ORDER_REPLY);
+        match &submsg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                assert_eq!(contract_addr, env.contract.address.as_str());
+                let ExecuteMsg::FillOrder(fill) = from_json(msg).unwrap() else {
+                    panic!("unexpected message");
+                };
+                assert_eq!(fill.immutables.order_hash, second_hash);
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        #[allow(deprecated)]
+        let failed = Reply {
+            id: submsg.id,
+            payload: submsg.payload.clone(),
+            gas_used: 0,
+            result: SubMsgResult::Err("Order already processed".to_string()),
+        };
+        let res = reply(deps.as_mut(), env, failed).unwrap();
+        let attributes: Vec<_> = res.events[0]
+            .attributes
+            .iter()
+            .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                ("index", "1"),
+                ("order_hash", second_hash.as_str()),
+                ("result", "failed"),
+                ("error", "Order already processed")
+            ]
+        );
+    }
+
+    #[test]
+    fn renounced_contract_has_no_admin() {
+        let mut deps = mock_deps();
+        setup(deps.as_mut());
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            ExecuteMsg::UpdateOwnership(OwnershipAction::RenounceOwnership),
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            ExecuteMsg::UpdateConfig {
+                escrow_src_code: Some(escrow_code(3)),
+                escrow_dst_code_id: None,
+                migratable_escrows: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Ownership(OwnershipError::NoOwner)));
+    }
+
+    #[test]
+    fn migrate_legacy_instance() {
+        let mut deps = mock_deps();
+        STATE
+            .save(
+                deps.as_mut().storage,
+                &State {
+                    escrow_src_code_id: 1,
+                    escrow_dst_code_id: 0,
+                    migratable_escrows: false,
+                    escrow_vault: None,
+                },
+            )
+            .unwrap();
+        let env = mock_env();
+        let msg = fill_order_msg(&env);
+        let order_hash = msg.immutables.order_hash.clone();
+        crate::migrations::save_legacy_completed_order(deps.as_mut().storage, &order_hash, true)
+            .unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                owner: None,
+                escrow_dst_code_id: Some(2),
+            },
+        )
+        .unwrap();
+
+        let order = query::order(deps.as_ref(), order_hash).unwrap();
+        assert_eq!(order, Some(OrderRecord::default()));
+        let config = query::config(deps.as_ref()).unwrap();
+        assert!(!config.paused);
+        assert_eq!(config.escrow_src_code_id, 1);
+        assert_eq!(config.escrow_dst_code_id, 2);
+
+        // a second fill of a migrated order is still rejected
+        let err = execute(
+            deps.as_mut(),
+            env,
+            message_info(&addr("taker"), &[]),
+            ExecuteMsg::FillOrder(msg),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OrderAlreadyProcessed));
+    }
+
+    #[test]
+    fn escrow_codes_must_match_their_checksum() {
+        let mut deps = mock_deps();
+        let wrong = EscrowCode {
+            code_id: 1,
+            checksum: checksum(2),
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            InstantiateMsg {
+                escrow_src_code: wrong.clone(),
+                escrow_dst_code_id: 2,
+                owner: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ChecksumMismatch { code_id: 1, .. }));
+
+        setup(deps.as_mut());
+        let update = |src, dst| ExecuteMsg::UpdateConfig {
+            escrow_src_code: src,
+            escrow_dst_code_id: dst,
+            migratable_escrows: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            update(Some(wrong), Some(4)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ChecksumMismatch { code_id: 1, .. }));
+        // nothing was applied
+        assert_eq!(query::config(deps.as_ref()).unwrap().escrow_dst_code_id, 2);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr("owner"), &[]),
+            update(Some(escrow_code(3)), Some(4)),
+        )
+        .unwrap();
+        let config = query::config(deps.as_ref()).unwrap();
+        assert_eq!(config.escrow_src_code_id, 3);
+        assert_eq!(config.escrow_dst_code_id, 4);
+    }
+}
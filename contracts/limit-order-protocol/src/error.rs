@@ -1,3 +1,4 @@
+use cosmic_common::ownership::OwnershipError;
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
@@ -18,16 +19,39 @@ pub enum ContractError {
     #[error("Price is above takers threshold")]
     PriceIsAboveThreshold,
 
-    #[error("Unable to pull funds from maker")]
-    PullFundsError,
+    #[error("Order already processed")]
+    OrderAlreadyProcessed,
 
-    #[error("Unable to create escrow contract")]
-    EscrowContractError,
+    #[error("Hashlock {hashlock} is already used by another order")]
+    HashlockAlreadyUsed { hashlock: String },
 
-    #[error("Order already processed")]
-    OrderAlreadyProcessed
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("Contract is paused")]
+    Paused,
+
+    #[error("Batch has no items")]
+    EmptyBatch,
+
+    #[error("Invalid token basket: {reason}")]
+    InvalidBasket { reason: String },
+
+    #[error("Order hash {actual} doesn't match the order, expected {expected}")]
+    InvalidOrderHash { expected: String, actual: String },
+
+    #[error("Invalid cross-chain order: {reason}")]
+    InvalidCrossChainOrder { reason: String },
 
+    #[error("Maker {maker} has not granted a send authorization covering the order")]
+    MissingAuthzGrant { maker: String },
 
+    #[error("Code {code_id} has checksum {actual}, expected {expected}")]
+    ChecksumMismatch {
+        code_id: u64,
+        expected: String,
+        actual: String,
+    },
 
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
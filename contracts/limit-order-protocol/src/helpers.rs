@@ -1,10 +1,31 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_json_binary, Addr, AnyMsg, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{
+    instantiate2_address, to_json_binary, to_json_vec, Addr, AnyMsg, Api, Coin, CosmosMsg, Env,
+    HexBinary, QuerierWrapper, StdError, StdResult, Timestamp, Uint128, WasmMsg,
+};
+use injective_std::types::cosmos::{
+    authz::v1beta1::{GenericAuthorization, QueryGrantsRequest, QueryGrantsResponse},
+    bank::v1beta1::SendAuthorization,
+};
 
-use crate::msg::ExecuteMsg;
+use crate::contract::execute::MSG_BANK_SEND;
+use crate::msg::{
+    AuctionParameters, BasketLeg, CrossChainOrder, ExecuteMsg, FillOrderMsg, HashAlgorithm,
+    Timelocks,
+};
 use prost::Message;
+use sha3::{Digest, Keccak256};
+
+pub const QUERY_GRANTS: &str = "/cosmos.authz.v1beta1.Query/Grants";
+pub const SEND_AUTHORIZATION: &str = "/cosmos.bank.v1beta1.SendAuthorization";
+pub const GENERIC_AUTHORIZATION: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+
+/// Name and version of the order hash domain, bump the version when the
+/// hashed fields change
+pub const DOMAIN_NAME: &str = "limit-order-protocol";
+pub const DOMAIN_VERSION: &str = "1";
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -33,6 +54,149 @@ pub(crate) fn encode_bytes_message<T: Message>(msg: &T) -> Result<Vec<u8>, prost
     Ok(buffer)
 }
 
+/// Fields of an order set by its maker, the taker's side of the fill is left out
+#[derive(Serialize)]
+struct OrderHashInput<'a> {
+    maker: &'a Addr,
+    making_amount: &'a Coin,
+    taking_amount: &'a Coin,
+    basket: &'a [BasketLeg],
+    auction_params: &'a AuctionParameters,
+    hashlock: &'a str,
+    timelocks: &'a Timelocks,
+    rescue_delay: u64,
+    hash_algorithm: HashAlgorithm,
+    extension: Option<&'a CrossChainOrder>,
+}
+
+/// Binds order hashes to one chain and protocol deployment, like an EIP-712
+/// domain. No signature is checked against it, the hash only has to be
+/// consistent with the order's fields and the deployment filling it.
+#[derive(Serialize)]
+pub struct Domain<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub chain_id: &'a str,
+    pub verifying_contract: &'a Addr,
+}
+
+impl<'a> Domain<'a> {
+    pub fn new(env: &'a Env) -> Self {
+        Domain {
+            name: DOMAIN_NAME,
+            version: DOMAIN_VERSION,
+            chain_id: &env.block.chain_id,
+            verifying_contract: &env.contract.address,
+        }
+    }
+
+    /// keccak256 of the JSON encoded domain
+    pub fn separator(&self) -> StdResult<[u8; 32]> {
+        Ok(Keccak256::digest(to_json_vec(self)?).into())
+    }
+}
+
+/// Hex encoded keccak256 of `0x1901 || domain separator || keccak256(order)`,
+/// where the order is the JSON encoding of its maker fields
+pub fn order_hash(domain: &Domain, order: &FillOrderMsg) -> StdResult<String> {
+    let input = OrderHashInput {
+        maker: &order.immutables.maker,
+        making_amount: &order.making_amount,
+        taking_amount: &order.taking_amount,
+        basket: &order.basket,
+        auction_params: &order.auction_params,
+        hashlock: &order.immutables.hashlock,
+        timelocks: &order.immutables.timelocks,
+        rescue_delay: order.immutables.rescue_delay,
+        hash_algorithm: order.immutables.hash_algorithm,
+        extension: order.extension.as_ref(),
+    };
+    let digest = Keccak256::new()
+        .chain_update(b"\x19\x01")
+        .chain_update(domain.separator()?)
+        .chain_update(Keccak256::digest(to_json_vec(&input)?))
+        .finalize();
+    Ok(HexBinary::from(digest.as_slice()).to_hex())
+}
+
+/// Address `instantiate2` assigns to a contract of `code_id` spawned by `creator` with `salt`
+pub fn escrow_address(
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    creator: &Addr,
+    code_id: u64,
+    salt: &[u8],
+) -> StdResult<Addr> {
+    let checksum = querier.query_wasm_code_info(code_id)?.checksum;
+    let creator = api.addr_canonicalize(creator.as_str())?;
+    let address = instantiate2_address(checksum.as_slice(), &creator, salt)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    api.addr_humanize(&address)
+}
+
+/// Whether `granter` has an unexpired grant letting `grantee` send `amount` to
+/// `recipient`: a bank `SendAuthorization` covering it, or an unlimited
+/// `GenericAuthorization` for `MsgSend`
+pub fn has_send_grant(
+    querier: &QuerierWrapper,
+    granter: &Addr,
+    grantee: &Addr,
+    recipient: &Addr,
+    amount: &[Coin],
+    now: Timestamp,
+) -> StdResult<bool> {
+    let request = QueryGrantsRequest {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        msg_type_url: MSG_BANK_SEND.to_string(),
+        pagination: None,
+    };
+    let response = querier.query_grpc(QUERY_GRANTS.to_string(), request.encode_to_vec().into())?;
+    let response = QueryGrantsResponse::decode(response.as_slice())
+        .map_err(|e| StdError::parse_err("QueryGrantsResponse", e))?;
+
+    for grant in response.grants {
+        if grant
+            .expiration
+            .is_some_and(|expiration| expiration.seconds <= now.seconds() as i64)
+        {
+            continue;
+        }
+        let Some(authorization) = grant.authorization else {
+            continue;
+        };
+        if authorization.type_url == GENERIC_AUTHORIZATION {
+            let generic = GenericAuthorization::decode(authorization.value.as_slice())
+                .map_err(|e| StdError::parse_err("GenericAuthorization", e))?;
+            if generic.msg == MSG_BANK_SEND {
+                return Ok(true);
+            }
+            continue;
+        }
+        if authorization.type_url != SEND_AUTHORIZATION {
+            continue;
+        }
+        let send = SendAuthorization::decode(authorization.value.as_slice())
+            .map_err(|e| StdError::parse_err("SendAuthorization", e))?;
+        if !send.allow_list.is_empty() && !send.allow_list.iter().any(|a| a == recipient.as_str()) {
+            continue;
+        }
+
+        let covered = amount.iter().all(|coin| {
+            send.spend_limit.iter().any(|limit| {
+                limit.denom == coin.denom
+                    && limit.amount.parse::<Uint128>().is_ok_and(|limit| limit >= coin.amount)
+            })
+        });
+        if covered {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
 pub fn create_stargate_msg(type_url: &str, value: Vec<u8>) -> StdResult<CosmosMsg> {
     Ok(CosmosMsg::Any(AnyMsg {
         type_url: type_url.to_string(),
@@ -0,0 +1,171 @@
+use cosmwasm_std::{coins, Addr, Coin, Empty, Uint128};
+use cw_multi_test::{Contract, ContractWrapper, Executor};
+use escrow_src::authz::grant_send_any_msg;
+use escrow_src::multitest::{cosmic_app, escrow_src_contract, CosmicApp};
+use escrow_src::query::CosmicQueryWrapper;
+
+use crate::msg::{
+    AuctionParameters, EscrowCode, ExecuteMsg, FillOrderMsg, GasCost, Immutables, InstantiateMsg,
+    OrderHashResponse, QueryMsg, TakerTraits, Timelocks,
+};
+use crate::state::OrderRecord;
+
+const MAKER: &str = "maker";
+const TAKER: &str = "taker";
+const DENOM: &str = "stake";
+
+fn lop_contract() -> Box<dyn Contract<Empty, CosmicQueryWrapper>> {
+    Box::new(
+        ContractWrapper::new_with_empty(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        ),
+    )
+}
+
+fn escrow_code(app: &CosmicApp, code_id: u64) -> EscrowCode {
+    EscrowCode {
+        code_id,
+        checksum: app.wrap().query_wasm_code_info(code_id).unwrap().checksum,
+    }
+}
+
+/// App with a funded maker, the escrow codes stored and the LOP instantiated
+fn setup() -> (CosmicApp, Addr) {
+    let mut app = cosmic_app();
+    let maker = app.api().addr_make(MAKER);
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &maker, coins(1000, DENOM))
+            .unwrap();
+    });
+
+    let owner = app.api().addr_make("owner");
+    let escrow_src_code_id = app.store_code(escrow_src_contract());
+    let lop_code_id = app.store_code(lop_contract());
+    let lop = app
+        .instantiate_contract(
+            lop_code_id,
+            owner,
+            &InstantiateMsg {
+                escrow_src_code: escrow_code(&app, escrow_src_code_id),
+                escrow_dst_code_id: 0,
+                owner: None,
+            },
+            &[],
+            "limit-order-protocol",
+            None,
+        )
+        .unwrap();
+
+    (app, lop)
+}
+
+/// The order with the hash the protocol computes for it
+fn signed(app: &CosmicApp, lop: &Addr, mut msg: FillOrderMsg) -> FillOrderMsg {
+    let res: OrderHashResponse = app
+        .wrap()
+        .query_wasm_smart(lop, &QueryMsg::OrderHash { order: msg.clone() })
+        .unwrap();
+    msg.immutables.order_hash = res.order_hash;
+    msg
+}
+
+fn fill_order_msg(app: &CosmicApp, lop: &Addr) -> FillOrderMsg {
+    let msg = FillOrderMsg {
+        making_amount: Coin::new(1000u32, DENOM),
+        taking_amount: Coin::new(900u32, "uusdc"),
+        basket: vec![],
+        auction_params: AuctionParameters {
+            duration: 100,
+            start_time: app.block_info().time.seconds() - 10,
+            initial_rate_bump: 0,
+            points: vec![],
+            gas_cost: GasCost {
+                gas_bump_estimate: 0,
+                gas_price_estimate: 0,
+            },
+        },
+        taker_traits: TakerTraits {
+            threshold_taking_price: Uint128::zero(),
+        },
+        immutables: Immutables {
+            rescue_delay: 1,
+            order_hash: String::new(),
+            hashlock: "bb".to_string(),
+            maker: app.api().addr_make(MAKER),
+            taker: app.api().addr_make(TAKER),
+            timelocks: Timelocks {
+                dest_withdrawal: 10,
+                dest_public_withdrawal: 20,
+                dest_cancellation: 30,
+                src_cancellation: 40,
+                src_withdrawal: 10,
+                src_public_withdrawal: 20,
+                src_public_cancellation: 50,
+            },
+            hash_algorithm: Default::default(),
+        },
+        extension: None,
+    };
+    signed(app, lop, msg)
+}
+
+fn balance(app: &CosmicApp, addr: &Addr) -> Uint128 {
+    app.wrap().query_balance(addr, DENOM).unwrap().amount
+}
+
+#[test]
+fn fill_order_pulls_maker_funds_into_escrow() {
+    let (mut app, lop) = setup();
+    let maker = app.api().addr_make(MAKER);
+    let taker = app.api().addr_make(TAKER);
+
+    let grant = grant_send_any_msg(&maker, &lop, &coins(1000, DENOM), None).unwrap();
+    app.execute(maker.clone(), grant).unwrap();
+
+    let order = fill_order_msg(&app, &lop);
+    let order_hash = order.immutables.order_hash.clone();
+    app.execute_contract(taker, lop.clone(), &ExecuteMsg::FillOrder(order), &[])
+        .unwrap();
+
+    let order: Option<OrderRecord> = app
+        .wrap()
+        .query_wasm_smart(&lop, &QueryMsg::Order { order_hash })
+        .unwrap();
+    let escrow = order.and_then(|order| order.escrow).unwrap();
+
+    // the maker's funds go straight into the freshly deployed escrow
+    let details: escrow_src::msg::GetOrderDetailsResponse = app
+        .wrap()
+        .query_wasm_smart(&escrow, &escrow_src::msg::QueryMsg::OrderDetails {})
+        .unwrap();
+    assert_eq!(details.maker, maker);
+    assert_eq!(balance(&app, &maker), Uint128::zero());
+    assert_eq!(balance(&app, &lop), Uint128::zero());
+    assert_eq!(balance(&app, &escrow), Uint128::new(1000));
+}
+
+#[test]
+fn fill_order_fails_once_grant_is_spent() {
+    let (mut app, lop) = setup();
+    let maker = app.api().addr_make(MAKER);
+    let taker = app.api().addr_make(TAKER);
+
+    let grant = grant_send_any_msg(&maker, &lop, &coins(1000, DENOM), None).unwrap();
+    app.execute(maker.clone(), grant).unwrap();
+
+    let msg = ExecuteMsg::FillOrder(fill_order_msg(&app, &lop));
+    app.execute_contract(taker.clone(), lop.clone(), &msg, &[])
+        .unwrap();
+
+    // a second order from the same maker finds no grant left
+    let mut second = fill_order_msg(&app, &lop);
+    second.immutables.hashlock = "cc".to_string();
+    let second = signed(&app, &lop, second);
+    app.execute_contract(taker, lop, &ExecuteMsg::FillOrder(second), &[])
+        .unwrap_err();
+    assert_eq!(balance(&app, &maker), Uint128::zero());
+}
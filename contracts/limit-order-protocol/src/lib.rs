@@ -1,6 +1,9 @@
 pub mod contract;
 mod error;
 pub mod helpers;
+#[cfg(test)]
+mod integration_tests;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
@@ -1,30 +1,160 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_std::{Addr, Binary, Checksum, Coin, Uint128};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
+use crate::state::{OrderRecord, Ownership};
+
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub escrow_code_id : u64
+    /// `escrow_src` code spawned for every filled order
+    pub escrow_src_code: EscrowCode,
+    /// `escrow_dst` code the takers deploy on the destination chain, it isn't
+    /// stored here so its checksum can't be checked
+    pub escrow_dst_code_id: u64,
+    /// defaults to the instantiator
+    pub owner: Option<String>,
+}
+
+/// An escrow code id together with the checksum it is expected to have, the
+/// protocol refuses codes whose stored checksum differs
+#[cw_serde]
+pub struct EscrowCode {
+    pub code_id: u64,
+    pub checksum: Checksum,
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     FillOrder(FillOrderMsg),
+    /// Fill several orders in one transaction. An atomic batch fails as a
+    /// whole, otherwise every fill succeeds or fails on its own and reports
+    /// its outcome in a `fill_order_result` event.
+    FillOrders {
+        orders: Vec<FillOrderMsg>,
+        #[serde(default)]
+        atomic: bool,
+    },
+    UpdateOwnership(OwnershipAction),
+    UpdateConfig {
+        escrow_src_code: Option<EscrowCode>,
+        escrow_dst_code_id: Option<u64>,
+        migratable_escrows: Option<bool>,
+    },
+    /// Lock new fills in an `escrow-vault` instead of spawning `escrow_src`
+    /// contracts, `None` switches back to spawning
+    SetEscrowVault { vault: Option<String> },
+    Pause {},
+    Unpause {},
+    /// Upgrade an escrow that was spawned while `migratable_escrows` was set
+    MigrateEscrow {
+        escrow: String,
+        code_id: u64,
+        msg: Binary,
+    },
 }
 
+pub use cosmic_common::hashlock::HashAlgorithm;
+pub use cosmic_common::ownership::OwnershipAction;
+pub use cosmic_common::timelocks::Timelocks;
+
 #[cw_serde]
 #[derive(QueryResponses)]
-pub enum QueryMsg {}
+#[allow(clippy::large_enum_variant)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(Ownership)]
+    Ownership {},
+    #[returns(Option<OrderRecord>)]
+    Order { order_hash: String },
+    /// Hash of the order's maker-defined fields, cross-chain extension included,
+    /// under this deployment's domain. Fills have to carry it as their `order_hash`.
+    #[returns(OrderHashResponse)]
+    OrderHash { order: FillOrderMsg },
+    /// Domain every order hash is bound to, the chain and the protocol contract
+    #[returns(DomainSeparatorResponse)]
+    DomainSeparator {},
+    /// Whether a filled order already uses `hashlock`, filling another one
+    /// with it fails
+    #[returns(IsHashlockUsedResponse)]
+    IsHashlockUsed { hashlock: String },
+}
+
+#[cw_serde]
+pub struct IsHashlockUsedResponse {
+    pub used: bool,
+}
+
+#[cw_serde]
+pub struct OrderHashResponse {
+    pub order_hash: String,
+}
+
+#[cw_serde]
+pub struct DomainSeparatorResponse {
+    /// hex encoded keccak256 of the JSON encoded domain
+    pub domain_separator: String,
+    pub name: String,
+    pub version: String,
+    pub chain_id: String,
+    pub verifying_contract: Addr,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub escrow_src_code_id: u64,
+    pub escrow_dst_code_id: u64,
+    pub migratable_escrows: bool,
+    pub escrow_vault: Option<Addr>,
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// owner to set when migrating an instance that predates ownership
+    pub owner: Option<String>,
+    /// `escrow_dst` code to record when migrating from before v0.3.0, which
+    /// only knew the source escrow code
+    #[serde(default)]
+    pub escrow_dst_code_id: Option<u64>,
+}
 
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct FillOrderMsg {
     pub making_amount: Coin,
     pub taking_amount:Coin,
+    /// further legs of a basket order, priced along the same auction curve
+    #[serde(default)]
+    pub basket: Vec<BasketLeg>,
     pub auction_params: AuctionParameters,
     pub taker_traits: TakerTraits,
     pub immutables: Immutables,
+    /// where and what the maker is paid on the destination chain
+    #[serde(default)]
+    pub extension: Option<CrossChainOrder>,
+}
+
+/// Destination side of a cross-chain order, `escrow_dst` pays `receiver`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct CrossChainOrder {
+    /// has to be the chain the order is filled on
+    pub src_chain_id: String,
+    pub dst_chain_id: String,
+    /// EVM token address or Cosmos denom
+    pub dst_asset: String,
+    /// maker's payee, in the destination chain's address format
+    pub receiver: String,
+    /// deposited by the taker along with the `escrow_dst` funds
+    pub dst_safety_deposit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct BasketLeg {
+    pub making_amount: Coin,
+    pub taking_amount: Coin,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
@@ -61,26 +191,46 @@ pub struct Immutables {
     pub maker: Addr,
     pub taker: Addr,
     pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
-pub struct Timelocks {
-    pub dest_withdrawal: u64,
-    pub dest_public_withdrawal: u64,
-    pub dest_cancellation: u64,
-    pub src_cancellation: u64,
-    pub src_withdrawal:u64,
-    pub src_public_withdrawal:u64,
-    pub src_public_cancellation : u64
+#[cw_serde]
+pub struct EscrowInstantiateMsg {
+    pub rescue_delay: u64,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: Addr,
+    pub taker: Addr,
+    pub tokens: Vec<Coin>,
+    pub timelocks: Timelocks,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
 }
 
+/// Subset of the escrow vault's `ExecuteMsg` the protocol calls into
 #[cw_serde]
-pub struct EscrowInstantiateMsg {
+pub enum VaultExecuteMsg {
+    Create(VaultCreateMsg),
+}
+
+/// `escrow_src` instantiate message plus the vault's side of the swap
+#[cw_serde]
+pub struct VaultCreateMsg {
+    pub side: VaultSide,
     pub rescue_delay: u64,
     pub order_hash: String,
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: Coin,
+    pub tokens: Vec<Coin>,
     pub timelocks: Timelocks,
+    pub hash_algorithm: HashAlgorithm,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum VaultSide {
+    Src,
+    Dst,
 }
@@ -1,11 +1,49 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Empty};
 use cw_storage_plus::{Item, Map};
- 
-pub const COMPLETED_ORDERS: Map<String, bool> = Map::new("completed_orders");
+
+use crate::msg::CrossChainOrder;
 
 #[cw_serde]
 pub struct State {
-    pub escrow_code_id: u64,
+    /// code of the `escrow_src` spawned on every fill
+    #[serde(alias = "escrow_code_id")]
+    pub escrow_src_code_id: u64,
+    /// code of the matching `escrow_dst`, unset (0) for instances migrated
+    /// from v0.2.0 without one
+    #[serde(default)]
+    pub escrow_dst_code_id: u64,
+    /// spawn escrows with the protocol as their admin, so they can be upgraded
+    /// through `MigrateEscrow`
+    #[serde(default)]
+    pub migratable_escrows: bool,
+    /// lock fills in this `escrow-vault` instead of spawning an escrow per order
+    #[serde(default)]
+    pub escrow_vault: Option<Addr>,
+}
+
+/// Bookkeeping for a filled order, fields are unknown for orders migrated from v0.1.0
+#[cw_serde]
+#[derive(Default)]
+pub struct OrderRecord {
+    pub maker: Option<Addr>,
+    pub taker: Option<Addr>,
+    /// unix timestamp (in sec) of the fill
+    pub filled_at: Option<u64>,
+    pub escrow: Option<Addr>,
+    /// destination side of a cross-chain order
+    #[serde(default)]
+    pub extension: Option<CrossChainOrder>,
 }
 
+pub const ORDERS: Map<String, OrderRecord> = Map::new("orders");
+
+/// lowercased hashlocks of every filled order, a revealed secret would unlock
+/// all escrows sharing its hashlock
+pub const USED_HASHLOCKS: Map<String, Empty> = Map::new("used_hashlocks");
+
 pub const STATE: Item<State> = Item::new("state");
+
+pub use cosmic_common::ownership::{Ownership, OWNERSHIP};
+
+pub const PAUSED: Item<bool> = Item::new("paused");
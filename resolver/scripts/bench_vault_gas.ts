@@ -0,0 +1,145 @@
+import { DirectSecp256k1HdWallet } from "@cosmjs/proto-signing";
+import { SigningCosmWasmClient } from "@cosmjs/cosmwasm-stargate";
+import { randomBytes } from "crypto";
+
+// Simulates locking the same escrow once by spawning a contract per order and
+// once through the escrow vault, for both the LOP fill (source side) and the
+// factory deploy (destination side), and prints the gas each path uses and
+// what the vault saves.
+//
+//   XION_LOP_ADDRESS            LOP spawning `escrow_src` contracts
+//   XION_VAULT_LOP_ADDRESS      LOP configured with `set_escrow_vault`
+//   BENCH_MAKER                 maker of the benched orders, must have granted both LOPs a bank send
+//   XION_DEST_FACTORY_ADDRESS   factory spawning `escrow_dst` contracts
+//   XION_VAULT_FACTORY_ADDRESS  factory configured with `set_escrow_vault`
+//   XION_ESCROW_VAULT_ADDRESS   `escrow-vault` instance listing the relayer among its `creators` (optional)
+//   BENCH_DENOM                 denom to lock, defaults to uxion
+//
+// A path without its contract address is skipped.
+
+const DENOM = Bun.env.BENCH_DENOM || "uxion";
+const ROUNDS = 5;
+
+type Path = {
+    name: string,
+    contract: string | undefined,
+    // the execute message and the funds attached to it
+    msg: (client: SigningCosmWasmClient, contract: string, relayer: string) => Promise<[object, any[]]>,
+};
+
+function timelocks() {
+    return {
+        dest_withdrawal: 10,
+        dest_public_withdrawal: 120,
+        dest_cancellation: 600,
+        src_withdrawal: 10,
+        src_public_withdrawal: 120,
+        src_cancellation: 600,
+        src_public_cancellation: 1200,
+    };
+}
+
+function escrowParams(taker: string) {
+    return {
+        rescue_delay: 3600,
+        order_hash: randomBytes(32).toString("hex"),
+        hashlock: randomBytes(32).toString("hex"),
+        maker: taker,
+        taker,
+        tokens: [{ amount: "10", denom: DENOM }],
+        timelocks: timelocks(),
+    };
+}
+
+// the LOP only fills orders carrying the hash it computes for its chain and address
+async function fillOrderMsg(client: SigningCosmWasmClient, lop: string, taker: string): Promise<[object, any[]]> {
+    const order = {
+        auction_params: {
+            duration: 3600,
+            start_time: Math.floor(Date.now() / 1000) - 20,
+            initial_rate_bump: 0,
+            points: [],
+            gas_cost: { gas_bump_estimate: 0, gas_price_estimate: 0 },
+        },
+        taker_traits: { threshold_taking_price: "0" },
+        immutables: {
+            rescue_delay: 3600,
+            order_hash: "",
+            hashlock: randomBytes(32).toString("hex"),
+            maker: Bun.env.BENCH_MAKER || "",
+            taker,
+            timelocks: timelocks(),
+        },
+        making_amount: { amount: "10", denom: DENOM },
+        taking_amount: { amount: "9", denom: DENOM },
+    };
+    const { order_hash } = await client.queryContractSmart(lop, { order_hash: { order } });
+    order.immutables.order_hash = order_hash;
+    return [{ fill_order: order }, []];
+}
+
+async function deployEscrowMsg(_client: SigningCosmWasmClient, _factory: string, relayer: string): Promise<[object, any[]]> {
+    const params = escrowParams(relayer);
+    return [{ deploy_escrow: params }, params.tokens];
+}
+
+async function vaultCreateMsg(_client: SigningCosmWasmClient, _vault: string, relayer: string): Promise<[object, any[]]> {
+    const params = escrowParams(relayer);
+    return [{ create: { side: "dst", ...params } }, params.tokens];
+}
+
+async function simulate(client: SigningCosmWasmClient, sender: string, contract: string, msg: object, funds: any[]) {
+    return client.simulate(sender, [{
+        typeUrl: "/cosmwasm.wasm.v1.MsgExecuteContract",
+        value: { sender, contract, msg: new TextEncoder().encode(JSON.stringify(msg)), funds },
+    }], "");
+}
+
+// average gas of the path over `ROUNDS` fresh orders, undefined when skipped
+async function bench(client: SigningCosmWasmClient, relayer: string, path: Path) {
+    if (!path.contract) {
+        console.log(`${path.name}: skipped, no contract address`);
+        return undefined;
+    }
+    const gas: number[] = [];
+    for (let i = 0; i < ROUNDS; i++) {
+        const [msg, funds] = await path.msg(client, path.contract, relayer);
+        gas.push(await simulate(client, relayer, path.contract, msg, funds));
+    }
+    const average = Math.round(gas.reduce((a, b) => a + b, 0) / gas.length);
+    console.log(`${path.name}: ${average} gas on average (${gas.join(", ")})`);
+    return average;
+}
+
+async function main() {
+    const wallet = await DirectSecp256k1HdWallet.fromMnemonic(Bun.env.XION_RELAYER || "", { prefix: "xion" });
+    const [{ address }] = await wallet.getAccounts();
+    const client = await SigningCosmWasmClient.connectWithSigner(Bun.env.RPC_URL || "", wallet);
+
+    // each pair is the spawning path and its vault counterpart
+    const pairs: [Path, Path][] = [
+        [
+            { name: "lop fill, spawned escrow", contract: Bun.env.XION_LOP_ADDRESS, msg: fillOrderMsg },
+            { name: "lop fill, vault mode", contract: Bun.env.XION_VAULT_LOP_ADDRESS, msg: fillOrderMsg },
+        ],
+        [
+            { name: "factory deploy, spawned escrow", contract: Bun.env.XION_DEST_FACTORY_ADDRESS, msg: deployEscrowMsg },
+            { name: "factory deploy, vault mode", contract: Bun.env.XION_VAULT_FACTORY_ADDRESS, msg: deployEscrowMsg },
+        ],
+    ];
+
+    for (const [spawned, vault] of pairs) {
+        const spawnedGas = await bench(client, address, spawned);
+        const vaultGas = await bench(client, address, vault);
+        if (spawnedGas !== undefined && vaultGas !== undefined) {
+            const saved = spawnedGas - vaultGas;
+            const percent = ((saved / spawnedGas) * 100).toFixed(1);
+            console.log(`  vault saves ${saved} gas per escrow (${percent}%)`);
+        }
+    }
+
+    // the vault on its own, without the factory's bookkeeping
+    await bench(client, address, { name: "vault create", contract: Bun.env.XION_ESCROW_VAULT_ADDRESS, msg: vaultCreateMsg });
+}
+
+main().catch(err => console.log(err));
@@ -29,6 +29,10 @@ export async function codeGen() {
             {
                 name: "escrow_src",
                 dir: "../contracts/escrow_src/schema"
+            },
+            {
+                name: "escrow_vault",
+                dir: "../contracts/escrow-vault/schema"
             }
         ]
     })
@@ -54,12 +54,12 @@ export class CosmosManager {
                 "src_public_withdrawal": 6000,
                 "src_withdrawal": 7000
             },
-            "token": { "amount": "10", "denom": "stake" },
+            "tokens": [{ "amount": "10", "denom": "stake" }],
             }, "auto", "", [{ "amount": "10", "denom": "stake" }]);
         }
         */
 
-        const result = await contractInstance.deployEscrow(deployParams, "auto", "", [deployParams.token])
+        const result = await contractInstance.deployEscrow(deployParams, "auto", "", deployParams.tokens)
         let escrowAddr = ""
         for (let e of result.events) {
             if (e.type == "instantiate") {
@@ -74,13 +74,28 @@ export class CosmosManager {
 
     async withdrawMaker(escrowAddr: string, secret: string) {
         const contractInstance = new contracts.EscrowDst.EscrowDstClient(this.wasmSigner, this.address, escrowAddr || "")
-        const result = await contractInstance.withdraw({ secret })
+        // secrets are raw bytes, passed base64 encoded
+        const result = await contractInstance.withdraw({ secret: Buffer.from(secret).toString("base64") })
         console.log({ result });
     }
 
 
     async deploySrcEscrow(order:any) {
-        const contractInstance = new contracts.LimitOrderProtocol.LimitOrderProtocolClient(this.wasmSigner, this.address, Bun.env.XION_LOP_ADDRESS || "")
+        const lopAddress = Bun.env.XION_LOP_ADDRESS || ""
+        const contractInstance = new contracts.LimitOrderProtocol.LimitOrderProtocolClient(this.wasmSigner, this.address, lopAddress)
+        // the LOP only fills orders carrying the hash it computes for its chain and address
+        const { order_hash } = await this.wasmSigner.queryContractSmart(lopAddress, {
+            order_hash: {
+                order: {
+                    auction_params: order.auctionParams,
+                    immutables: order.immutables,
+                    making_amount: order.makingAmount,
+                    taker_traits: order.takerTraits,
+                    taking_amount: order.takingAmount
+                }
+            }
+        })
+        order.immutables.order_hash = order_hash
         const result =await contractInstance.fillOrder(order , "auto" )
         const escrowAddr = this.fetchEscrowAddress(result.events)
         console.log(`📝 SRC Escrow contract at XION ${escrowAddr}`);
@@ -101,7 +116,7 @@ export class CosmosManager {
 
     async withdrawTaker(escrowAddr: string, secret: string) {
         const contractInstance = new contracts.EscrowSrc.EscrowSrcClient(this.wasmSigner, this.address, escrowAddr || "")
-        const result = await contractInstance.withdraw({secret})
+        const result = await contractInstance.withdraw({ secret: Buffer.from(secret).toString("base64") })
     }
 
    
@@ -28,7 +28,7 @@ export async function deployEscrows() {
             },
             "immutables": {
                 rescue_delay: 60 * 30,
-                "order_hash": "81ddf033cf9a8444d55d7a7c7275185893ccbc9da334d1f403b27c0d038ccd39",
+                "order_hash": "", // set from the LOP's order_hash query before filling
                 "hashlock": "65462b0520ef7d3df61b9992ed3bea0c56ead753be7c8b3614e0ce01e4cac41b",
                 "maker": "xion10lvzwepm0044lyjv9xkgujmqk9853jxj05x4hp",
                 "taker": "xion1zs43u36fujvlxsw90swpr65tdr5wskam0kghma",
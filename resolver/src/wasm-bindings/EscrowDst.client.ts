@@ -6,9 +6,21 @@
 
 import { CosmWasmClient, SigningCosmWasmClient, ExecuteResult } from "@cosmjs/cosmwasm-stargate";
 import { StdFee } from "@cosmjs/amino";
-import { Addr, Uint128, InstantiateMsg, Timelocks, Coin, ExecuteMsg, WithdrawMsg, QueryMsg } from "./EscrowDst.types";
+import { Addr, Uint128, HashAlgorithm, InstantiateMsg, Timelocks, Coin, ExecuteMsg, WithdrawMsg, QueryMsg, Action, AllowedActionsResponse, BalanceResponse, ConfigResponse, Stage, CurrentStageResponse, GetOrderDetailsResponse, Binary, RevealedSecretResponse, EscrowStatus, StatusResponse, TimelocksResponse } from "./EscrowDst.types";
 export interface EscrowDstReadOnlyInterface {
   contractAddress: string;
+  orderDetails: () => Promise<GetOrderDetailsResponse>;
+  timelocks: () => Promise<TimelocksResponse>;
+  balance: () => Promise<BalanceResponse>;
+  status: () => Promise<StatusResponse>;
+  config: () => Promise<ConfigResponse>;
+  revealedSecret: () => Promise<RevealedSecretResponse>;
+  currentStage: () => Promise<CurrentStageResponse>;
+  allowedActions: ({
+    address
+  }: {
+    address: string;
+  }) => Promise<AllowedActionsResponse>;
 }
 export class EscrowDstQueryClient implements EscrowDstReadOnlyInterface {
   client: CosmWasmClient;
@@ -16,7 +28,61 @@ export class EscrowDstQueryClient implements EscrowDstReadOnlyInterface {
   constructor(client: CosmWasmClient, contractAddress: string) {
     this.client = client;
     this.contractAddress = contractAddress;
+    this.orderDetails = this.orderDetails.bind(this);
+    this.timelocks = this.timelocks.bind(this);
+    this.balance = this.balance.bind(this);
+    this.status = this.status.bind(this);
+    this.config = this.config.bind(this);
+    this.revealedSecret = this.revealedSecret.bind(this);
+    this.currentStage = this.currentStage.bind(this);
+    this.allowedActions = this.allowedActions.bind(this);
   }
+  orderDetails = async (): Promise<GetOrderDetailsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      order_details: {}
+    });
+  };
+  timelocks = async (): Promise<TimelocksResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      timelocks: {}
+    });
+  };
+  balance = async (): Promise<BalanceResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      balance: {}
+    });
+  };
+  status = async (): Promise<StatusResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      status: {}
+    });
+  };
+  config = async (): Promise<ConfigResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      config: {}
+    });
+  };
+  revealedSecret = async (): Promise<RevealedSecretResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      revealed_secret: {}
+    });
+  };
+  currentStage = async (): Promise<CurrentStageResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      current_stage: {}
+    });
+  };
+  allowedActions = async ({
+    address
+  }: {
+    address: string;
+  }): Promise<AllowedActionsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      allowed_actions: {
+        address
+      }
+    });
+  };
 }
 export interface EscrowDstInterface extends EscrowDstReadOnlyInterface {
   contractAddress: string;
@@ -6,14 +6,19 @@
 
 export type Addr = string;
 export type Uint128 = string;
+export type HashAlgorithm = "keccak256" | "sha256";
 export interface InstantiateMsg {
+  factory?: Addr | null;
+  hash_algorithm?: HashAlgorithm;
   hashlock: string;
   maker: Addr;
   order_hash: string;
+  receiver?: string | null;
+  refund_surplus?: boolean;
   rescue_delay: number;
   taker: Addr;
   timelocks: Timelocks;
-  token: Coin;
+  tokens: Coin[];
 }
 export interface Timelocks {
   dest_cancellation: number;
@@ -36,4 +41,68 @@ export type ExecuteMsg = ("cancel" | "rescue_funds") | {
 export interface WithdrawMsg {
   secret: string;
 }
-export type QueryMsg = string;
\ No newline at end of file
+export type QueryMsg = {
+  order_details: {};
+} | {
+  timelocks: {};
+} | {
+  balance: {};
+} | {
+  status: {};
+} | {
+  config: {};
+} | {
+  revealed_secret: {};
+} | {
+  current_stage: {};
+} | {
+  allowed_actions: {
+    address: string;
+  };
+};
+export type Action = "withdraw" | "public_withdraw" | "cancel" | "public_cancel" | "rescue_funds";
+export interface AllowedActionsResponse {
+  actions: Action[];
+}
+export interface BalanceResponse {
+  balance: Coin[];
+}
+export interface ConfigResponse {
+  contract: string;
+  factory?: Addr | null;
+  hash_algorithm: HashAlgorithm;
+  rescue_delay: number;
+  version: string;
+}
+export type Stage = "finality_lock" | "private_withdrawal" | "public_withdrawal" | "cancellation" | "private_cancellation" | "public_cancellation";
+export interface CurrentStageResponse {
+  ends_at?: number | null;
+  seconds_until_next?: number | null;
+  stage: Stage;
+  starts_at: number;
+}
+export interface GetOrderDetailsResponse {
+  deployed_at: number;
+  funded_at?: number | null;
+  hash_algorithm: HashAlgorithm;
+  hashlock: string;
+  maker: Addr;
+  order_hash: string;
+  receiver?: Addr | null;
+  rescue_delay: number;
+  taker: Addr;
+  timelocks: Timelocks;
+  tokens: Coin[];
+}
+export type Binary = string;
+export interface RevealedSecretResponse {
+  secret?: Binary | null;
+}
+export type EscrowStatus = "unfunded" | "active" | "withdrawn" | "cancelled" | "rescued";
+export interface StatusResponse {
+  status: EscrowStatus;
+}
+export interface TimelocksResponse {
+  deployed_at: number;
+  timelocks: Timelocks;
+}
\ No newline at end of file
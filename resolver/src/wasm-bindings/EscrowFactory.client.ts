@@ -28,7 +28,7 @@ export interface EscrowFactoryInterface extends EscrowFactoryReadOnlyInterface {
     rescueDelay,
     taker,
     timelocks,
-    token
+    tokens
   }: {
     hashlock: string;
     maker: Addr;
@@ -36,7 +36,7 @@ export interface EscrowFactoryInterface extends EscrowFactoryReadOnlyInterface {
     rescueDelay: number;
     taker: Addr;
     timelocks: Timelocks;
-    token: Coin;
+    tokens: Coin[];
   }, fee_?: number | StdFee | "auto", memo_?: string, funds_?: Coin[]) => Promise<ExecuteResult>;
 }
 export class EscrowFactoryClient extends EscrowFactoryQueryClient implements EscrowFactoryInterface {
@@ -57,7 +57,7 @@ export class EscrowFactoryClient extends EscrowFactoryQueryClient implements Esc
     rescueDelay,
     taker,
     timelocks,
-    token
+    tokens
   }: {
     hashlock: string;
     maker: Addr;
@@ -65,7 +65,7 @@ export class EscrowFactoryClient extends EscrowFactoryQueryClient implements Esc
     rescueDelay: number;
     taker: Addr;
     timelocks: Timelocks;
-    token: Coin;
+    tokens: Coin[];
   }, fee_: number | StdFee | "auto" = "auto", memo_?: string, funds_?: Coin[]): Promise<ExecuteResult> => {
     return await this.client.execute(this.sender, this.contractAddress, {
       deploy_escrow: {
@@ -75,7 +75,7 @@ export class EscrowFactoryClient extends EscrowFactoryQueryClient implements Esc
         rescue_delay: rescueDelay,
         taker,
         timelocks,
-        token
+        tokens
       }
     }, fee_, memo_, funds_);
   };
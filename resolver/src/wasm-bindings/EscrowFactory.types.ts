@@ -19,7 +19,7 @@ export interface EscrowInstantiateMsg {
   rescue_delay: number;
   taker: Addr;
   timelocks: Timelocks;
-  token: Coin;
+  tokens: Coin[];
 }
 export interface Timelocks {
   dest_cancellation: number;
@@ -6,10 +6,21 @@
 
 import { CosmWasmClient, SigningCosmWasmClient, ExecuteResult } from "@cosmjs/cosmwasm-stargate";
 import { StdFee } from "@cosmjs/amino";
-import { Addr, Uint128, InstantiateMsg, Timelocks, Coin, ExecuteMsg, PullFundsMsg, WithdrawMsg, WithdrawToMsg, QueryMsg, GetOrderDetailsResponse } from "./EscrowSrc.types";
+import { Addr, Uint128, InstantiateMsg, Timelocks, Coin, ExecuteMsg, WithdrawMsg, WithdrawToMsg, QueryMsg, GetOrderDetailsResponse, Stage, CurrentStageResponse, Action, AllowedActionsResponse, TimelocksResponse, BalanceResponse, EscrowStatus, StatusResponse, ConfigResponse, Binary, RevealedSecretResponse } from "./EscrowSrc.types";
 export interface EscrowSrcReadOnlyInterface {
   contractAddress: string;
   orderDetails: () => Promise<GetOrderDetailsResponse>;
+  timelocks: () => Promise<TimelocksResponse>;
+  balance: () => Promise<BalanceResponse>;
+  status: () => Promise<StatusResponse>;
+  config: () => Promise<ConfigResponse>;
+  revealedSecret: () => Promise<RevealedSecretResponse>;
+  currentStage: () => Promise<CurrentStageResponse>;
+  allowedActions: ({
+    address
+  }: {
+    address: string;
+  }) => Promise<AllowedActionsResponse>;
 }
 export class EscrowSrcQueryClient implements EscrowSrcReadOnlyInterface {
   client: CosmWasmClient;
@@ -18,23 +29,65 @@ export class EscrowSrcQueryClient implements EscrowSrcReadOnlyInterface {
     this.client = client;
     this.contractAddress = contractAddress;
     this.orderDetails = this.orderDetails.bind(this);
+    this.timelocks = this.timelocks.bind(this);
+    this.balance = this.balance.bind(this);
+    this.status = this.status.bind(this);
+    this.config = this.config.bind(this);
+    this.revealedSecret = this.revealedSecret.bind(this);
+    this.currentStage = this.currentStage.bind(this);
+    this.allowedActions = this.allowedActions.bind(this);
   }
   orderDetails = async (): Promise<GetOrderDetailsResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       order_details: {}
     });
   };
+  timelocks = async (): Promise<TimelocksResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      timelocks: {}
+    });
+  };
+  balance = async (): Promise<BalanceResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      balance: {}
+    });
+  };
+  status = async (): Promise<StatusResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      status: {}
+    });
+  };
+  config = async (): Promise<ConfigResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      config: {}
+    });
+  };
+  revealedSecret = async (): Promise<RevealedSecretResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      revealed_secret: {}
+    });
+  };
+  currentStage = async (): Promise<CurrentStageResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      current_stage: {}
+    });
+  };
+  allowedActions = async ({
+    address
+  }: {
+    address: string;
+  }): Promise<AllowedActionsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      allowed_actions: {
+        address
+      }
+    });
+  };
 }
 export interface EscrowSrcInterface extends EscrowSrcReadOnlyInterface {
   contractAddress: string;
   sender: string;
-  pullFunds: ({
-    amount,
-    from
-  }: {
-    amount: Coin;
-    from: Addr;
-  }, fee_?: number | StdFee | "auto", memo_?: string, funds_?: Coin[]) => Promise<ExecuteResult>;
+  pullFunds: (fee_?: number | StdFee | "auto", memo_?: string, funds_?: Coin[]) => Promise<ExecuteResult>;
   withdraw: ({
     secret
   }: {
@@ -71,18 +124,9 @@ export class EscrowSrcClient extends EscrowSrcQueryClient implements EscrowSrcIn
     this.cancel = this.cancel.bind(this);
     this.publicCancel = this.publicCancel.bind(this);
   }
-  pullFunds = async ({
-    amount,
-    from
-  }: {
-    amount: Coin;
-    from: Addr;
-  }, fee_: number | StdFee | "auto" = "auto", memo_?: string, funds_?: Coin[]): Promise<ExecuteResult> => {
+  pullFunds = async (fee_: number | StdFee | "auto" = "auto", memo_?: string, funds_?: Coin[]): Promise<ExecuteResult> => {
     return await this.client.execute(this.sender, this.contractAddress, {
-      pull_funds: {
-        amount,
-        from
-      }
+      pull_funds: {}
     }, fee_, memo_, funds_);
   };
   withdraw = async ({
@@ -13,7 +13,7 @@ export interface InstantiateMsg {
   rescue_delay: number;
   taker: Addr;
   timelocks: Timelocks;
-  token: Coin;
+  tokens: Coin[];
 }
 export interface Timelocks {
   dest_cancellation: number;
@@ -29,7 +29,7 @@ export interface Coin {
   denom: string;
 }
 export type ExecuteMsg = {
-  pull_funds: PullFundsMsg;
+  pull_funds: [];
 } | {
   withdraw: WithdrawMsg;
 } | {
@@ -41,10 +41,6 @@ export type ExecuteMsg = {
 } | {
   public_cancel: [];
 };
-export interface PullFundsMsg {
-  amount: Coin;
-  from: Addr;
-}
 export interface WithdrawMsg {
   secret: string;
 }
@@ -54,14 +50,66 @@ export interface WithdrawToMsg {
 }
 export type QueryMsg = {
   order_details: {};
+} | {
+  timelocks: {};
+} | {
+  balance: {};
+} | {
+  status: {};
+} | {
+  config: {};
+} | {
+  revealed_secret: {};
+} | {
+  current_stage: {};
+} | {
+  allowed_actions: {
+    address: string;
+  };
 };
 export interface GetOrderDetailsResponse {
   deployed_at: number;
+  funded_at?: number | null;
+  hash_algorithm: HashAlgorithm;
   hashlock: string;
   maker: Addr;
   order_hash: string;
   rescue_delay: number;
   taker: Addr;
   timelocks: Timelocks;
-  token: Coin;
+  tokens: Coin[];
+}
+export type Stage = "finality_lock" | "private_withdrawal" | "public_withdrawal" | "cancellation" | "private_cancellation" | "public_cancellation";
+export interface CurrentStageResponse {
+  ends_at?: number | null;
+  seconds_until_next?: number | null;
+  stage: Stage;
+  starts_at: number;
+}
+export type Action = "withdraw" | "public_withdraw" | "cancel" | "public_cancel" | "rescue_funds";
+export interface AllowedActionsResponse {
+  actions: Action[];
+}
+export interface TimelocksResponse {
+  deployed_at: number;
+  timelocks: Timelocks;
+}
+export interface BalanceResponse {
+  balance: Coin[];
+}
+export type EscrowStatus = "unfunded" | "active" | "withdrawn" | "cancelled" | "rescued";
+export interface StatusResponse {
+  status: EscrowStatus;
+}
+export type HashAlgorithm = "keccak256" | "sha256";
+export interface ConfigResponse {
+  contract: string;
+  factory?: Addr | null;
+  hash_algorithm: HashAlgorithm;
+  rescue_delay: number;
+  version: string;
+}
+export type Binary = string;
+export interface RevealedSecretResponse {
+  secret?: Binary | null;
 }
\ No newline at end of file
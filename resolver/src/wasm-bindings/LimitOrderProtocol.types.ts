@@ -4,8 +4,15 @@
 * and run the @cosmwasm/ts-codegen generate command to regenerate this file.
 */
 
+export type Checksum = string;
 export interface InstantiateMsg {
-  escrow_code_id: number;
+  escrow_dst_code_id: number;
+  escrow_src_code: EscrowCode;
+  owner?: string | null;
+}
+export interface EscrowCode {
+  checksum: Checksum;
+  code_id: number;
 }
 export type ExecuteMsg = {
   fill_order: FillOrderMsg;